@@ -1,9 +1,11 @@
 pub extern crate rand;
 
 use super::field::z251::Z251;
-use super::field::FieldIdentity;
-use groth16::{Random, Identity, EllipticEncryptable};
+use super::field::zp::{Z4294967291, Z65521, Zp};
+use super::field::{Field, FieldIdentity};
+use groth16::{Random, Identity, EllipticEncryptable, ToFieldElements};
 use std::iter::Sum;
+use self::rand::{FromEntropy, Rng, StdRng};
 
 pub trait Encryptable {
     type Output;
@@ -12,21 +14,75 @@ pub trait Encryptable {
     fn random() -> Self;
 }
 
+/// A field whose elements are encrypted as powers of `GENERATOR`, which is
+/// how `Z251` is encrypted with 69. The same element is what
+/// `EllipticEncryptable` multiplies by for fields that are their own groups.
+pub trait EncryptionGenerator: Field {
+    const GENERATOR: Self;
+
+    /// `GENERATOR` to the power of `self`.
+    fn encrypt_with_generator(self) -> Self {
+        Self::GENERATOR.pow(self.to_canonical_u64())
+    }
+}
+
+impl EncryptionGenerator for Z251 {
+    const GENERATOR: Self = Z251 { inner: 69 };
+}
+
+impl EncryptionGenerator for Z65521 {
+    const GENERATOR: Self = Zp { inner: 17 };
+}
+
+impl EncryptionGenerator for Z4294967291 {
+    const GENERATOR: Self = Zp { inner: 2 };
+}
+
+impl<const P: u64> Encryptable for Zp<P>
+where
+    Zp<P>: EncryptionGenerator,
+{
+    type Output = Zp<P>;
+
+    fn encrypt(self) -> Self::Output {
+        self.encrypt_with_generator()
+    }
+    fn random() -> Self {
+        Zp {
+            inner: rand::random::<u64>() % P,
+        }
+    }
+}
+
 pub trait EncryptProperties {
     fn detect_root(&self) -> bool;
     fn valid(&self) -> bool;
 }
 
+/// `69^i mod 251` for every `u8` `i`, so that encrypting a `Z251` is a
+/// single lookup rather than `encrypt_with_generator`.
+const ENCRYPT_TABLE: [u8; 256] = encrypt_table();
+
+const fn encrypt_table() -> [u8; 256] {
+    let generator = <Z251 as EncryptionGenerator>::GENERATOR.inner as u16;
+    let mut table = [0; 256];
+    let mut power: u16 = 1;
+    let mut i = 0;
+    while i < 256 {
+        table[i] = power as u8;
+        power = power * generator % 251;
+        i += 1;
+    }
+    table
+}
+
 impl Encryptable for Z251 {
     type Output = Z251;
 
     fn encrypt(self) -> Self::Output {
-        let mut ret = Z251::one();
-        for _ in 0..self.inner {
-            ret = ret * Z251 { inner: 69 };
+        Z251 {
+            inner: ENCRYPT_TABLE[self.inner as usize],
         }
-
-        ret
     }
     fn random() -> Self {
         Z251 {
@@ -53,6 +109,105 @@ impl Random for Z251 {
         }
         r
     }
+    fn random_elem_from<R: Rng>(rng: &mut R) -> Self {
+        let mut r = Z251::zero();
+        while r == Z251::zero() {
+            r = Z251 {
+                inner: rng.gen::<u8>() % 251,
+            };
+        }
+        r
+    }
+}
+
+impl<const P: u64> Random for Zp<P> {
+    fn random_elem() -> Self {
+        Self::random_elem_from(&mut StdRng::from_entropy())
+    }
+}
+
+/// As for `Z251`, encrypting is multiplying by `GENERATOR`, which hides
+/// nothing and is only for testing larger circuits.
+impl EllipticEncryptable for Z65521 {
+    type G1 = Self;
+    type G2 = Self;
+    type GT = Self;
+
+    fn encrypt_g1(self) -> Self::G1 {
+        self * Self::GENERATOR
+    }
+    fn encrypt_g2(self) -> Self::G2 {
+        self * Self::GENERATOR
+    }
+    fn exp_encrypted_g1(self, g1: Self::G1) -> Self::G1 {
+        #[cfg(feature = "metrics")]
+        groth16::metrics::count_exponentiations(1);
+        self * g1
+    }
+    fn exp_encrypted_g2(self, g2: Self::G2) -> Self::G2 {
+        self * g2
+    }
+    fn pairing(g1: Self::G1, g2: Self::G2) -> Self::GT {
+        #[cfg(feature = "metrics")]
+        groth16::metrics::count_pairings(1);
+        g1 * g2
+    }
+}
+
+/// The same as for `Z65521`.
+impl EllipticEncryptable for Z4294967291 {
+    type G1 = Self;
+    type G2 = Self;
+    type GT = Self;
+
+    fn encrypt_g1(self) -> Self::G1 {
+        self * Self::GENERATOR
+    }
+    fn encrypt_g2(self) -> Self::G2 {
+        self * Self::GENERATOR
+    }
+    fn exp_encrypted_g1(self, g1: Self::G1) -> Self::G1 {
+        #[cfg(feature = "metrics")]
+        groth16::metrics::count_exponentiations(1);
+        self * g1
+    }
+    fn exp_encrypted_g2(self, g2: Self::G2) -> Self::G2 {
+        self * g2
+    }
+    fn pairing(g1: Self::G1, g2: Self::G2) -> Self::GT {
+        #[cfg(feature = "metrics")]
+        groth16::metrics::count_pairings(1);
+        g1 * g2
+    }
+}
+
+impl<const P: u64> Identity for Zp<P> {
+    fn is_identity(&self) -> bool {
+        *self == Self::zero()
+    }
+}
+
+impl<const P: u64> ToFieldElements<Zp<P>> for Zp<P> {
+    const LEN: usize = 1;
+
+    fn to_field_elements(&self) -> Vec<Zp<P>> {
+        vec![*self]
+    }
+    fn from_field_elements(elems: &[Zp<P>]) -> Option<Self> {
+        match elems {
+            [elem] => Some(*elem),
+            _ => None,
+        }
+    }
+}
+
+impl<const P: u64> Sum for Zp<P> {
+    fn sum<I>(iter: I) -> Self
+    where
+        I: Iterator<Item = Self>,
+    {
+        iter.fold(Zp::zero(), |acc, x| acc + x)
+    }
 }
 
 impl EllipticEncryptable for Z251 {
@@ -61,18 +216,22 @@ impl EllipticEncryptable for Z251 {
     type GT = Self;
 
     fn encrypt_g1(self) -> Self::G1 {
-        self * 69.into()
+        self * Self::GENERATOR
     }
     fn encrypt_g2(self) -> Self::G2 {
-        self * 69.into()
+        self * Self::GENERATOR
     }
     fn exp_encrypted_g1(self, g1: Self::G1) -> Self::G1 {
+        #[cfg(feature = "metrics")]
+        groth16::metrics::count_exponentiations(1);
         self * g1
     }
     fn exp_encrypted_g2(self, g2: Self::G2) -> Self::G2 {
         self * g2
     }
     fn pairing(g1: Self::G1, g2: Self::G2) -> Self::GT {
+        #[cfg(feature = "metrics")]
+        groth16::metrics::count_pairings(1);
         g1 * g2
     }
 }
@@ -83,6 +242,20 @@ impl Identity for Z251 {
     }
 }
 
+impl ToFieldElements<Z251> for Z251 {
+    const LEN: usize = 1;
+
+    fn to_field_elements(&self) -> Vec<Z251> {
+        vec![*self]
+    }
+    fn from_field_elements(elems: &[Z251]) -> Option<Self> {
+        match elems {
+            [elem] => Some(*elem),
+            _ => None,
+        }
+    }
+}
+
 impl Sum for Z251 {
     fn sum<I>(iter: I) -> Self
     where
@@ -90,4 +263,49 @@ impl Sum for Z251 {
     {
         iter.fold(Z251::from(0), |acc, x| acc + x)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn z251_random_in_range() {
+        let mut seen = [false; 251];
+        for _ in 0..10000 {
+            let x = Z251::random_in_range(100, 150);
+            assert!(100 <= x.inner && x.inner < 150);
+            seen[x.inner as usize] = true;
+        }
+
+        // Each value is missed with probability (49/50)^10000
+        assert!((100..150).all(|i| seen[i]));
+        assert!(Z251::random_in_range(0, 1) == Z251::zero());
+    }
+
+    #[test]
+    fn z251_encrypt_table() {
+        for x in Z251::all_elements() {
+            let mut expected = Z251::one();
+            for _ in 0..x.inner {
+                expected = expected * Z251 { inner: 69 };
+            }
+
+            assert_eq!(x.encrypt(), expected);
+        }
+    }
+
+    #[test]
+    fn z251_encrypt_with_generator() {
+        for x in Z251::all_elements() {
+            assert_eq!(x.encrypt_with_generator(), x.encrypt());
+        }
+    }
+
+    #[test]
+    fn z65521_encrypt() {
+        let x = Z65521::from(12345);
+        assert_eq!(x.encrypt(), Z65521::GENERATOR.pow(12345));
+        assert_eq!((x + Z65521::one()).encrypt(), x.encrypt() * Z65521::GENERATOR);
+    }
+}
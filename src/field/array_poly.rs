@@ -0,0 +1,107 @@
+//! `ArrayPoly<T, N>` is a `Polynomial` with at most `N` coefficients that are
+//! stored inline, so that small polynomials can be used without allocating.
+//!
+//! ```
+//! use zksnark::field::array_poly::ArrayPoly;
+//! use zksnark::field::z251::Z251;
+//! use zksnark::field::*;
+//!
+//! // f(x) = 1 + 2x + 3x^2
+//! let coeffs = vec![1, 2, 3].into_iter().map(Z251::from).collect::<Vec<_>>();
+//! let poly = ArrayPoly::<Z251, 4>::from(coeffs);
+//! assert_eq!(poly.degree(), 2);
+//! assert_eq!(poly.evaluate(Z251::from(2)), Z251::from(17));
+//! ```
+
+use super::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ArrayPoly<T, const N: usize> {
+    coeffs: [T; N],
+}
+
+/// Pads with zeros up to `N` coefficients.
+///
+/// Panics if any coefficient of degree `N` or higher is nonzero, since it
+/// could not be stored.
+impl<T, const N: usize> From<Vec<T>> for ArrayPoly<T, N>
+where
+    T: Field,
+{
+    fn from(vec: Vec<T>) -> Self {
+        let mut coeffs = [T::zero(); N];
+        for (i, c) in vec.into_iter().enumerate() {
+            if i < N {
+                coeffs[i] = c;
+            } else if c != T::zero() {
+                panic!("ArrayPoly: polynomial has degree of at least {}", N);
+            }
+        }
+
+        ArrayPoly { coeffs }
+    }
+}
+
+impl<T, const N: usize> From<ArrayPoly<T, N>> for Vec<T>
+where
+    T: Copy,
+{
+    fn from(poly: ArrayPoly<T, N>) -> Self {
+        poly.coeffs.to_vec()
+    }
+}
+
+impl<T, const N: usize> Deref for ArrayPoly<T, N> {
+    type Target = [T];
+    fn deref(&self) -> &Self::Target {
+        &self.coeffs
+    }
+}
+
+impl<T, const N: usize> Polynomial<T> for ArrayPoly<T, N> where T: Field {}
+
+#[cfg(test)]
+mod tests {
+    use super::super::z251::Z251;
+    use super::*;
+
+    fn to_z251(coeffs: &[usize]) -> Vec<Z251> {
+        coeffs.iter().map(|&c| Z251::from(c)).collect()
+    }
+
+    #[test]
+    fn array_poly_matches_vec() {
+        let a = to_z251(&[1, 0, 3, 1]);
+        let b = to_z251(&[2, 1]);
+        let (a_arr, b_arr) = (
+            ArrayPoly::<Z251, 4>::from(a.clone()),
+            ArrayPoly::<Z251, 4>::from(b.clone()),
+        );
+
+        for x in 0..10 {
+            assert_eq!(a_arr.evaluate(Z251::from(x)), a.evaluate(Z251::from(x)));
+        }
+
+        let (q, r) = polynomial_division(a, b);
+        let (q_arr, r_arr) = polynomial_division(a_arr, b_arr);
+        assert_eq!(q_arr, ArrayPoly::from(q));
+        assert_eq!(r_arr, ArrayPoly::from(r));
+    }
+
+    #[test]
+    fn array_poly_vec_round_trip() {
+        let poly = to_z251(&[5, 0, 7]);
+        let arr = ArrayPoly::<Z251, 4>::from(poly.clone());
+        assert_eq!(Vec::from(arr), to_z251(&[5, 0, 7, 0]));
+
+        // Trailing zeros beyond N are fine to drop
+        let arr = ArrayPoly::<Z251, 2>::from(to_z251(&[5, 7, 0, 0]));
+        assert_eq!(Vec::from(arr), to_z251(&[5, 7]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn array_poly_too_large() {
+        ArrayPoly::<Z251, 2>::from(to_z251(&[5, 0, 7]));
+    }
+}
@@ -0,0 +1,219 @@
+//! `DynField` carries a field element without knowing its concrete type at
+//! compile time, for front-ends such as scripting where the field is only
+//! chosen at runtime. The arithmetic is dispatched on the variant, and mixing
+//! elements of different fields is an error rather than a panic.
+//!
+//! ```
+//! use zksnark::field::dyn_field::DynField;
+//! use zksnark::field::z251::Z251;
+//! use zksnark::field::zp::Zp;
+//!
+//! let a = DynField::from(Z251::from(200));
+//! let b = DynField::from(Z251::from(100));
+//! assert_eq!(a + b, Ok(DynField::from(Z251::from(49))));
+//!
+//! let c = DynField::from(Zp::<7>::from(3));
+//! assert!((a + c).is_err());
+//! ```
+
+use super::z251::Z251;
+use super::zp::Zp;
+use super::*;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DynField {
+    Z251(Z251),
+    /// An element of `Zp<modulus>`, with the modulus carried at runtime.
+    Zp { inner: u64, modulus: u64 },
+}
+
+/// The two sides of an operation on `DynField` were from different fields.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldMismatch {
+    pub lhs: String,
+    pub rhs: String,
+}
+
+impl fmt::Display for FieldMismatch {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "cannot mix elements of {} and {}", self.lhs, self.rhs)
+    }
+}
+
+impl DynField {
+    /// The name of the field this element is from.
+    pub fn field_name(&self) -> String {
+        match *self {
+            DynField::Z251(_) => "Z251".to_string(),
+            DynField::Zp { modulus, .. } => format!("Zp<{}>", modulus),
+        }
+    }
+
+    /// The zero of the same field as `self`.
+    pub fn zero_like(&self) -> DynField {
+        match *self {
+            DynField::Z251(_) => DynField::Z251(Z251::zero()),
+            DynField::Zp { modulus, .. } => DynField::Zp { inner: 0, modulus },
+        }
+    }
+
+    /// The one of the same field as `self`.
+    pub fn one_like(&self) -> DynField {
+        match *self {
+            DynField::Z251(_) => DynField::Z251(Z251::one()),
+            DynField::Zp { modulus, .. } => DynField::Zp { inner: 1, modulus },
+        }
+    }
+
+    /// The multiplicative inverse. Panics on zero, like `Field::mul_inv`.
+    pub fn mul_inv(self) -> DynField {
+        match self {
+            DynField::Z251(x) => DynField::Z251(x.mul_inv()),
+            DynField::Zp { inner, modulus } => {
+                assert!(inner != 0, "Tried to get mul inv of zero");
+                // Fermat's little theorem: a^(p - 2) = a^-1
+                DynField::Zp {
+                    inner: pow_mod(inner, modulus - 2, modulus),
+                    modulus,
+                }
+            }
+        }
+    }
+
+    fn zip_with<F, G>(self, rhs: DynField, z251: F, zp: G) -> Result<DynField, FieldMismatch>
+    where
+        F: Fn(Z251, Z251) -> Z251,
+        G: Fn(u64, u64, u64) -> u64,
+    {
+        match (self, rhs) {
+            (DynField::Z251(x), DynField::Z251(y)) => Ok(DynField::Z251(z251(x, y))),
+            (DynField::Zp { inner: x, modulus }, DynField::Zp { inner: y, modulus: m })
+                if modulus == m =>
+            {
+                Ok(DynField::Zp {
+                    inner: zp(x, y, modulus),
+                    modulus,
+                })
+            }
+            _ => Err(FieldMismatch {
+                lhs: self.field_name(),
+                rhs: rhs.field_name(),
+            }),
+        }
+    }
+}
+
+fn mul_mod(a: u64, b: u64, modulus: u64) -> u64 {
+    ((a as u128 * b as u128) % modulus as u128) as u64
+}
+
+fn pow_mod(mut base: u64, mut exp: u64, modulus: u64) -> u64 {
+    let mut acc = 1 % modulus;
+    while exp != 0 {
+        if exp & 1 == 1 {
+            acc = mul_mod(acc, base, modulus);
+        }
+        base = mul_mod(base, base, modulus);
+        exp >>= 1;
+    }
+    acc
+}
+
+impl From<Z251> for DynField {
+    fn from(x: Z251) -> Self {
+        DynField::Z251(x)
+    }
+}
+
+impl<const P: u64> From<Zp<P>> for DynField {
+    fn from(x: Zp<P>) -> Self {
+        DynField::Zp {
+            inner: x.inner,
+            modulus: P,
+        }
+    }
+}
+
+impl Add for DynField {
+    type Output = Result<DynField, FieldMismatch>;
+
+    fn add(self, rhs: DynField) -> Self::Output {
+        self.zip_with(rhs, |x, y| x + y, |x, y, m| {
+            ((x as u128 + y as u128) % m as u128) as u64
+        })
+    }
+}
+
+impl Neg for DynField {
+    type Output = DynField;
+
+    fn neg(self) -> Self::Output {
+        match self {
+            DynField::Z251(x) => DynField::Z251(-x),
+            DynField::Zp { inner, modulus } => DynField::Zp {
+                inner: if inner == 0 { 0 } else { modulus - inner },
+                modulus,
+            },
+        }
+    }
+}
+
+impl Sub for DynField {
+    type Output = Result<DynField, FieldMismatch>;
+
+    fn sub(self, rhs: DynField) -> Self::Output {
+        self + -rhs
+    }
+}
+
+impl Mul for DynField {
+    type Output = Result<DynField, FieldMismatch>;
+
+    fn mul(self, rhs: DynField) -> Self::Output {
+        self.zip_with(rhs, |x, y| x * y, mul_mod)
+    }
+}
+
+impl Div for DynField {
+    type Output = Result<DynField, FieldMismatch>;
+
+    fn div(self, rhs: DynField) -> Self::Output {
+        // Check the fields match before inverting, so that a mismatch is
+        // reported even when rhs is zero
+        self.zip_with(rhs, |x, _| x, |x, _, _| x)?;
+        self * rhs.mul_inv()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dyn_field_z251_arithmetic() {
+        let (a, b) = (DynField::from(Z251::from(17)), DynField::from(Z251::from(30)));
+
+        assert_eq!(a + b, Ok(DynField::from(Z251::from(47))));
+        assert_eq!(a * b, Ok(DynField::from(Z251::from(17) * Z251::from(30))));
+        assert_eq!((a / b).and_then(|q| q * b), Ok(a));
+    }
+
+    #[test]
+    fn dyn_field_mismatch() {
+        let a = DynField::from(Z251::from(17));
+        let b = DynField::from(Zp::<251>::from(17));
+        let c = DynField::from(Zp::<7>::from(3));
+
+        // The same modulus is still a different field type
+        assert_eq!(
+            a + b,
+            Err(FieldMismatch {
+                lhs: "Z251".to_string(),
+                rhs: "Zp<251>".to_string()
+            })
+        );
+        assert!((b * c).is_err());
+        assert!((c / a).is_err());
+    }
+}
@@ -45,11 +45,16 @@ extern crate itertools;
 
 use self::itertools::unfold;
 use itertools::Itertools;
+use std::fmt;
+use std::iter::Map;
 use std::ops::*;
 use std::str::FromStr;
 
 #[doc(hidden)]
 pub mod z251;
+pub mod array_poly;
+pub mod dyn_field;
+pub mod zp;
 
 /// `FieldIdentity` only makes sense when defined with a Field. The reason
 /// this trait is not a part of [`Field`] is to provide a "zero" element and a
@@ -90,6 +95,264 @@ pub trait Field:
     fn add_inv(self) -> Self {
         -self
     }
+
+    /// `self * self`, which a field with a faster way to square than to
+    /// multiply can override. `pow` squares with this.
+    fn square(self) -> Self {
+        self * self
+    }
+
+    /// `self` to the power of `exp`, by square and multiply. `pow(0)` is
+    /// one, even for zero.
+    ///
+    /// ```
+    /// use zksnark::field::z251::Z251;
+    /// use zksnark::field::*;
+    ///
+    /// assert_eq!(Z251::from(3).pow(5), Z251::from(243));
+    /// // Fermat's little theorem
+    /// assert_eq!(Z251::from(3).pow(250), Z251::one());
+    /// ```
+    fn pow(self, mut exp: u64) -> Self {
+        let (mut base, mut acc) = (self, Self::one());
+        while exp != 0 {
+            if exp & 1 == 1 {
+                acc = acc * base;
+            }
+            base = base.square();
+            exp >>= 1;
+        }
+        acc
+    }
+
+    /// Whether the integer that represents this element is below the
+    /// characteristic of the field. Every element that the field's own
+    /// operations give is, but one deserialized from untrusted bytes might
+    /// not be, and it would then stand for a different element.
+    fn is_canonical(self) -> bool {
+        true
+    }
+
+    /// The integer that this element represents, in `0..p` where `p` is the
+    /// characteristic of the field. Panics if that integer does not fit in a
+    /// `u64`.
+    fn to_canonical_u64(self) -> u64;
+
+    /// The element that represents `n`, reduced modulo the characteristic of
+    /// the field. For any `n` below the characteristic this is the inverse
+    /// of `to_canonical_u64`.
+    ///
+    /// ```
+    /// use zksnark::field::z251::Z251;
+    /// use zksnark::field::*;
+    ///
+    /// assert_eq!(Z251::from_canonical_u64(300), Z251::from(49));
+    /// assert_eq!(Z251::from_canonical_u64(200).to_canonical_u64(), 200);
+    /// ```
+    fn from_canonical_u64(n: u64) -> Self {
+        // Double and add from the most significant bit
+        (0..64).rev().fold(Self::zero(), |acc, i| {
+            let double = acc + acc;
+            if (n >> i) & 1 == 1 {
+                double + Self::one()
+            } else {
+                double
+            }
+        })
+    }
+
+    /// The big-endian bytes of the integer that represents this element.
+    /// Every element of a field takes the same number of bytes: one for
+    /// `Z251`, as many as the characteristic needs for a `Zp`, and 32 for
+    /// `FrLocal`.
+    fn to_bytes(&self) -> Vec<u8>;
+
+    /// The inverse of `to_bytes`. Gives `None` unless there are exactly as
+    /// many bytes as `to_bytes` gives and they stand for an integer below
+    /// the characteristic, so that each element has only one encoding.
+    ///
+    /// ```
+    /// use zksnark::field::z251::Z251;
+    /// use zksnark::field::*;
+    ///
+    /// assert_eq!(Z251::from_bytes(&Z251::from(7).to_bytes()), Some(Z251::from(7)));
+    /// assert_eq!(Z251::from_bytes(&[251]), None);
+    /// ```
+    fn from_bytes(bytes: &[u8]) -> Option<Self>;
+}
+
+/// The error from parsing a field element with `TryFrom<&str>`, which unlike
+/// `FromStr` tells apart a string that is not an integer from an integer that
+/// is not an element of the field.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldParseErr {
+    /// The string is not a non-negative integer.
+    Invalid(String),
+    /// The string is an integer, but not one below the characteristic of
+    /// the field.
+    OutOfRange { value: String, field: String },
+}
+
+impl fmt::Display for FieldParseErr {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FieldParseErr::Invalid(ref value) => write!(fmt, "'{}' is not an integer", value),
+            FieldParseErr::OutOfRange {
+                ref value,
+                ref field,
+            } => write!(fmt, "{} is out of range for {}", value, field),
+        }
+    }
+}
+
+/// Parses `s` as an integer below `modulus`, for the `TryFrom<&str>` of the
+/// field called `field`.
+pub(crate) fn parse_canonical(s: &str, modulus: u64, field: &str) -> Result<u64, FieldParseErr> {
+    use std::num::IntErrorKind;
+
+    let out_of_range = || FieldParseErr::OutOfRange {
+        value: s.to_string(),
+        field: field.to_string(),
+    };
+
+    match u64::from_str(s) {
+        Ok(n) if n < modulus => Ok(n),
+        Ok(_) => Err(out_of_range()),
+        Err(ref e) if *e.kind() == IntErrorKind::PosOverflow => Err(out_of_range()),
+        Err(_) => Err(FieldParseErr::Invalid(s.to_string())),
+    }
+}
+
+/// `Sum` and `Product` cannot be implemented for every `Field` at once
+/// because of the orphan rules, so instead any iterator over the elements of
+/// a `Field` gets these.
+///
+/// ```
+/// use zksnark::field::z251::Z251;
+/// use zksnark::field::*;
+///
+/// let elems = vec![Z251::from(3), Z251::from(4), Z251::from(5)];
+/// assert_eq!(elems.iter().cloned().field_sum(), Z251::from(12));
+/// assert_eq!(elems.iter().cloned().field_product(), Z251::from(60));
+/// ```
+pub trait FieldIterator<T>: Iterator<Item = T> + Sized
+where
+    T: Field,
+{
+    /// The sum of the elements, `zero()` if there are none.
+    fn field_sum(self) -> T {
+        self.fold(T::zero(), |acc, x| acc + x)
+    }
+
+    /// The product of the elements, `one()` if there are none.
+    fn field_product(self) -> T {
+        self.fold(T::one(), |acc, x| acc * x)
+    }
+}
+
+impl<I, T> FieldIterator<T> for I
+where
+    I: Iterator<Item = T>,
+    T: Field,
+{}
+
+/// A `Field` of prime order that is small enough for every element to be
+/// enumerated, which makes exhaustive tests possible.
+///
+/// ```
+/// use zksnark::field::z251::Z251;
+/// use zksnark::field::*;
+///
+/// assert_eq!(Z251::all().count(), 251);
+/// assert_eq!(Z251::all().nth(7), Some(Z251::from(7)));
+/// ```
+pub trait PrimeField: Field + From<usize> {
+    /// The number of elements in the field.
+    const ORDER: usize;
+
+    /// A generator of the multiplicative group, so that its powers are every
+    /// nonzero element of the field.
+    ///
+    /// By default this is the smallest element `g` for which `g^((ORDER - 1)
+    /// / q)` is not one for any prime `q` dividing `ORDER - 1`. Finding it
+    /// factors `ORDER - 1` by trial division, so large fields should give
+    /// their generator directly.
+    fn multiplicative_generator() -> Self {
+        let group_order = Self::ORDER - 1;
+        let factors = prime_factors(group_order);
+
+        // Starting from one so that Z2, with no factors, gets one
+        (1..Self::ORDER)
+            .map(<Self as From<usize>>::from)
+            .find(|g| {
+                factors
+                    .iter()
+                    .all(|&q| g.pow((group_order / q) as u64) != Self::one())
+            })
+            .expect("multiplicative_generator: the multiplicative group of a prime field is cyclic")
+    }
+
+    /// The largest `s` such that `2^s` divides `ORDER - 1`, the order of the
+    /// multiplicative group. Unlike `TwoAdicField::TWO_ADICITY` this is
+    /// worked out from `ORDER`, so every `PrimeField` has it.
+    fn two_adicity() -> u32 {
+        (Self::ORDER - 1).trailing_zeros()
+    }
+
+    /// A root of unity of order exactly `2^two_adicity()`, which is the
+    /// multiplicative generator raised to the odd part of `ORDER - 1`.
+    fn two_adic_root_of_unity() -> Self {
+        let odd = (Self::ORDER - 1) >> Self::two_adicity();
+        Self::multiplicative_generator().pow(odd as u64)
+    }
+
+    /// Yields every element of the field exactly once, starting from
+    /// `zero()`.
+    fn all() -> Map<Range<usize>, fn(usize) -> Self> {
+        (0..Self::ORDER).map(<Self as From<usize>>::from as fn(usize) -> Self)
+    }
+}
+
+/// The distinct prime factors of `n`, in increasing order.
+fn prime_factors(mut n: usize) -> Vec<usize> {
+    let mut factors = Vec::new();
+    let mut p = 2;
+    while p <= n / p {
+        if n % p == 0 {
+            factors.push(p);
+            while n % p == 0 {
+                n /= p;
+            }
+        }
+        p += 1;
+    }
+    if n > 1 {
+        factors.push(n);
+    }
+    factors
+}
+
+/// A `Field` whose multiplicative group has a subgroup of order
+/// `2^TWO_ADICITY`, so that it has roots of unity for every power of two up to
+/// that size. These are what the radix-2 `fft` needs.
+pub trait TwoAdicField: Field {
+    /// The largest `k` such that `2^k` divides the order of the
+    /// multiplicative group.
+    const TWO_ADICITY: u32;
+
+    /// A root of unity of order exactly `2^TWO_ADICITY`.
+    fn two_adic_root() -> Self;
+
+    /// A root of unity of order exactly `2^log_size`.
+    fn root_of_unity(log_size: u32) -> Self {
+        assert!(
+            log_size <= Self::TWO_ADICITY,
+            "The field has no root of unity of order 2^{}",
+            log_size
+        );
+
+        (log_size..Self::TWO_ADICITY).fold(Self::two_adic_root(), |root, _| root.square())
+    }
 }
 
 /// The core reason we need a function like this is to let us assign
@@ -202,6 +465,35 @@ where
         }).collect()
 }
 
+/// A field element given to `field_bits_to_bytes` that is neither zero nor
+/// one, at `index` of the bits.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NotABit {
+    pub index: usize,
+}
+
+impl fmt::Display for NotABit {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "the field element at {} is not a bit", self.index)
+    }
+}
+
+/// The bytes with the bits `bits`, in the order of `to_field_bits`, which is
+/// eight to a byte with the least significant bit first as for the wires of
+/// a `Word8`.
+/// Unlike `from_field_bits` this gives an error rather than panicking if an
+/// element is not a bit. A last byte with fewer than eight bits has zeros
+/// for its high bits.
+pub fn field_bits_to_bytes<T>(bits: &[T]) -> Result<Vec<u8>, NotABit>
+where
+    T: Field,
+{
+    match bits.iter().position(|&b| b != T::zero() && b != T::one()) {
+        Some(index) => Err(NotABit { index }),
+        None => Ok(from_field_bits(bits)),
+    }
+}
+
 /// A line, `Polynomial`, represented as a vector of `Field` elements where the
 /// position in the vector determines the power of the exponent.
 ///
@@ -257,6 +549,21 @@ where
         self.iter().map(|&x| x).collect()
     }
 
+    /// The coefficients of the `Polynomial` without copying them, lowest
+    /// degree first. Prefer this to `coefficients` when the coefficients are
+    /// only read.
+    ///
+    /// ```
+    /// use zksnark::field::z251::Z251;
+    /// use zksnark::field::*;
+    ///
+    /// let poly = vec![1, 2, 0, 4].into_iter().map(Z251::from).collect::<Vec<_>>();
+    /// assert_eq!(poly.coefficients_ref(), &poly.coefficients()[..]);
+    /// ```
+    fn coefficients_ref(&self) -> &[T] {
+        &**self
+    }
+
     /// Returns the highest exponent of the polynomial.
     ///
     /// # Examples
@@ -336,11 +643,48 @@ where
     /// );
     /// ```
     fn evaluate(&self, x: T) -> T {
-        self.coefficients()
+        self.coefficients_ref()
             .iter()
             .rev()
             .fold(T::zero(), |acc, y| (acc * x) + *y)
     }
+
+    /// Evaluates the polynomial at the `count` points `start * ratio^i`.
+    ///
+    /// Rather than a Horner evaluation at each point, every term `c_j
+    /// (start * ratio^i)^j` is kept and moved on to the next point by
+    /// multiplying it by `ratio^j`, so no power of a point is ever computed.
+    ///
+    /// ```
+    /// use zksnark::field::z251::Z251;
+    /// use zksnark::field::*;
+    ///
+    /// // f(x) = 1 + x + x^2 at 1, 2 and 4
+    /// let poly = vec![1, 1, 1].into_iter().map(Z251::from).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(
+    ///     poly.evaluate_geometric(Z251::from(1), Z251::from(2), 3),
+    ///     vec![3, 7, 21].into_iter().map(Z251::from).collect::<Vec<_>>()
+    /// );
+    /// ```
+    fn evaluate_geometric(&self, start: T, ratio: T, count: usize) -> Vec<T> {
+        let mut terms = self
+            .iter()
+            .zip(powers(start))
+            .map(|(&c, s)| c * s)
+            .collect::<Vec<_>>();
+        let steps = powers(ratio).take(terms.len()).collect::<Vec<_>>();
+
+        (0..count)
+            .map(|_| {
+                let value = terms.iter().fold(T::zero(), |acc, &t| acc + t);
+                for (term, &step) in terms.iter_mut().zip(steps.iter()) {
+                    *term = *term * step;
+                }
+                value
+            }).collect()
+    }
+
     fn remove_leading_zeros(&mut self) {
         *self = self
             .coefficients()
@@ -357,7 +701,24 @@ where
 
 impl<T> Polynomial<T> for Vec<T> where T: Field {}
 
-fn ext_euc_alg<T>(a: T, b: T) -> (T, T, T)
+/// The extended Euclidean algorithm. Returns `(g, s, t)` where `g` is the
+/// greatest common divisor of `a` and `b` and `s`, `t` are Bézout
+/// coefficients such that `a * s + b * t = g`.
+///
+/// The bounds only ask for the integer-like operations that the algorithm
+/// uses, with `Div` expected to be truncating division. This makes it
+/// useful for types such as `isize`; in a `Field` every division is exact
+/// so the result is not very interesting.
+///
+/// ```
+/// use zksnark::field::ext_euc_alg;
+///
+/// let (g, s, t) = ext_euc_alg(240isize, 46);
+///
+/// assert_eq!((g, s, t), (2, -9, 47));
+/// assert_eq!(240 * s + 46 * t, g);
+/// ```
+pub fn ext_euc_alg<T>(a: T, b: T) -> (T, T, T)
 where
     T: Div<Output = T> + Mul<Output = T> + Sub<Output = T> + Eq + FieldIdentity + Copy,
 {
@@ -384,7 +745,23 @@ where
     (*r0, *s0, *t0)
 }
 
-fn chinese_remainder<T>(rems: &[T], moduli: &[T]) -> T
+/// The Chinese remainder theorem. Given pairwise coprime `moduli` and the
+/// remainders `rems` of some `x` by each of them, returns an integer that
+/// is congruent to `x` modulo the product of the `moduli`. The result is not
+/// reduced, so it may be negative or larger than the product.
+///
+/// As with `ext_euc_alg`, the bounds are those of an integer-like type with
+/// truncating `Div`, such as `isize`.
+///
+/// ```
+/// use zksnark::field::chinese_remainder;
+///
+/// // x = 2 mod 3, x = 3 mod 5 and x = 2 mod 7 gives x = 23 mod 105
+/// let x = chinese_remainder(&[2isize, 3, 2], &[3, 5, 7]);
+///
+/// assert_eq!(x.rem_euclid(105), 23);
+/// ```
+pub fn chinese_remainder<T>(rems: &[T], moduli: &[T]) -> T
 where
     T: Div<Output = T>
         + Mul<Output = T>
@@ -431,9 +808,9 @@ where
     T: Field,
 {
     if dividend
-        .coefficients()
-        .into_iter()
-        .skip_while(|&c| c == T::zero())
+        .coefficients_ref()
+        .iter()
+        .skip_while(|&&c| c == T::zero())
         .count()
         == 0
     {
@@ -450,7 +827,7 @@ where
     let mut q = vec![T::zero(); poly.degree() + 1 - dividend.degree()];
     let mut r = poly.coefficients();
     let d = dividend.degree();
-    let c = dividend.coefficients()[d];
+    let c = dividend.coefficients_ref()[d];
 
     while r.degree() >= d && r.len() != 0 {
         let s = r[r.degree()] / c;
@@ -459,7 +836,7 @@ where
             .iter_mut()
             .rev()
             .skip_while(|&&mut c| c == T::zero())
-            .zip(dividend.coefficients().into_iter().map(|a| a * s).rev())
+            .zip(dividend.coefficients_ref().iter().map(|&a| a * s).rev())
             .for_each(|(r, b)| *r = *r - b);
 
         r.remove_leading_zeros();
@@ -468,6 +845,31 @@ where
     (q.into(), r.into())
 }
 
+/// The remainder of `a` divided by `modulus`, as given by
+/// `polynomial_division`. When `a` has a lower degree than `modulus` it is
+/// its own remainder and is returned unchanged. Panics if `modulus` is zero.
+///
+/// ```
+/// use zksnark::field::z251::Z251;
+/// use zksnark::field::*;
+///
+/// // x^2 + 3 = (x^2 + 1) + 2
+/// let a: Vec<Z251> = vec![3, 0, 1].into_iter().map(Z251::from).collect();
+/// let modulus: Vec<Z251> = vec![1, 0, 1].into_iter().map(Z251::from).collect();
+///
+/// assert_eq!(poly_mod(a, modulus), vec![Z251::from(2)]);
+/// ```
+pub fn poly_mod<T>(a: Vec<T>, modulus: Vec<T>) -> Vec<T>
+where
+    T: Field,
+{
+    if a.degree() < modulus.degree() {
+        return a;
+    }
+
+    polynomial_division(a, modulus).1
+}
+
 /// Yields an infinite list of powers of x starting from x^0.
 ///
 /// ```rust
@@ -503,6 +905,35 @@ where
     }))
 }
 
+/// The `size` distinct powers of a root of unity of order exactly `size`,
+/// starting from `one()`. These are the points that a `dft` of that size
+/// evaluates at. Returns `None` if the field has no such root, which is when
+/// `size` does not divide `ORDER - 1`.
+///
+/// ```
+/// use zksnark::field::z251::Z251;
+/// use zksnark::field::*;
+///
+/// assert_eq!(domain_elements::<Z251>(5).map(|d| d.len()), Some(5));
+/// assert_eq!(domain_elements::<Z251>(4), None);
+/// ```
+pub fn domain_elements<T>(size: usize) -> Option<Vec<T>>
+where
+    T: PrimeField,
+{
+    if size == 0 || (T::ORDER - 1) % size != 0 {
+        return None;
+    }
+
+    // x has order exactly size if the first power of x to return to one is
+    // x^size
+    let root = T::all().skip(1).find(|&x| {
+        powers(x).skip(1).take(size).position(|y| y == T::one()) == Some(size - 1)
+    })?;
+
+    Some(powers(root).take(size).collect::<Vec<_>>())
+}
+
 /// Discrete Fourier Transformation
 ///
 pub fn dft<T>(seq: &[T], root: T) -> Vec<T>
@@ -515,25 +946,193 @@ where
             seq.iter()
                 .zip(powers(ri))
                 .map(|(&a, r)| a * r)
-                .fold(T::zero(), |acc, x| acc + x)
+                .field_sum()
         }).collect::<Vec<_>>()
 }
 
+/// The ways that an evaluation domain can be unusable for a transformation.
+#[derive(Debug, PartialEq)]
+pub enum DomainError {
+    /// The size of the domain is a multiple of the characteristic of the
+    /// field, so it is zero in the field and cannot be divided by.
+    SizeNotInvertible { size: usize },
+}
+
+/// The inverse of the length of `seq` as a field element, which the inverse
+/// transformations scale by. Gives an error if the length is a multiple of
+/// the characteristic of the field.
+fn inverse_length<T>(seq: &[T]) -> Result<T, DomainError>
+where
+    T: Field,
+{
+    // The length as a field element, without going through `From<usize>`
+    // which may not accept values at or above the characteristic
+    let len = seq.iter().fold(T::zero(), |acc, _| acc + T::one());
+    if len == T::zero() {
+        return Err(DomainError::SizeNotInvertible { size: seq.len() });
+    }
+    Ok(len.mul_inv())
+}
+
 /// Inverse Discrete Fourier Transformation
 ///
-pub fn idft<T>(seq: &[T], root: T) -> Vec<T>
+/// Gives an error if the length of `seq` is a multiple of the characteristic
+/// of the field, since the result is scaled by the inverse of the length.
+pub fn idft<T>(seq: &[T], root: T) -> Result<Vec<T>, DomainError>
 where
-    T: Field + From<usize>,
+    T: Field,
 {
-    powers(root.mul_inv())
+    if seq.is_empty() {
+        return Ok(Vec::new());
+    }
+    let len_inv = inverse_length(seq)?;
+
+    Ok(powers(root.mul_inv())
         .take(seq.len())
         .map(|ri| {
             seq.iter()
                 .zip(powers(ri))
                 .map(|(&a, r)| a * r)
-                .fold(T::zero(), |acc, x| acc + x)
-                * T::from(seq.len()).mul_inv()
-        }).collect::<Vec<_>>()
+                .field_sum()
+                * len_inv
+        }).collect::<Vec<_>>())
+}
+
+/// Radix-2 Fast Fourier Transformation. Gives the same result as `dft`
+/// but `seq` must have a length that is a power of two and `root` must have
+/// exactly that order.
+///
+/// ```
+/// use zksnark::field::z251::Z251;
+/// use zksnark::field::*;
+///
+/// // 250 = -1 has order 2 in Z251
+/// let seq = vec![Z251::from(3), Z251::from(5)];
+/// assert_eq!(fft(&seq, Z251::from(250)), dft(&seq, Z251::from(250)));
+/// ```
+pub fn fft<T>(seq: &[T], root: T) -> Vec<T>
+where
+    T: Field,
+{
+    let n = seq.len();
+    assert!(n.is_power_of_two(), "fft: length must be a power of two");
+
+    if n == 1 {
+        return seq.to_vec();
+    }
+
+    let even = seq.iter().cloned().step_by(2).collect::<Vec<_>>();
+    let odd = seq.iter().cloned().skip(1).step_by(2).collect::<Vec<_>>();
+    let squared = root.square();
+    let (even, odd) = (fft(&even, squared), fft(&odd, squared));
+
+    let half = n / 2;
+    let mut result = vec![T::zero(); n];
+    for (k, rk) in powers(root).take(half).enumerate() {
+        let t = rk * odd[k];
+        result[k] = even[k] + t;
+        result[k + half] = even[k] - t;
+    }
+    result
+}
+
+/// Inverse of `fft`.
+///
+/// As with `idft`, gives an error if the length of `seq` is a multiple of the
+/// characteristic of the field, since the result is scaled by the inverse of
+/// the length.
+///
+/// ```
+/// use zksnark::field::z251::Z251;
+/// use zksnark::field::*;
+///
+/// let seq = vec![Z251::from(3), Z251::from(5)];
+/// let root = Z251::from(250);
+/// assert_eq!(ifft(&fft(&seq, root), root), Ok(seq));
+/// ```
+pub fn ifft<T>(seq: &[T], root: T) -> Result<Vec<T>, DomainError>
+where
+    T: Field,
+{
+    if seq.is_empty() {
+        return Ok(Vec::new());
+    }
+    let n_inv = inverse_length(seq)?;
+
+    Ok(fft(seq, root.mul_inv())
+        .into_iter()
+        .map(|x| x * n_inv)
+        .collect::<Vec<_>>())
+}
+
+/// Fourier Transformation of any length using Bluestein's algorithm. Gives
+/// the same result as `dft` for any nonzero `root`, but in `O(n log n)` by
+/// rewriting the transformation as a convolution and doing that with the
+/// radix-2 `fft`.
+///
+/// It uses `jk = C(k, 2) + C(-j, 2) - C(k - j, 2)`, where `C(m, 2) = m(m -
+/// 1) / 2`, so that `X_k = r^C(k, 2) * sum_j (x_j * r^C(-j, 2)) *
+/// r^-C(k - j, 2)`.
+pub fn fft_bluestein<T>(seq: &[T], root: T) -> Vec<T>
+where
+    T: TwoAdicField + From<usize>,
+{
+    let n = seq.len();
+    if n == 0 {
+        return Vec::new();
+    }
+
+    // chirp[m] = root^C(m, 2) and inv_chirp[m] = root^-C(m, 2) for m in
+    // 0..=n, where C(m + 1, 2) = C(m, 2) + m. Negative m are covered by
+    // C(-m, 2) = C(m + 1, 2).
+    let chirp_table = |r: T| {
+        powers(r)
+            .take(n)
+            .scan(T::one(), |acc, rm| {
+                let c = *acc;
+                *acc = *acc * rm;
+                Some(c)
+            }).chain(::std::iter::once(
+                powers(r).take(n).field_product(),
+            )).collect::<Vec<_>>()
+    };
+    let chirp = chirp_table(root);
+    let inv_chirp = chirp_table(root.mul_inv());
+
+    // Large enough that the cyclic convolution does not wrap around
+    let size = (2 * n - 1).next_power_of_two();
+    let log_size = size.trailing_zeros();
+    let omega = T::root_of_unity(log_size);
+
+    let mut a = vec![T::zero(); size];
+    for (j, &x) in seq.iter().enumerate() {
+        a[j] = x * chirp[j + 1];
+    }
+
+    let mut b = vec![T::zero(); size];
+    b[0] = inv_chirp[0];
+    for d in 1..n {
+        b[d] = inv_chirp[d];
+        b[size - d] = inv_chirp[d + 1];
+    }
+
+    // size divides the order of the multiplicative group, so it is never a
+    // multiple of the characteristic
+    let conv = ifft(
+        &fft(&a, omega)
+            .into_iter()
+            .zip(fft(&b, omega))
+            .map(|(x, y)| x * y)
+            .collect::<Vec<_>>(),
+        omega,
+    ).expect("fft_bluestein: the size of the convolution is invertible");
+
+    chirp
+        .into_iter()
+        .zip(conv)
+        .take(n)
+        .map(|(c, x)| c * x)
+        .collect::<Vec<_>>()
 }
 
 #[cfg(test)]
@@ -544,6 +1143,14 @@ mod tests {
     extern crate quickcheck;
     use self::quickcheck::quickcheck;
 
+    #[test]
+    fn prime_factors_test() {
+        assert_eq!(prime_factors(1), Vec::<usize>::new());
+        assert_eq!(prime_factors(250), vec![2, 5]);
+        assert_eq!(prime_factors(65520), vec![2, 3, 5, 7, 13]);
+        assert_eq!(prime_factors(4294967291), vec![4294967291]);
+    }
+
     quickcheck! {
         fn field_bits_u8_prop(vec: Vec<u8>) -> bool {
             let field_bits: Vec<Z251> = to_field_bits(&vec);
@@ -568,6 +1175,16 @@ mod tests {
                 .zip(powers(x))
                 .fold(Z251::zero(), |acc, (&c, x)| acc + c * x)
         }
+        fn evaluate_geometric_prop(vec: Vec<usize>, start: usize, ratio: usize, count: usize) -> bool {
+            let poly: Vec<Z251> = vec.into_iter().map(|x| Z251::from(x % 251)).collect();
+            let (start, ratio) = (Z251::from(start % 251), Z251::from(ratio % 251));
+            let count = count % 32;
+
+            poly.evaluate_geometric(start, ratio, count) == powers(ratio)
+                .take(count)
+                .map(|r| poly.evaluate(start * r))
+                .collect::<Vec<_>>()
+        }
         fn degree_prop(vec: Vec<usize>) -> bool {
             let poly: Vec<Z251> = vec.into_iter().map(|x| Z251::from(x % 251)).collect();
             let coeffs = poly.coefficients();
@@ -588,6 +1205,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn poly_mod_test() {
+        let z = |coeffs: Vec<usize>| coeffs.into_iter().map(Z251::from).collect::<Vec<_>>();
+
+        // x^3 = x (x^2 - 1) + x
+        assert_eq!(poly_mod(z(vec![0, 0, 0, 1]), z(vec![250, 0, 1])), z(vec![0, 1]));
+        // x + 1 has a lower degree than x^2 - 1
+        assert_eq!(poly_mod(z(vec![1, 1]), z(vec![250, 0, 1])), z(vec![1, 1]));
+    }
+
+    #[test]
+    fn field_bits_bytes_round_trip() {
+        // The input of the keccak circuit tests
+        let bytes = [63u8; 20];
+        let bits: Vec<Z251> = to_field_bits(&bytes);
+        assert_eq!(bits.len(), 160);
+        assert_eq!(field_bits_to_bytes(&bits), Ok(bytes.to_vec()));
+
+        let mut not_bits = bits.clone();
+        not_bits[42] = Z251::from(2);
+        assert_eq!(field_bits_to_bytes(&not_bits), Err(NotABit { index: 42 }));
+    }
+
     #[test]
     fn powers_test() {
         let root = Z251 { inner: 9 };
@@ -603,6 +1243,54 @@ mod tests {
         );
     }
 
+    #[test]
+    fn field_sum_product_test() {
+        let elems = (1..20).map(Z251::from).collect::<Vec<_>>();
+
+        assert_eq!(
+            elems.iter().cloned().field_sum(),
+            elems.iter().fold(Z251::zero(), |acc, &x| acc + x)
+        );
+        assert_eq!(
+            elems.iter().cloned().field_product(),
+            elems.iter().fold(Z251::one(), |acc, &x| acc * x)
+        );
+
+        // Also works for a field that has no `Sum` or `Product` of its own
+        let elems = (1..20).map(zp::Zp::<7>::from).collect::<Vec<_>>();
+        assert_eq!(elems.iter().cloned().field_sum(), zp::Zp::<7>::from(190));
+        assert_eq!(elems.iter().cloned().field_product(), zp::Zp::<7>::zero());
+
+        assert_eq!(Vec::<Z251>::new().into_iter().field_sum(), Z251::zero());
+        assert_eq!(Vec::<Z251>::new().into_iter().field_product(), Z251::one());
+    }
+
+    #[test]
+    fn domain_elements_test() {
+        let domain = domain_elements::<Z251>(25).unwrap();
+
+        let distinct = domain.iter().collect::<::std::collections::HashSet<_>>();
+        assert_eq!(distinct.len(), 25);
+
+        // Multiply out (x - e) for every e in the domain, lowest degree first
+        let vanishing = domain.iter().fold(vec![Z251::one()], |poly, &e| {
+            let mut next = vec![Z251::zero(); poly.len() + 1];
+            for (i, &c) in poly.iter().enumerate() {
+                next[i + 1] = next[i + 1] + c;
+                next[i] = next[i] - e * c;
+            }
+            next
+        });
+
+        let mut expected = vec![Z251::zero(); 26];
+        expected[0] = -Z251::one();
+        expected[25] = Z251::one();
+        assert_eq!(vanishing, expected);
+
+        assert_eq!(domain_elements::<Z251>(3), None);
+        assert_eq!(domain_elements::<Z251>(0), None);
+    }
+
     #[test]
     fn dft_test() {
         // 25 divies 251 - 1 and 5 has order 25 in Z251
@@ -631,7 +1319,19 @@ mod tests {
         seq[2] = 3.into();
         let root = 5.into();
 
-        assert_eq!(idft(&dft(&seq[..], root)[..], root), seq.to_vec());
+        assert_eq!(idft(&dft(&seq[..], root)[..], root), Ok(seq.to_vec()));
+    }
+
+    #[test]
+    fn idft_size_not_invertible_test() {
+        // 251 is zero in Z251, so there is no 1 / 251 to scale by
+        let seq = [Z251::one(); 251];
+        let root = 5.into();
+
+        assert_eq!(
+            idft(&seq[..], root),
+            Err(DomainError::SizeNotInvertible { size: 251 })
+        );
     }
 
     #[test]
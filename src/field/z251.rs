@@ -1,5 +1,6 @@
 use super::*;
 
+use std::convert::TryFrom;
 use serde_derive::{Serialize, Deserialize};
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
@@ -75,6 +76,51 @@ impl Field for Z251 {
     fn mul_inv(self) -> Self {
         Z251::one().div(self)
     }
+    fn is_canonical(self) -> bool {
+        self.inner < 251
+    }
+    fn to_canonical_u64(self) -> u64 {
+        self.inner as u64
+    }
+    fn from_canonical_u64(n: u64) -> Self {
+        Z251 {
+            inner: (n % 251) as u8,
+        }
+    }
+    fn to_bytes(&self) -> Vec<u8> {
+        vec![self.inner]
+    }
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        match *bytes {
+            [inner] if inner < 251 => Some(Z251 { inner }),
+            _ => None,
+        }
+    }
+}
+
+impl PrimeField for Z251 {
+    const ORDER: usize = 251;
+
+    // 6^50 and 6^125 are not one, so 6 has order 250
+    fn multiplicative_generator() -> Self {
+        Z251 { inner: 6 }
+    }
+}
+
+impl TwoAdicField for Z251 {
+    // 250 = 2 * 5^3
+    const TWO_ADICITY: u32 = 1;
+
+    fn two_adic_root() -> Self {
+        Z251 { inner: 250 }
+    }
+}
+
+impl Z251 {
+    /// Yields every element of `Z251` exactly once, in increasing order.
+    pub fn all_elements() -> impl Iterator<Item = Z251> {
+        Self::all()
+    }
 }
 
 impl From<usize> for Z251 {
@@ -98,6 +144,15 @@ impl FromStr for Z251 {
     }
 }
 
+impl<'a> TryFrom<&'a str> for Z251 {
+    type Error = FieldParseErr;
+
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        parse_canonical(s, 251, "Z251")?;
+        Z251::from_str(s).map_err(|_| FieldParseErr::Invalid(s.to_string()))
+    }
+}
+
 impl BitAnd for Z251 {
     type Output=Z251;
     fn bitand(self, rhs: Self) -> Self {
@@ -109,6 +164,40 @@ impl BitAnd for Z251 {
 mod tests {
     use super::*;
 
+    #[test]
+    fn z251_pow() {
+        assert_eq!(Z251::from(2).pow(10), Z251::from(1024 % 251));
+        assert_eq!(Z251::from(2).pow(0), Z251::one());
+        assert_eq!(Z251::zero().pow(0), Z251::one());
+        assert_eq!(Z251::zero().pow(7), Z251::zero());
+
+        for n in 0..300 {
+            let x = Z251::from(17);
+            assert_eq!(x.pow(n), powers(x).nth(n as usize).unwrap());
+        }
+    }
+
+    #[test]
+    fn z251_try_from_str() {
+        assert_eq!(Z251::try_from("42"), Ok(Z251::from(42)));
+        assert_eq!(Z251::try_from("250"), Ok(Z251::from(250)));
+        assert_eq!(
+            Z251::try_from("251"),
+            Err(FieldParseErr::OutOfRange {
+                value: "251".to_string(),
+                field: "Z251".to_string()
+            })
+        );
+        assert_eq!(
+            Z251::try_from("forty-two"),
+            Err(FieldParseErr::Invalid("forty-two".to_string()))
+        );
+        assert_eq!(
+            Z251::try_from("-1"),
+            Err(FieldParseErr::Invalid("-1".to_string()))
+        );
+    }
+
     #[test]
     fn z251_add() {
         for i in 0_u16..251_u16 {
@@ -139,6 +228,71 @@ mod tests {
         }
     }
 
+    #[test]
+    fn z251_all_elements() {
+        use std::collections::HashSet;
+
+        assert_eq!(Z251::all_elements().count(), 251);
+
+        let distinct = Z251::all_elements().collect::<HashSet<_>>();
+        assert_eq!(distinct.len(), 251);
+        assert!(Z251::all_elements().all(|x| x.inner < 251));
+    }
+
+    #[test]
+    fn z251_square() {
+        for x in (0..251).map(Z251::from) {
+            assert_eq!(x.square(), x * x);
+        }
+    }
+
+    #[test]
+    fn z251_bytes_round_trip() {
+        for x in Z251::all() {
+            let bytes = x.to_bytes();
+            assert_eq!(bytes.len(), 1);
+            assert_eq!(Z251::from_bytes(&bytes), Some(x));
+        }
+    }
+
+    #[test]
+    fn z251_from_bytes_rejects() {
+        assert_eq!(Z251::from_bytes(&[255]), None);
+        assert_eq!(Z251::from_bytes(&[251]), None);
+        assert_eq!(Z251::from_bytes(&[]), None);
+        assert_eq!(Z251::from_bytes(&[0, 1]), None);
+    }
+
+    #[test]
+    fn z251_two_adicity() {
+        assert_eq!(Z251::two_adicity(), 1);
+
+        let root = Z251::two_adic_root_of_unity();
+        assert_ne!(root, Z251::one());
+        assert_eq!(root * root, Z251::one());
+        assert_eq!(root, Z251::two_adic_root());
+    }
+
+    #[test]
+    fn z251_multiplicative_generator() {
+        use std::collections::HashSet;
+
+        let g = Z251::multiplicative_generator();
+        let cycle = powers(g).skip(1).take(250).collect::<Vec<_>>();
+
+        assert_eq!(cycle.iter().collect::<HashSet<_>>().len(), 250);
+        assert!(!cycle.contains(&Z251::zero()));
+        assert_eq!(cycle[249], Z251::one());
+    }
+
+    #[test]
+    fn z251_canonical_u64() {
+        for x in Z251::all_elements() {
+            assert_eq!(Z251::from_canonical_u64(x.to_canonical_u64()), x);
+        }
+        assert_eq!(Z251::from_canonical_u64(251), Z251::zero());
+    }
+
     #[test]
     fn crt() {
         let rems = [0, 3, 4];
@@ -0,0 +1,334 @@
+//! `Zp<P>` is the field of integers modulo any prime `P` that fits in a
+//! `u64`. It fills the gap between `Z251`, which is only good for testing, and
+//! the curve backed `FrLocal`.
+//!
+//! ```
+//! use zksnark::field::zp::Zp;
+//! use zksnark::field::*;
+//!
+//! type Z7 = Zp<7>;
+//!
+//! assert_eq!(Z7::from(5) + Z7::from(4), Z7::from(2));
+//! assert_eq!(Z7::from(3) * Z7::from(3).mul_inv(), Z7::one());
+//! ```
+
+use super::*;
+use std::convert::TryFrom;
+use serde_derive::{Serialize, Deserialize};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Zp<const P: u64> {
+    pub inner: u64,
+}
+
+impl<const P: u64> Zp<P> {
+    /// The number of bytes in `Field::to_bytes`, enough for `P - 1`.
+    const BYTES: usize = (64 - (P - 1).leading_zeros() as usize + 7) / 8;
+
+    /// The full product of two `u64`, which cannot overflow a `u128`.
+    fn widening_mul(a: u64, b: u64) -> u128 {
+        (a as u128) * (b as u128)
+    }
+
+    /// The Barrett constant `floor((2^128 - 1) / P)`, which is within 2 of
+    /// `2^128 / P`.
+    const MU: u128 = u128::MAX / (P as u128);
+
+    /// The high 128 bits of the full product of two `u128`.
+    fn mul_high(a: u128, b: u128) -> u128 {
+        let (a_lo, a_hi) = (a as u64 as u128, a >> 64);
+        let (b_lo, b_hi) = (b as u64 as u128, b >> 64);
+
+        let lo_lo = a_lo * b_lo;
+        let hi_lo = a_hi * b_lo;
+        let lo_hi = a_lo * b_hi;
+
+        // Less than 3 * 2^64, so cannot overflow
+        let cross = (lo_lo >> 64) + (hi_lo as u64 as u128) + (lo_hi as u64 as u128);
+        a_hi * b_hi + (hi_lo >> 64) + (lo_hi >> 64) + (cross >> 64)
+    }
+
+    /// Reduces a `u128` modulo `P` with Barrett reduction. The estimated
+    /// quotient `wide * MU / 2^128` is at most 2 less than the real one, so
+    /// `wide - quotient * P`, which cannot underflow, is less than `3P` and
+    /// at most two subtractions bring it into the field.
+    fn reduce(wide: u128) -> u64 {
+        let p = P as u128;
+        let quotient = Self::mul_high(wide, Self::MU);
+
+        let mut rem = wide - quotient * p;
+        if rem >= p {
+            rem -= p;
+        }
+        if rem >= p {
+            rem -= p;
+        }
+        debug_assert!(rem < p, "Zp: Barrett reduction produced a value outside of the field");
+        rem as u64
+    }
+}
+
+impl<const P: u64> Add for Zp<P> {
+    type Output = Zp<P>;
+
+    fn add(self, rhs: Zp<P>) -> Self::Output {
+        // When P is close to u64::MAX the sum can overflow, in which case the
+        // wrapped sum is exactly 2^64 too small and subtracting P (wrapping)
+        // gives the right answer.
+        let (sum, carry) = self.inner.overflowing_add(rhs.inner);
+        let inner = if carry || sum >= P { sum.wrapping_sub(P) } else { sum };
+        debug_assert!(inner < P, "Zp: addition produced a value outside of the field");
+
+        Zp { inner }
+    }
+}
+
+impl<const P: u64> Neg for Zp<P> {
+    type Output = Zp<P>;
+
+    fn neg(self) -> Self::Output {
+        if self.inner == 0 {
+            self
+        } else {
+            Zp { inner: P - self.inner }
+        }
+    }
+}
+
+impl<const P: u64> Sub for Zp<P> {
+    type Output = Zp<P>;
+
+    fn sub(self, rhs: Zp<P>) -> Self::Output {
+        self + -rhs
+    }
+}
+
+impl<const P: u64> Mul for Zp<P> {
+    type Output = Zp<P>;
+
+    fn mul(self, rhs: Zp<P>) -> Self::Output {
+        Zp {
+            inner: Self::reduce(Self::widening_mul(self.inner, rhs.inner)),
+        }
+    }
+}
+
+impl<const P: u64> Div for Zp<P> {
+    type Output = Zp<P>;
+
+    fn div(self, rhs: Zp<P>) -> Self::Output {
+        self * rhs.mul_inv()
+    }
+}
+
+impl<const P: u64> FieldIdentity for Zp<P> {
+    fn zero() -> Self {
+        Zp { inner: 0 }
+    }
+    fn one() -> Self {
+        Zp { inner: 1 }
+    }
+}
+
+impl<const P: u64> Field for Zp<P> {
+    fn mul_inv(self) -> Self {
+        assert!(self.inner != 0, "Tried to get mul inv of zero");
+        // Fermat's little theorem: a^(P - 1) = 1, so a^(P - 2) = a^-1
+        self.pow(P - 2)
+    }
+    fn is_canonical(self) -> bool {
+        self.inner < P
+    }
+    fn to_canonical_u64(self) -> u64 {
+        self.inner
+    }
+    fn from_canonical_u64(n: u64) -> Self {
+        Zp {
+            inner: Self::reduce(n as u128),
+        }
+    }
+    fn to_bytes(&self) -> Vec<u8> {
+        self.inner.to_be_bytes()[8 - Self::BYTES..].to_vec()
+    }
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != Self::BYTES {
+            return None;
+        }
+
+        let inner = bytes.iter().fold(0, |acc, &b| acc << 8 | b as u64);
+        if inner < P {
+            Some(Zp { inner })
+        } else {
+            None
+        }
+    }
+}
+
+impl<const P: u64> From<usize> for Zp<P> {
+    fn from(n: usize) -> Self {
+        Zp {
+            inner: Self::reduce(n as u128),
+        }
+    }
+}
+
+impl<const P: u64> FromStr for Zp<P> {
+    type Err = ::std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Zp {
+            inner: Self::reduce(u64::from_str(s)? as u128),
+        })
+    }
+}
+
+/// Unlike `FromStr`, which reduces its input modulo `P`, this only accepts
+/// integers that are already below `P`.
+impl<'a, const P: u64> TryFrom<&'a str> for Zp<P> {
+    type Error = FieldParseErr;
+
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        parse_canonical(s, P, &format!("Zp<{}>", P))?;
+        Zp::from_str(s).map_err(|_| FieldParseErr::Invalid(s.to_string()))
+    }
+}
+
+/// A 16 bit prime field, for demo circuits that do not fit in `Z251`.
+pub type Z65521 = Zp<65521>;
+
+/// A 32 bit prime field, since `2^32 - 5` is prime.
+pub type Z4294967291 = Zp<4294967291>;
+
+impl PrimeField for Z65521 {
+    const ORDER: usize = 65521;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 62 bit prime
+    type Large = Zp<4611686018427387847>;
+    /// The largest prime that fits in a u64
+    type Max = Zp<18446744073709551557>;
+
+    #[test]
+    fn zp_large_mul_inv() {
+        for &n in [
+            1,
+            2,
+            3,
+            1234567890123456789,
+            4611686018427387000,
+            4611686018427387846,
+        ]
+            .iter()
+        {
+            let a = Large { inner: n };
+            assert_eq!(a * a.mul_inv(), Large::one());
+        }
+    }
+
+    #[test]
+    fn zp_bytes_round_trip() {
+        for &n in [0, 1, 255, 256, 65520].iter() {
+            let x = Zp::<65521>::from(n);
+            assert_eq!(x.to_bytes().len(), 2);
+            assert_eq!(Zp::<65521>::from_bytes(&x.to_bytes()), Some(x));
+        }
+        assert_eq!(Zp::<65521>::from_bytes(&[0xff, 0xf1]), None);
+        assert_eq!(Zp::<65521>::from_bytes(&[1]), None);
+
+        let x = -Max::one();
+        assert_eq!(x.to_bytes(), x.inner.to_be_bytes().to_vec());
+        assert_eq!(Max::from_bytes(&[0xff; 8]), None);
+    }
+
+    #[test]
+    fn zp_try_from_str() {
+        assert_eq!(Max::try_from("18446744073709551556"), Ok(Max { inner: 18446744073709551556 }));
+        assert_eq!(
+            Max::try_from("18446744073709551557"),
+            Err(FieldParseErr::OutOfRange {
+                value: "18446744073709551557".to_string(),
+                field: "Zp<18446744073709551557>".to_string()
+            })
+        );
+        // Too large for a u64 is still out of range rather than invalid
+        assert_eq!(
+            Max::try_from("100000000000000000000"),
+            Err(FieldParseErr::OutOfRange {
+                value: "100000000000000000000".to_string(),
+                field: "Zp<18446744073709551557>".to_string()
+            })
+        );
+        assert_eq!(Large::try_from("0x10"), Err(FieldParseErr::Invalid("0x10".to_string())));
+    }
+
+    #[test]
+    fn zp_max_no_overflow() {
+        let a = Max { inner: 18446744073709551556 };
+        let b = Max { inner: 18446744073709551555 };
+
+        // (-1) + (-2) = -3
+        assert_eq!(a + b, Max { inner: 18446744073709551554 });
+        // (-1) * (-2) = 2
+        assert_eq!(a * b, Max { inner: 2 });
+        assert_eq!(a * a.mul_inv(), Max::one());
+        assert_eq!(b * b.mul_inv(), Max::one());
+        assert_eq!(a - b, Max::one());
+    }
+
+    #[test]
+    fn z65521_default_multiplicative_generator() {
+        let g = Z65521::multiplicative_generator();
+        assert_eq!(g, Z65521::from(17));
+
+        // 65520 = 2^4 * 3^2 * 5 * 7 * 13
+        for &q in [2, 3, 5, 7, 13].iter() {
+            assert_ne!(g.pow(65520 / q), Z65521::one());
+        }
+        assert_eq!(g.pow(65520), Z65521::one());
+    }
+
+    #[test]
+    fn zp_reduce_matches_remainder() {
+        fn check<const P: u64>() {
+            for &wide in [
+                0,
+                1,
+                P as u128 - 1,
+                P as u128,
+                (P as u128 - 1) * (P as u128 - 1),
+                u64::MAX as u128,
+                u128::MAX / 3,
+                u128::MAX,
+            ]
+                .iter()
+            {
+                assert_eq!(Zp::<P>::reduce(wide) as u128, wide % (P as u128));
+            }
+        }
+
+        check::<2>();
+        check::<251>();
+        check::<65521>();
+        check::<4611686018427387847>();
+        check::<18446744073709551557>();
+    }
+
+    #[test]
+    fn zp_small_matches_z251() {
+        use super::super::z251::Z251;
+
+        for i in 0..251 {
+            for j in 0..251 {
+                let (a, b) = (Zp::<251>::from(i), Zp::<251>::from(j));
+                let (x, y) = (Z251::from(i), Z251::from(j));
+
+                assert_eq!((a + b).inner, (x + y).inner as u64);
+                assert_eq!((a * b).inner, (x * y).inner as u64);
+            }
+        }
+    }
+}
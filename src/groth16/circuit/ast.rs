@@ -1,3 +1,4 @@
+use super::super::super::field::Field;
 use std::collections::HashMap;
 use std::str::FromStr;
 
@@ -30,6 +31,10 @@ pub enum Expression<T> {
     Assign(Box<Expression<T>>, Box<Expression<T>>),
     Mul(Box<Expression<T>>, Box<Expression<T>>),
     Add(Vec<Expression<T>>),
+    /// `(- a)` is the additive inverse of `a` and `(- a b ...)` is `a - b -
+    /// ...`. These are removed by `fold_constants` before compiling.
+    Sub(Vec<Expression<T>>),
+    Hash(Vec<Expression<T>>),
     Var(String),
     Literal(T),
 }
@@ -43,6 +48,8 @@ pub enum Key {
     Equal,
     Mul,
     Add,
+    Sub,
+    Hash,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -91,7 +98,7 @@ where
     let mut expressions = Vec::new();
 
     loop {
-        let group = next_group(group_iter);
+        let group = next_group(group_iter)?;
         if group.tokens.len() == 0 {
             break;
         }
@@ -103,6 +110,111 @@ where
     Ok(expressions)
 }
 
+/// The same as `expressions` but with `fold_constants` applied to each.
+pub fn folded_expressions<F>(code: &str) -> Result<Vec<Expression<F>>, ParseErr>
+where
+    F: Field + FromStr,
+{
+    Ok(expressions(code)?
+        .into_iter()
+        .map(fold_constants)
+        .collect())
+}
+
+/// Rewrites subtraction and negation as sums of negated terms, and folds the
+/// constant terms of each sum into a single literal, computed in the field.
+/// Over `Z251`, `(+ (- 3 5) x)` becomes `(+ 249 x)`. The multiplication of a
+/// gate itself is never folded, since each gate must stay a product.
+pub fn fold_constants<T>(expression: Expression<T>) -> Expression<T>
+where
+    T: Field,
+{
+    use self::Expression::*;
+
+    match expression {
+        Program(gates) => Program(gates.into_iter().map(fold_constants).collect()),
+        Assign(left, right) => {
+            let right = match *right {
+                Mul(l, r) => Mul(Box::new(fold_constants(*l)), Box::new(fold_constants(*r))),
+                other => fold_constants(other),
+            };
+            Assign(left, Box::new(right))
+        }
+        Mul(left, right) => match (fold_constants(*left), fold_constants(*right)) {
+            (Literal(l), Literal(r)) => Literal(l * r),
+            (l, r) => Mul(Box::new(l), Box::new(r)),
+        },
+        Sub(mut terms) => {
+            if terms.len() == 1 {
+                return negate(fold_constants(terms.remove(0)));
+            }
+
+            let mut terms = terms.into_iter();
+            let first = terms.next();
+            fold_constants(Add(first.into_iter().chain(terms.map(negate)).collect()))
+        }
+        Add(terms) => {
+            // Nested sums are flattened so that all of their constants meet
+            let terms = terms
+                .into_iter()
+                .map(fold_constants)
+                .flat_map(|t| match t {
+                    Add(inner) => inner,
+                    t => vec![t],
+                });
+
+            // The folded constant takes the place of the first one
+            let mut folded = Vec::new();
+            let mut constant = None;
+            for term in terms {
+                match (term, constant) {
+                    (Literal(l), None) => {
+                        constant = Some(folded.len());
+                        folded.push(Literal(l));
+                    }
+                    (Literal(l), Some(i)) => {
+                        if let Literal(ref mut c) = folded[i] {
+                            *c = *c + l;
+                        }
+                    }
+                    (term, _) => folded.push(term),
+                }
+            }
+
+            Add(folded)
+        }
+        other => other,
+    }
+}
+
+/// The additive inverse of an already folded `expression`. Anything but a
+/// literal is negated as a sum, such as `(+ (* -1 x))` for a variable, so
+/// that it can still be an operand of a gate.
+fn negate<T>(expression: Expression<T>) -> Expression<T>
+where
+    T: Field,
+{
+    use self::Expression::*;
+
+    match expression {
+        Literal(l) => Literal(-l),
+        Add(terms) => Add(
+            terms
+                .into_iter()
+                .flat_map(|t| match negate(t) {
+                    Add(inner) => inner,
+                    t => vec![t],
+                }).collect(),
+        ),
+        Mul(left, right) => match *left {
+            Literal(l) => Add(vec![Mul(Box::new(Literal(-l)), right)]),
+            left => Add(vec![Mul(Box::new(negate(left)), right)]),
+        },
+        Sub(terms) => negate(fold_constants(Sub(terms))),
+        other => Add(vec![Mul(Box::new(Literal(-T::one())), Box::new(other))]),
+    }
+}
+
 fn parse_expression<T>(token_list: TokenList<T>) -> Result<Expression<T>, ParseErr> {
     use self::Key::*;
     use self::ParseErr::StructureErr;
@@ -119,7 +231,7 @@ fn parse_expression<T>(token_list: TokenList<T>) -> Result<Expression<T>, ParseE
 
                 for token in iter {
                     if let Var(v) = token {
-                        vars.push(Expression::Var(v));
+                        vars.extend(expand_array(v)?.into_iter().map(Expression::Var));
                     } else {
                         return Err(StructureErr(
                             None,
@@ -166,7 +278,7 @@ fn parse_expression<T>(token_list: TokenList<T>) -> Result<Expression<T>, ParseE
                 let mut gates = Vec::new();
 
                 loop {
-                    let group = next_group(iter);
+                    let group = next_group(iter)?;
                     if group.tokens.len() == 0 {
                         break;
                     }
@@ -178,7 +290,7 @@ fn parse_expression<T>(token_list: TokenList<T>) -> Result<Expression<T>, ParseE
                 Ok(Expression::Program(gates))
             }
             Equal => {
-                let left = next_group(iter);
+                let left = next_group(iter)?;
                 if left.tokens.len() != 1 {
                     return Err(StructureErr(
                         None,
@@ -195,13 +307,13 @@ fn parse_expression<T>(token_list: TokenList<T>) -> Result<Expression<T>, ParseE
                     }
                 };
 
-                let right = parse_expression(next_group(iter))?;
+                let right = parse_expression(next_group(iter)?)?;
 
                 Ok(Expression::Assign(Box::new(left), Box::new(right)))
             }
             Mul => {
-                let left = parse_expression(next_group(iter))?;
-                let right = parse_expression(next_group(iter))?;
+                let left = parse_expression(next_group(iter)?)?;
+                let right = parse_expression(next_group(iter)?)?;
 
                 Ok(Expression::Mul(Box::new(left), Box::new(right)))
             }
@@ -209,7 +321,7 @@ fn parse_expression<T>(token_list: TokenList<T>) -> Result<Expression<T>, ParseE
                 let mut expressions = Vec::new();
 
                 loop {
-                    let group = next_group(iter);
+                    let group = next_group(iter)?;
                     if group.tokens.len() == 0 {
                         break;
                     }
@@ -220,6 +332,44 @@ fn parse_expression<T>(token_list: TokenList<T>) -> Result<Expression<T>, ParseE
 
                 Ok(Expression::Add(expressions))
             }
+            Sub => {
+                let mut expressions = Vec::new();
+
+                loop {
+                    let group = next_group(iter)?;
+                    if group.tokens.len() == 0 {
+                        break;
+                    }
+
+                    let exp = parse_expression(group)?;
+                    expressions.push(exp);
+                }
+
+                if expressions.is_empty() {
+                    return Err(StructureErr(
+                        None,
+                        "'-' needs at least one argument".to_string(),
+                    ));
+                }
+
+                Ok(Expression::Sub(expressions))
+            }
+            Hash => {
+                let mut vars = Vec::new();
+
+                for token in iter {
+                    if let Var(v) = token {
+                        vars.push(Expression::Var(v));
+                    } else {
+                        return Err(StructureErr(
+                            None,
+                            "Non variable found in 'hash' expression".to_string(),
+                        ));
+                    }
+                }
+
+                Ok(Expression::Hash(vars))
+            }
         },
         Some(Var(v)) => Ok(Expression::Var(v)),
         Some(Literal(l)) => Ok(Expression::Literal(l)),
@@ -227,11 +377,58 @@ fn parse_expression<T>(token_list: TokenList<T>) -> Result<Expression<T>, ParseE
     }
 }
 
-pub fn next_group<I, T>(token_iter: &mut I) -> TokenList<T>
+/// The largest array that can be declared with `name[n]`, so that a
+/// malformed program cannot make the parser allocate without bound.
+const MAX_ARRAY_SIZE: usize = 1 << 16;
+
+/// An input declared as `name[n]` is an array of `n` inputs, named
+/// `name[0]` up to `name[n - 1]` so that they can be used as if indexed. Any
+/// other variable is left as it is. An array must have between 1 and
+/// `MAX_ARRAY_SIZE` inputs.
+fn expand_array(var: String) -> Result<Vec<String>, ParseErr> {
+    use self::ParseErr::StructureErr;
+
+    if !var.ends_with(']') {
+        return Ok(vec![var]);
+    }
+
+    let (name, size) = match var.find('[') {
+        Some(open) if open > 0 => (&var[..open], &var[open + 1..var.len() - 1]),
+        _ => {
+            return Err(StructureErr(
+                None,
+                format!("Malformed array declaration '{}'", var),
+            ))
+        }
+    };
+    let size = size.parse::<usize>().map_err(|_| {
+        StructureErr(
+            None,
+            format!("Array size of '{}' must be a number", var),
+        )
+    })?;
+    if size == 0 || size > MAX_ARRAY_SIZE {
+        return Err(StructureErr(
+            None,
+            format!(
+                "Array size of '{}' must be between 1 and {}",
+                var, MAX_ARRAY_SIZE
+            ),
+        ));
+    }
+
+    Ok((0..size).map(|i| format!("{}[{}]", name, i)).collect())
+}
+
+/// Takes the next group of tokens, which is either a single variable or
+/// literal, or everything inside the next pair of parentheses. An empty
+/// group means there are no tokens left.
+pub fn next_group<I, T>(token_iter: &mut I) -> Result<TokenList<T>, ParseErr>
 where
     I: Iterator<Item = Token<T>>,
 {
     use self::ParenCase::*;
+    use self::ParseErr::StructureErr;
     use self::Token::*;
 
     let mut depth = 0;
@@ -239,7 +436,7 @@ where
     match token_iter.next() {
         Some(Parenthesis(Open)) => {
             depth += 1;
-            token_iter
+            Ok(token_iter
                 .map(|t| {
                     match t {
                         Parenthesis(Open) => depth += 1,
@@ -251,12 +448,15 @@ where
                 .take_while(|&(_, d)| d != 0)
                 .map(|(t, _)| t)
                 .collect::<Vec<_>>()
-                .into()
+                .into())
         }
-        Some(v @ Var(_)) => vec![v].into(),
-        Some(l @ Literal(_)) => vec![l].into(),
-        None => vec![].into(),
-        _ => panic!("Cannot parse malformed group"),
+        Some(v @ Var(_)) => Ok(vec![v].into()),
+        Some(l @ Literal(_)) => Ok(vec![l].into()),
+        None => Ok(vec![].into()),
+        _ => Err(StructureErr(
+            None,
+            "Expected a '(', variable or literal".to_string(),
+        )),
     }
 }
 
@@ -332,6 +532,17 @@ where
         "=" => tokens.push(Keyword(Equal)),
         "*" => tokens.push(Keyword(Mul)),
         "+" => tokens.push(Keyword(Add)),
+        "-" => tokens.push(Keyword(Sub)),
+        "hash" => tokens.push(Keyword(Hash)),
+        _ if substr.starts_with('-') && substr[1..].starts_with(char::is_numeric) => {
+            // A negative literal is shorthand for negating it, `(- n)`
+            let mut literal = parse_token(&substr[1..])?;
+            literal.insert(1, Parenthesis(Close));
+
+            tokens.push(Parenthesis(Open));
+            tokens.push(Keyword(Sub));
+            tokens.append(&mut literal);
+        }
         _ => {
             if substr.contains("(") {
                 return Err(TokenErr("unexpected '('".to_string()));
@@ -344,16 +555,14 @@ where
                 return Err(TokenErr("unexpected ')'".to_string()));
             }
 
-            // It is safe to unwrap because substr.len() >= 1
-            let first = start.chars().nth(0).unwrap();
-
-            if first.is_numeric() {
-                match start.parse::<T>() {
+            // start is only empty when the substring is all ')'
+            match start.chars().nth(0) {
+                Some(first) if first.is_numeric() => match start.parse::<T>() {
                     Ok(n) => tokens.push(Literal(n)),
                     _ => return Err(TokenErr("could not parse literal".to_string())),
-                }
-            } else {
-                tokens.push(Var(start.to_owned()));
+                },
+                Some(_) => tokens.push(Var(start.to_owned())),
+                None => (),
             }
 
             for c in end.chars() {
@@ -391,6 +600,75 @@ mod tests {
         assert_eq!(split_at_char(s, ')'), ("variable", ")))"));
     }
 
+    #[test]
+    fn fold_constants_test() {
+        use self::Expression::*;
+
+        let code = "(in x)
+                    (out y)
+                    (verify x y)
+                    (program
+                        (= y
+                            (* 1 (+ (- 3 5) x))))";
+
+        let program = folded_expressions::<Z251>(code).unwrap().pop().unwrap();
+        let sum = Add(vec![Literal(249.into()), Var("x".to_string())]);
+        assert_eq!(
+            program,
+            Program(vec![Assign(
+                Box::new(Var("y".to_string())),
+                Box::new(Mul(Box::new(Literal(1.into())), Box::new(sum)))
+            )])
+        );
+
+        // y = 3 - 5 + x = 8 when x = 10
+        let weights = super::super::weights(code, &[Z251::from(10)]).unwrap();
+        assert!(weights.contains(&Z251::from(8)));
+
+        // Negation distributes over sums and negative literals are negated
+        let folded = fold_constants::<Z251>(Sub(vec![
+            Literal(6.into()),
+            Add(vec![Literal(2.into()), Var("a".to_string())]),
+            Sub(vec![Literal(1.into())]),
+        ]));
+        assert_eq!(
+            folded,
+            Add(vec![
+                Literal(5.into()),
+                Mul(Box::new(Literal(250.into())), Box::new(Var("a".to_string()))),
+            ])
+        );
+    }
+
+    #[test]
+    fn fold_constants_gate_test() {
+        // The product of a gate is not folded even when both sides are
+        // constants
+        let code = "(in x)
+                    (out y z)
+                    (verify x y z)
+                    (program
+                        (= y
+                            (* 2 3))
+                        (= z
+                            (* x y)))";
+
+        let weights = super::super::weights(code, &[Z251::from(2)]).unwrap();
+        assert!(weights.contains(&Z251::from(6)));
+        assert!(weights.contains(&Z251::from(12)));
+
+        // A negated variable is a sum, so it can be the operand of a gate
+        let code = "(in x y)
+                    (out z)
+                    (verify x y z)
+                    (program
+                        (= z
+                            (* (- x) y)))";
+
+        let weights = super::super::weights(code, &[Z251::from(2), Z251::from(3)]).unwrap();
+        assert!(weights.contains(&Z251::from(245)));
+    }
+
     #[test]
     fn parse_token_test() {
         use self::Key::*;
@@ -434,6 +712,22 @@ mod tests {
             parse_token::<Z251>(substr),
             Ok(vec![Parenthesis(Open), Keyword(Add)])
         );
+        let substr = "(-";
+        assert_eq!(
+            parse_token::<Z251>(substr),
+            Ok(vec![Parenthesis(Open), Keyword(Sub)])
+        );
+        let substr = "-2)";
+        assert_eq!(
+            parse_token::<Z251>(substr),
+            Ok(vec![
+                Parenthesis(Open),
+                Keyword(Sub),
+                Literal(2.into()),
+                Parenthesis(Close),
+                Parenthesis(Close),
+            ])
+        );
         let substr = "x";
         assert_eq!(parse_token::<Z251>(substr), Ok(vec![Var("x".to_string())]));
         let substr = "y)";
@@ -603,6 +897,24 @@ mod tests {
         assert_eq!(Ok(expected), actual);
     }
 
+    #[test]
+    fn expand_array_test() {
+        assert_eq!(expand_array("x".to_string()), Ok(vec!["x".to_string()]));
+        assert_eq!(
+            expand_array("bits[3]".to_string()),
+            Ok(vec![
+                "bits[0]".to_string(),
+                "bits[1]".to_string(),
+                "bits[2]".to_string(),
+            ])
+        );
+        assert!(expand_array("bits[n]".to_string()).is_err());
+        assert!(expand_array("[3]".to_string()).is_err());
+        assert!(expand_array("bits[0]".to_string()).is_err());
+        assert!(expand_array("bits[99999999999]".to_string()).is_err());
+        assert_eq!(expand_array("bits[65536]".to_string()).map(|v| v.len()), Ok(65536));
+    }
+
     #[test]
     fn next_group_test() {
         use self::Token::*;
@@ -610,14 +922,14 @@ mod tests {
         let s = "(in x y)";
         let t_list = try_to_list::<Z251>(s.to_string()).unwrap();
         let inner_t_list = try_to_list::<Z251>("in x y".to_string()).unwrap();
-        assert_eq!(inner_t_list, next_group(&mut t_list.clone().into_iter()));
+        assert_eq!(Ok(inner_t_list), next_group(&mut t_list.clone().into_iter()));
 
         let s = "y (* 1 (+ t2 c)))";
         let t_list = try_to_list::<Z251>(s.to_string()).unwrap();
         let inner_t_list = try_to_list::<Z251>("* 1 (+ t2 c)".to_string()).unwrap();
         let mut iter = t_list.clone().into_iter();
-        assert_eq!(next_group(iter.by_ref()), vec![Var("y".to_string())].into());
-        assert_eq!(next_group(iter.by_ref()), inner_t_list);
+        assert_eq!(next_group(iter.by_ref()), Ok(vec![Var("y".to_string())].into()));
+        assert_eq!(next_group(iter.by_ref()), Ok(inner_t_list));
     }
 
     #[test]
@@ -638,7 +950,7 @@ mod tests {
         let token_list = try_to_list::<Z251>(code.to_string()).unwrap();
         let iter = &mut token_list.into_iter();
 
-        let actual = parse_expression(next_group(iter)).unwrap();
+        let actual = parse_expression(next_group(iter).unwrap()).unwrap();
         let expected: Expression<Z251> = In(vec![
             Var("x".to_string()),
             Var("a".to_string()),
@@ -647,15 +959,15 @@ mod tests {
         ]);
         assert_eq!(actual, expected);
 
-        let actual = parse_expression(next_group(iter)).unwrap();
+        let actual = parse_expression(next_group(iter).unwrap()).unwrap();
         let expected: Expression<Z251> = Out(vec![Var("y".to_string())]);
         assert_eq!(actual, expected);
 
-        let actual = parse_expression(next_group(iter)).unwrap();
+        let actual = parse_expression(next_group(iter).unwrap()).unwrap();
         let expected: Expression<Z251> = Verify(vec![Var("x".to_string()), Var("y".to_string())]);
         assert_eq!(actual, expected);
 
-        let actual = parse_expression(next_group(iter)).unwrap();
+        let actual = parse_expression(next_group(iter).unwrap()).unwrap();
         let expected: Expression<Z251> = Program(vec![
             Assign(
                 Box::new(Var("t1".to_string())),
@@ -0,0 +1,121 @@
+use super::*;
+
+/// The wires that a `Gadget` was synthesized onto. `inputs` are the fresh
+/// wires that the caller must assign before evaluating, and `outputs` are the
+/// wires carrying the result.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GadgetWires {
+    pub inputs: Vec<WireId>,
+    pub outputs: Vec<WireId>,
+}
+
+/// A reusable piece of circuit with a fixed public interface. Gadgets are
+/// synthesized into an existing `Circuit`, so they compose with each other and
+/// with any hand written sub circuits.
+///
+/// ```
+/// use zksnark::field::z251::Z251;
+/// use zksnark::field::*;
+/// use zksnark::groth16::circuit::*;
+///
+/// let mut circuit = Circuit::<Z251>::new();
+/// let wires = OrGadget.synthesize(&mut circuit);
+/// assert_eq!(wires.inputs.len(), Gadget::<Z251>::num_inputs(&OrGadget));
+///
+/// circuit.set_value(wires.inputs[0], Z251::zero());
+/// circuit.set_value(wires.inputs[1], Z251::one());
+/// assert_eq!(circuit.evaluate(wires.outputs[0]), Z251::one());
+/// ```
+pub trait Gadget<T>
+where
+    T: Field,
+{
+    /// The number of input wires created by `synthesize`.
+    fn num_inputs(&self) -> usize;
+    /// The number of output wires returned by `synthesize`.
+    fn num_outputs(&self) -> usize;
+
+    fn synthesize(&self, cs: &mut Circuit<T>) -> GadgetWires;
+}
+
+/// The OR of two bits.
+///
+/// Requires that both inputs are either 0 or 1
+pub struct OrGadget;
+
+impl<T> Gadget<T> for OrGadget
+where
+    T: Field,
+{
+    fn num_inputs(&self) -> usize {
+        2
+    }
+    fn num_outputs(&self) -> usize {
+        1
+    }
+
+    fn synthesize(&self, cs: &mut Circuit<T>) -> GadgetWires {
+        let (lhs, rhs) = (cs.new_wire(), cs.new_wire());
+        let or = cs.new_or(lhs, rhs);
+
+        GadgetWires {
+            inputs: vec![lhs, rhs],
+            outputs: vec![or],
+        }
+    }
+}
+
+/// Compares two `Word8`, the output is one iff the left input is greater
+/// than the right. The first 8 inputs are the bits of the left `Word8` and
+/// the last 8 are the bits of the right.
+pub struct GreaterThanGadget;
+
+impl<T> Gadget<T> for GreaterThanGadget
+where
+    T: Field,
+{
+    fn num_inputs(&self) -> usize {
+        16
+    }
+    fn num_outputs(&self) -> usize {
+        1
+    }
+
+    fn synthesize(&self, cs: &mut Circuit<T>) -> GadgetWires {
+        let (left, right) = (cs.new_word8(), cs.new_word8());
+        let cmp = cs.greater_than(&left, &right);
+
+        GadgetWires {
+            inputs: flatten_word8(&[left, right]),
+            outputs: vec![cmp],
+        }
+    }
+}
+
+/// The keccak256 hash of a fixed number of bytes. The inputs are the bits of
+/// each byte in order and the outputs are the 256 bits of the hash.
+pub struct Keccak256Gadget {
+    pub bytes: usize,
+}
+
+impl<T> Gadget<T> for Keccak256Gadget
+where
+    T: Field,
+{
+    fn num_inputs(&self) -> usize {
+        self.bytes * 8
+    }
+    fn num_outputs(&self) -> usize {
+        256
+    }
+
+    fn synthesize(&self, cs: &mut Circuit<T>) -> GadgetWires {
+        let input = cs.new_word8_vec(self.bytes);
+        let hash = cs.keccak256(&input);
+
+        GadgetWires {
+            inputs: flatten_word8(&input),
+            outputs: flatten_word8(&hash),
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use super::*;
+
+/// A hash function that can be built into a `Circuit`. This is what the
+/// `hash` builtin of a .zk program is compiled to, see
+/// `ASTParser::to_circuit_with_hash`.
+pub trait HashFamily<T>
+where
+    T: Field,
+{
+    /// Adds the hash of `inputs` to the circuit and returns the wire that
+    /// carries the digest.
+    fn hash(&self, cs: &mut Circuit<T>, inputs: &[WireId]) -> WireId;
+}
+
+/// keccak256 of the input bits, which are grouped into bytes in order, least
+/// significant bit first. The number of inputs must be a multiple of 8.
+///
+/// The 256 bits of the digest are packed into a single field element as
+/// `sum(2^i * bit_i)`, so in a field smaller than 2^256 the digest is reduced
+/// modulo the order of the field.
+///
+/// Requires that all of the input wires are either 0 or 1
+pub struct Keccak256Hash;
+
+impl<T> HashFamily<T> for Keccak256Hash
+where
+    T: Field,
+{
+    fn hash(&self, cs: &mut Circuit<T>, inputs: &[WireId]) -> WireId {
+        assert!(
+            inputs.len() % 8 == 0,
+            "Keccak256Hash: the number of input bits must be a multiple of 8"
+        );
+
+        let bytes = inputs
+            .chunks(8)
+            .map(|byte| byte.iter().collect::<Word8>())
+            .collect::<Vec<_>>();
+        let digest = cs.keccak256(&bytes);
+        pack_digest(cs, &digest)
+    }
+}
+
+/// SHA-256 of the input bits, grouped into bytes in the same way as
+/// `Keccak256Hash` and packed into a single field element in the same way.
+///
+/// The additions of SHA-256 are range checked through `zero_checks`, so the
+/// digest only means something in a proof where all of `zero_checks` are
+/// verified to be zero. The field must have more than `2^33` elements.
+///
+/// Requires that all of the input wires are either 0 or 1
+pub struct Sha256Hash;
+
+impl<T> HashFamily<T> for Sha256Hash
+where
+    T: Field,
+{
+    fn hash(&self, cs: &mut Circuit<T>, inputs: &[WireId]) -> WireId {
+        assert!(
+            inputs.len() % 8 == 0,
+            "Sha256Hash: the number of input bits must be a multiple of 8"
+        );
+
+        let bytes = inputs
+            .chunks(8)
+            .map(|byte| byte.iter().collect::<Word8>())
+            .collect::<Vec<_>>();
+        let digest = cs.sha256(&bytes);
+        pack_digest(cs, &digest)
+    }
+}
+
+/// Packs the bits of `digest` into one wire as `sum(2^i * bit_i)`.
+fn pack_digest<T>(cs: &mut Circuit<T>, digest: &[Word8]) -> WireId
+where
+    T: Field,
+{
+    let mut weight = T::one();
+    let packed = flatten_word8(digest)
+        .into_iter()
+        .map(|bit| {
+            let term = (weight, bit);
+            weight = weight + weight;
+            term
+        }).collect::<Vec<_>>();
+
+    cs.new_sub_circuit(packed, vec![(T::one(), cs.unity_wire())])
+}
+
+/// The MiMC block cipher with exponent `e`, used in Miyaguchi-Preneel mode so
+/// that each input field element is absorbed as
+///
+/// `h' = E_h(x) + h + x` where `E_k(x)` raises `x + k + c_i` to the power `e`
+/// for each round constant `c_i` and then adds `k`.
+///
+/// The cipher is only a permutation when `e` is coprime to `p - 1` for the
+/// order `p` of the field. That holds for 3 in `Z251`, but 3 divides `r - 1`
+/// for the field of `FrLocal`, which needs 5 instead.
+///
+/// The round constants are `c_i = i^3`, which is fine for testing but they are
+/// not from any standard parameter set.
+pub struct MiMCHash<T> {
+    pub round_constants: Vec<T>,
+    pub exponent: usize,
+}
+
+impl<T> MiMCHash<T>
+where
+    T: Field + From<usize>,
+{
+    /// MiMC with exponent 3 and `rounds` rounds.
+    pub fn new(rounds: usize) -> Self {
+        Self::with_exponent(rounds, 3)
+    }
+
+    /// MiMC with the given exponent, which must be at least 3.
+    pub fn with_exponent(rounds: usize, exponent: usize) -> Self {
+        assert!(exponent >= 3, "MiMCHash: the exponent must be at least 3");
+
+        MiMCHash {
+            round_constants: (0..rounds)
+                .map(|i| {
+                    let c = T::from(i);
+                    c * c * c
+                }).collect(),
+            exponent,
+        }
+    }
+
+    /// MiMC with the given exponent and the usual `ceil(log_e(p))` rounds
+    /// for a field whose order `p` has `field_bits` bits.
+    pub fn for_field(exponent: usize, field_bits: usize) -> Self {
+        let rounds = (field_bits as f64 / (exponent as f64).log2()).ceil() as usize;
+        Self::with_exponent(rounds, exponent)
+    }
+}
+
+impl<T> MiMCHash<T>
+where
+    T: Field,
+{
+    /// The same hash as the one `hash` builds into a circuit, computed
+    /// directly from the values of the inputs.
+    pub fn hash_values(&self, inputs: &[T]) -> T {
+        inputs.iter().fold(T::zero(), |key, &input| {
+            let cipher = self.round_constants.iter().fold(input, |x, &c| {
+                let t = x + key + c;
+                (1..self.exponent).fold(t, |power, _| power * t)
+            });
+
+            cipher + key + key + input
+        })
+    }
+}
+
+impl<T> HashFamily<T> for MiMCHash<T>
+where
+    T: Field,
+{
+    fn hash(&self, cs: &mut Circuit<T>, inputs: &[WireId]) -> WireId {
+        let one = T::one();
+
+        inputs.iter().fold(cs.zero_wire(), |key, &input| {
+            let mut x = vec![(one, input)];
+
+            for &c in self.round_constants.iter() {
+                let mut t = x.clone();
+                t.push((one, key));
+                t.push((c, cs.unity_wire()));
+
+                // Square and multiply from the most significant bit of the
+                // exponent, so 3 takes two gates and 5 takes three
+                let bits = 64 - (self.exponent as u64).leading_zeros() as usize;
+                let mut power = t.clone();
+                for i in (0..bits - 1).rev() {
+                    power = vec![(one, cs.new_sub_circuit(power.clone(), power))];
+                    if (self.exponent >> i) & 1 == 1 {
+                        power = vec![(one, cs.new_sub_circuit(power, t.clone()))];
+                    }
+                }
+                x = power;
+            }
+
+            // E_k(x) + k, then the feed forward of h and x
+            x.push((one + one, key));
+            x.push((one, input));
+            cs.new_sub_circuit(x, vec![(one, cs.unity_wire())])
+        })
+    }
+}
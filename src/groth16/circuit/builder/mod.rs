@@ -1,6 +1,6 @@
 use super::super::super::field::Field;
 use itertools::EitherOrBoth::{Both, Left, Right};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::collections::VecDeque;
 use std::fmt;
 use std::ops::{BitXor, Shl};
@@ -11,9 +11,16 @@ use itertools::Itertools;
 #[cfg(test)]
 pub mod tests;
 
+pub mod gadget;
+pub use self::gadget::{Gadget, GadgetWires, GreaterThanGadget, Keccak256Gadget, OrGadget};
+
+pub mod hash;
+pub use self::hash::{HashFamily, Keccak256Hash, MiMCHash, Sha256Hash};
+
 pub mod types;
 pub use self::types::{
-    flatten_word8, Binary, BinaryInput, CanConvert, ValidateBalance, ValidateOrder, Word64, Word8,
+    flatten_word8, flatten_word8_with_order, keccak_f1600, keccak_f1600_round, Binary,
+    BinaryInput, ByteOrder, CanConvert, ValidateBalance, ValidateOrder, Word32, Word64, Word8,
 };
 
 #[derive(Clone, Copy, Debug)]
@@ -60,7 +67,141 @@ where
 
     wire_assignments: HashMap<WireId, Vec<ConnectionType<T>>>,
     sub_circuit_wires: HashMap<SubCircuitId, SubCircuitConnections<T>>,
+    sub_circuit_labels: HashMap<SubCircuitId, String>,
+    // The tags given with `with_tag` to the sub circuits made inside it,
+    // outermost first
+    sub_circuit_tags: HashMap<SubCircuitId, Vec<String>>,
     wire_values: HashMap<WireId, Option<T>>,
+
+    // Wires whose value is not the output of a sub circuit but is instead
+    // computed as the inverse of another wire, mapped to that wire
+    inverse_hints: HashMap<WireId, WireId>,
+    // Wires that hold a single bit of another wire, mapped to that wire and
+    // the position of the bit
+    bit_hints: HashMap<WireId, (WireId, usize)>,
+    // Wires that hold the quotient or remainder of another wire divided by a
+    // constant, mapped to that wire and the constant
+    div_hints: HashMap<WireId, (WireId, u64, DivPart)>,
+    // The outputs of `new_nonzero`, which must all be one
+    nonzero_checks: Vec<WireId>,
+    // The outputs of `new_poly_accumulator_check`, `new_poly_eval`,
+    // `new_range_check`, `new_constant_eq` and `new_edwards_add`, which must
+    // all be zero
+    zero_checks: Vec<WireId>,
+    // The names given to wires with `alias`
+    wire_names: HashMap<String, WireId>,
+}
+
+/// A sub circuit whose output wire does not hold the product of its left and
+/// right inputs. The label is the one given with `Circuit::set_label`, if
+/// any.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnsatisfiedConstraint {
+    pub sub_circuit: SubCircuitId,
+    pub label: Option<String>,
+}
+
+impl fmt::Display for UnsatisfiedConstraint {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self.label {
+            Some(ref label) => write!(fmt, "constraint '{}' is not satisfied", label),
+            None => write!(
+                fmt,
+                "constraint {} is not satisfied",
+                self.sub_circuit.inner_id()
+            ),
+        }
+    }
+}
+
+/// Which half of a division by a constant a wire from `Circuit::new_mod`
+/// holds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum DivPart {
+    Quotient,
+    Remainder,
+}
+
+/// A constant given to `Circuit::new_checked_constant` that does not fit in
+/// the number of bits it was meant to be checked against, or in the field.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConstantOutOfRange {
+    pub constant: u64,
+    pub bits: usize,
+}
+
+impl fmt::Display for ConstantOutOfRange {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "constant {} does not fit in {} bits",
+            self.constant, self.bits
+        )
+    }
+}
+
+/// The ways that `Circuit::validate` can find a circuit to be malformed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CircuitError {
+    /// A sub circuit has an input wire that was never made in the circuit.
+    UndefinedWire {
+        sub_circuit: SubCircuitId,
+        wire: WireId,
+    },
+    /// The output wire of a sub circuit was never made in the circuit.
+    MissingOutput { sub_circuit: SubCircuitId },
+    /// A wire is the output of more than one sub circuit, given in order.
+    MultipleWriters {
+        wire: WireId,
+        sub_circuits: Vec<SubCircuitId>,
+    },
+}
+
+impl fmt::Display for CircuitError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CircuitError::UndefinedWire { sub_circuit, wire } => write!(
+                fmt,
+                "constraint {} uses wire {:?} which is not in the circuit",
+                sub_circuit.inner_id(),
+                wire
+            ),
+            CircuitError::MissingOutput { sub_circuit } => write!(
+                fmt,
+                "the output of constraint {} is not in the circuit",
+                sub_circuit.inner_id()
+            ),
+            CircuitError::MultipleWriters {
+                wire,
+                ref sub_circuits,
+            } => write!(
+                fmt,
+                "wire {:?} is the output of {} constraints",
+                wire,
+                sub_circuits.len()
+            ),
+        }
+    }
+}
+
+/// The ways that `Circuit::alias` can fail.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AliasErr {
+    /// The wire is not a wire of the circuit.
+    UnknownWire(WireId),
+    /// The name is already given to another wire.
+    NameTaken { name: String, wire: WireId },
+}
+
+impl fmt::Display for AliasErr {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AliasErr::UnknownWire(wire) => write!(fmt, "wire {:?} is not in the circuit", wire),
+            AliasErr::NameTaken { ref name, wire } => {
+                write!(fmt, "'{}' is already the name of wire {:?}", name, wire)
+            }
+        }
+    }
 }
 
 /// This is used internally in circuit bulider.
@@ -98,7 +239,15 @@ where
             next_sub_circuit_id: SubCircuitId(0),
             wire_assignments: HashMap::new(),
             sub_circuit_wires: HashMap::new(),
+            sub_circuit_labels: HashMap::new(),
+            sub_circuit_tags: HashMap::new(),
             wire_values,
+            inverse_hints: HashMap::new(),
+            bit_hints: HashMap::new(),
+            div_hints: HashMap::new(),
+            nonzero_checks: Vec::new(),
+            zero_checks: Vec::new(),
+            wire_names: HashMap::new(),
         }
     }
 
@@ -113,6 +262,13 @@ where
         WireId(1)
     }
 
+    /// The wire that always holds one, which constants in a linear
+    /// combination are weights of. This is the same wire as `unity_wire`, and
+    /// a `CircuitInstance` always puts it first, at index 0 of its weights.
+    pub fn one_wire(&self) -> WireId {
+        self.unity_wire()
+    }
+
     ////////////////////////////////////////////////////////////////////////////////
     /////////////////////////////// New Wire Functions /////////////////////////////
     ////////////////////////////////////////////////////////////////////////////////
@@ -189,6 +345,13 @@ where
         wrd64
     }
 
+    /// A new `Word32` placeholder, see `new_word64`.
+    pub fn new_word32(&mut self) -> Word32 {
+        let mut wrd32: Word32 = Word32::default();
+        (0..4).for_each(|x| wrd32[x] = self.new_word8());
+        wrd32
+    }
+
     /// NOTE: this is only used internally to implement Keccak
     fn initial_keccakmatrix(&mut self) -> [Word64; 25] {
         [Word64::default(); 25]
@@ -246,6 +409,13 @@ where
         wrd64
     }
 
+    /// A `Word32` of constant wires, see `const_word64`.
+    pub fn const_word32(&mut self, input: u32) -> Word32 {
+        let mut wrd32: Word32 = Word32::default();
+        (0..4).for_each(|i| wrd32[i] = self.const_word8((input >> (8 * i)) as u8));
+        wrd32
+    }
+
     ////////////////////////////////////////////////////////////////////////////////
     ///////////////////////////// Set Wire Functions ///////////////////////////////
     ////////////////////////////////////////////////////////////////////////////////
@@ -254,6 +424,69 @@ where
         self.wire_values.insert(wire, Some(value));
     }
 
+    /// Sets the value of a wire and forgets the value of every wire that
+    /// depends on it, so that the next `evaluate` only recomputes the part of
+    /// the circuit that changed.
+    ///
+    /// ```
+    /// use zksnark::field::z251::Z251;
+    /// use zksnark::groth16::circuit::*;
+    ///
+    /// let mut circuit = Circuit::<Z251>::new();
+    /// let (a, b) = (circuit.new_wire(), circuit.new_wire());
+    /// let and = circuit.new_and(a, b);
+    ///
+    /// circuit.set_value(a, Z251::from(1));
+    /// circuit.set_value(b, Z251::from(1));
+    /// assert_eq!(circuit.evaluate(and), Z251::from(1));
+    ///
+    /// circuit.update_value(b, Z251::from(0));
+    /// assert_eq!(circuit.evaluate(and), Z251::from(0));
+    /// ```
+    pub fn update_value(&mut self, wire: WireId, value: T) {
+        use self::ConnectionType::{Left, Right};
+
+        self.set_value(wire, value);
+
+        let mut stack = vec![wire];
+        while let Some(changed) = stack.pop() {
+            let dependents = self
+                .wire_assignments
+                .get(&changed)
+                .into_iter()
+                .flat_map(|connections| connections.iter())
+                .filter_map(|c| match *c {
+                    Left(_, sc) | Right(_, sc) => Some(self.sub_circuit_wires[&sc].output),
+                    _ => None,
+                }).chain(
+                    self.inverse_hints
+                        .iter()
+                        .filter(|&(_, x)| *x == changed)
+                        .map(|(&inv, _)| inv),
+                ).chain(
+                    self.bit_hints
+                        .iter()
+                        .filter(|&(_, &(x, _))| x == changed)
+                        .map(|(&bit, _)| bit),
+                ).chain(
+                    self.div_hints
+                        .iter()
+                        .filter(|&(_, &(x, _, _))| x == changed)
+                        .map(|(&part, _)| part),
+                ).collect::<Vec<_>>();
+
+            // A wire without a value has nothing downstream that was
+            // computed from it
+            for output in dependents {
+                if let Some(value) = self.wire_values.get_mut(&output) {
+                    if value.take().is_some() {
+                        stack.push(output);
+                    }
+                }
+            }
+        }
+    }
+
     /// set the values for a `Word8` from a u8.
     ///
     /// See `new_u8` for example
@@ -280,6 +513,14 @@ where
             .for_each(|(word, &num)| self.set_word8(word, num));
     }
 
+    /// Sets the wires of a `Word32` to the bits of `input`, see `set_word64`.
+    pub fn set_word32(&mut self, u32_wires: &Word32, input: u32) {
+        u32_wires
+            .iter()
+            .enumerate()
+            .for_each(|(i, word)| self.set_word8(word, (input >> (8 * i)) as u8));
+    }
+
     ////////////////////////////////////////////////////////////////////////////////
     ///////////////////// Set and create new Wire Functions ////////////////////////
     ////////////////////////////////////////////////////////////////////////////////
@@ -474,6 +715,30 @@ where
             .expect("wire id is not defined in this circuit")
     }
 
+    /// The wires whose values are hints rather than computed by a sub
+    /// circuit, such as the inverse from `new_nonzero` or the bits from
+    /// `new_range_check`, in the order they were made. A hint is only
+    /// trusted as far as the checks on it in `check_constraints`.
+    pub fn hint_wires(&self) -> Vec<WireId> {
+        let mut hints = self
+            .inverse_hints
+            .keys()
+            .chain(self.bit_hints.keys())
+            .chain(self.div_hints.keys())
+            .cloned()
+            .collect::<Vec<_>>();
+        hints.sort_by_key(|w| w.inner_id());
+        hints
+    }
+
+    /// The wires that the gadgets of the circuit check to be zero, such as
+    /// those of `new_constant_eq` or the bit checks of `new_keccak_preimage`,
+    /// in the order they were made. For a proof to enforce them they must be
+    /// verified, with zero as their values.
+    pub fn zero_checks(&self) -> &[WireId] {
+        &self.zero_checks
+    }
+
     fn insert_connection(&mut self, wire: WireId, connection: ConnectionType<T>) {
         if self.wire_assignments.get(&wire).is_none() {
             self.wire_assignments.insert(wire, vec![connection]);
@@ -488,6 +753,401 @@ where
         (0..self.next_sub_circuit_id.0).map(|id| SubCircuitId(id))
     }
 
+    /// The sub circuit that `wire` is the output of, if there is one.
+    fn output_of(&self, wire: WireId) -> Option<SubCircuitId> {
+        use self::ConnectionType::Output;
+
+        self.wire_assignments
+            .get(&wire)?
+            .iter()
+            .filter_map(|c| if let &Output(sc) = c { Some(sc) } else { None })
+            .nth(0)
+    }
+
+    /// Attaches a label, such as the statement in the source program it came
+    /// from, to the sub circuit that `wire` is the output of. The label is
+    /// reported when that constraint is not satisfied.
+    ///
+    /// Panics if `wire` is not the output of a sub circuit.
+    pub fn set_label(&mut self, wire: WireId, label: &str) {
+        let sub_circuit = self
+            .output_of(wire)
+            .expect("set_label: wire must be the output of a sub circuit");
+        self.sub_circuit_labels
+            .insert(sub_circuit, label.to_string());
+    }
+
+    /// The label of the sub circuit that `wire` is the output of.
+    pub fn label(&self, wire: WireId) -> Option<&str> {
+        self.output_of(wire)
+            .and_then(|sc| self.sub_circuit_labels.get(&sc))
+            .map(|label| label.as_str())
+    }
+
+    /// Tags every sub circuit that `build` makes with `tag`, so that they can
+    /// be found again with `tagged_sub_circuits` or optimized on their own
+    /// with `common_subexpr_elim_tagged`. Calls may be nested, in which case
+    /// the sub circuits have every enclosing tag.
+    ///
+    /// ```
+    /// use zksnark::field::z251::Z251;
+    /// use zksnark::groth16::circuit::*;
+    ///
+    /// let mut circuit = Circuit::<Z251>::new();
+    /// let (x, y) = (circuit.new_wire(), circuit.new_wire());
+    /// let z = circuit.with_tag("xor", |c| c.new_xor(x, y));
+    /// circuit.new_and(x, z);
+    ///
+    /// assert_eq!(circuit.sub_circuits().count(), 2);
+    /// assert_eq!(circuit.tagged_sub_circuits("xor").len(), 1);
+    /// ```
+    pub fn with_tag<F, R>(&mut self, tag: &str, build: F) -> R
+    where
+        F: FnOnce(&mut Self) -> R,
+    {
+        let first = self.next_sub_circuit_id.0;
+        let result = build(self);
+        for id in first..self.next_sub_circuit_id.0 {
+            self.sub_circuit_tags
+                .entry(SubCircuitId(id))
+                .or_insert_with(Vec::new)
+                .insert(0, tag.to_string());
+        }
+
+        result
+    }
+
+    /// The sub circuits made inside `with_tag` with this tag, in order.
+    pub fn tagged_sub_circuits(&self, tag: &str) -> Vec<SubCircuitId> {
+        self.sub_circuits()
+            .filter(|sc| self.tags(*sc).iter().any(|t| t == tag))
+            .collect()
+    }
+
+    /// The tags of a sub circuit, outermost first.
+    pub fn tags(&self, sub_circuit: SubCircuitId) -> &[String] {
+        self.sub_circuit_tags
+            .get(&sub_circuit)
+            .map(|tags| tags.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// The circuit in the DOT language of Graphviz, for drawing it. Each sub
+    /// circuit is a box with its label, or `*` if it has none. Each wire is
+    /// an edge with its id, from the sub circuit it is the output of to each
+    /// sub circuit it is an input of, solid for a left input and dashed for
+    /// a right one. A wire that is not the output of any sub circuit, or not
+    /// the input of any, has a node of its own at the other end.
+    pub fn to_dot(&self) -> String {
+        use self::ConnectionType::{Left, Right};
+
+        let mut gates = Vec::new();
+        let mut edges = Vec::new();
+        let mut wire_nodes = HashSet::new();
+
+        for sub_circuit in self.sub_circuits() {
+            let id = sub_circuit.inner_id();
+            let label = self
+                .sub_circuit_labels
+                .get(&sub_circuit)
+                .map(|label| label.replace('"', "\\\""))
+                .unwrap_or_else(|| "*".to_string());
+            gates.push(format!("    g{} [shape=box, label=\"{}\"];", id, label));
+
+            let connections = &self.sub_circuit_wires[&sub_circuit];
+            let inputs = connections
+                .left_inputs
+                .iter()
+                .map(|&(_, wire)| (wire, "solid"))
+                .chain(
+                    connections
+                        .right_inputs
+                        .iter()
+                        .map(|&(_, wire)| (wire, "dashed")),
+                );
+            for (wire, style) in inputs {
+                let source = match self.output_of(wire) {
+                    Some(source) => format!("g{}", source.inner_id()),
+                    None => {
+                        wire_nodes.insert(wire);
+                        format!("w{:?}", wire)
+                    }
+                };
+                edges.push(format!(
+                    "    {} -> g{} [label=\"{:?}\", style={}];",
+                    source, id, wire, style
+                ));
+            }
+
+            let output = connections.output;
+            let is_input = self.wire_assignments[&output].iter().any(|c| match *c {
+                Left(..) | Right(..) => true,
+                _ => false,
+            });
+            if !is_input {
+                wire_nodes.insert(output);
+                edges.push(format!("    g{} -> w{:?} [label=\"{:?}\"];", id, output, output));
+            }
+        }
+
+        let mut wire_nodes = wire_nodes.into_iter().collect::<Vec<_>>();
+        wire_nodes.sort_by_key(|w| w.inner_id());
+        let wire_nodes = wire_nodes
+            .into_iter()
+            .map(|w| format!("    w{:?} [shape=plaintext];", w));
+
+        let lines = gates.into_iter().chain(wire_nodes).chain(edges).collect::<Vec<_>>();
+        format!("digraph circuit {{\n{}\n}}\n", lines.join("\n"))
+    }
+
+    /// Gives `existing` the name `name`, by which `named_output` finds it. A
+    /// wire can have any number of names, so that the same wire can be found
+    /// by the name each part of a larger circuit knows it by. Giving a wire
+    /// the same name again does nothing.
+    pub fn alias(&mut self, existing: WireId, name: &str) -> Result<(), AliasErr> {
+        if !self.wire_values.contains_key(&existing) {
+            return Err(AliasErr::UnknownWire(existing));
+        }
+
+        match self.wire_names.get(name) {
+            Some(&wire) if wire != existing => Err(AliasErr::NameTaken {
+                name: name.to_string(),
+                wire,
+            }),
+            _ => {
+                self.wire_names.insert(name.to_string(), existing);
+                Ok(())
+            }
+        }
+    }
+
+    /// The wire given the name `name` with `alias`, if there is one.
+    pub fn named_output(&self, name: &str) -> Option<WireId> {
+        self.wire_names.get(name).cloned()
+    }
+
+    /// Checks the structure of the circuit without evaluating it: that every
+    /// wire a sub circuit uses, as an input or as its output, was made in the
+    /// circuit, and that no wire is the output of more than one sub circuit.
+    /// Returns the first problem found, going through the sub circuits in
+    /// order.
+    pub fn validate(&self) -> Result<(), CircuitError> {
+        use self::ConnectionType::Output;
+
+        for sub_circuit in self.sub_circuits() {
+            let connections = &self.sub_circuit_wires[&sub_circuit];
+            let inputs = connections
+                .left_inputs
+                .iter()
+                .chain(connections.right_inputs.iter());
+
+            for &(_, wire) in inputs {
+                if !self.wire_values.contains_key(&wire) {
+                    return Err(CircuitError::UndefinedWire { sub_circuit, wire });
+                }
+            }
+            if !self.wire_values.contains_key(&connections.output) {
+                return Err(CircuitError::MissingOutput { sub_circuit });
+            }
+        }
+
+        let mut wires = self.wire_assignments.keys().cloned().collect::<Vec<_>>();
+        wires.sort_by_key(|w| w.inner_id());
+        for wire in wires {
+            let mut sub_circuits = self.wire_assignments[&wire]
+                .iter()
+                .filter_map(|c| if let &Output(sc) = c { Some(sc) } else { None })
+                .collect::<Vec<_>>();
+            if sub_circuits.len() > 1 {
+                sub_circuits.sort_by_key(|sc| sc.inner_id());
+                return Err(CircuitError::MultipleWriters { wire, sub_circuits });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the output wire of every sub circuit holds the product of
+    /// its inputs, which can only fail if an output wire was given a value
+    /// with `set_value`, and that every check added by `new_nonzero`,
+    /// `new_poly_accumulator_check`, `new_poly_eval`, `new_range_check`,
+    /// `new_constant_eq`, `new_one_hot_check` and `new_edwards_add` holds.
+    /// Returns the first constraint that does not hold.
+    pub fn check_constraints(&mut self) -> Result<(), UnsatisfiedConstraint> {
+        for sub_circuit in self.sub_circuits().collect::<Vec<_>>() {
+            let output = self.sub_circuit_wires[&sub_circuit].output;
+
+            if self.evaluate_sub_circuit(sub_circuit) != self.evaluate(output) {
+                return Err(self.unsatisfied(sub_circuit));
+            }
+        }
+
+        for check in self.nonzero_checks.clone() {
+            if self.evaluate(check) != T::one() {
+                let sub_circuit = self
+                    .output_of(check)
+                    .expect("a nonzero check is the output of a sub circuit");
+                return Err(self.unsatisfied(sub_circuit));
+            }
+        }
+
+        for check in self.zero_checks.clone() {
+            if self.evaluate(check) != T::zero() {
+                let sub_circuit = self
+                    .output_of(check)
+                    .expect("a zero check is the output of a sub circuit");
+                return Err(self.unsatisfied(sub_circuit));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn unsatisfied(&self, sub_circuit: SubCircuitId) -> UnsatisfiedConstraint {
+        UnsatisfiedConstraint {
+            sub_circuit,
+            label: self.sub_circuit_labels.get(&sub_circuit).cloned(),
+        }
+    }
+
+    /// Merges sub circuits that compute the same product, so that each is
+    /// only computed once. Two sub circuits are the same when they have the
+    /// same linear combinations as inputs, on either side, once the outputs
+    /// of earlier duplicates are replaced by the outputs they duplicate. The
+    /// sub circuits are then numbered again from zero, in the same order.
+    ///
+    /// Returns the output wires that were removed, mapped to the wires that
+    /// now hold their values. Any such wire held outside the circuit, such
+    /// as one to be verified, must be replaced by its new wire.
+    pub fn common_subexpr_elim(&mut self) -> HashMap<WireId, WireId> {
+        self.merge_duplicates(|_| true)
+    }
+
+    /// The same as `common_subexpr_elim` but only sub circuits tagged with
+    /// `tag` by `with_tag` are merged, and only with each other. Every other
+    /// sub circuit is kept as it is, apart from its inputs being replaced.
+    pub fn common_subexpr_elim_tagged(&mut self, tag: &str) -> HashMap<WireId, WireId> {
+        let tagged = self
+            .tagged_sub_circuits(tag)
+            .into_iter()
+            .collect::<HashSet<_>>();
+        self.merge_duplicates(|sc| tagged.contains(&sc))
+    }
+
+    fn merge_duplicates<F>(&mut self, can_merge: F) -> HashMap<WireId, WireId>
+    where
+        F: Fn(SubCircuitId) -> bool,
+    {
+        use self::ConnectionType::{Left, Output, Right};
+
+        let mut replaced = HashMap::new();
+        let mut kept = Vec::new();
+        // Sub circuits by the wires of their inputs, with the sides in order,
+        // so that only those with the same wires have their weights compared
+        let mut signatures: HashMap<(Vec<usize>, Vec<usize>), Vec<SubCircuitId>> =
+            HashMap::new();
+
+        for sub_circuit in self.sub_circuits().collect::<Vec<_>>() {
+            let mut connections = self
+                .sub_circuit_wires
+                .remove(&sub_circuit)
+                .expect("every sub circuit id should have connections");
+            connections.left_inputs = merge_inputs(&connections.left_inputs, &replaced);
+            connections.right_inputs = merge_inputs(&connections.right_inputs, &replaced);
+
+            let wires = |inputs: &[(T, WireId)]| {
+                inputs.iter().map(|&(_, w)| w.inner_id()).collect::<Vec<_>>()
+            };
+            let (left, right) = (
+                wires(&connections.left_inputs),
+                wires(&connections.right_inputs),
+            );
+            let signature = if left <= right { (left, right) } else { (right, left) };
+
+            if !can_merge(sub_circuit) {
+                kept.push(sub_circuit);
+                self.sub_circuit_wires.insert(sub_circuit, connections);
+                continue;
+            }
+
+            let duplicate = signatures.get(&signature).and_then(|candidates| {
+                candidates.iter().cloned().find(|id| {
+                    let other = &self.sub_circuit_wires[id];
+                    (other.left_inputs == connections.left_inputs
+                        && other.right_inputs == connections.right_inputs)
+                        || (other.left_inputs == connections.right_inputs
+                            && other.right_inputs == connections.left_inputs)
+                })
+            });
+
+            match duplicate {
+                Some(original) => {
+                    replaced.insert(connections.output, self.sub_circuit_wires[&original].output);
+                    if let Some(label) = self.sub_circuit_labels.remove(&sub_circuit) {
+                        self.sub_circuit_labels.entry(original).or_insert(label);
+                    }
+                    self.sub_circuit_tags.remove(&sub_circuit);
+                }
+                None => {
+                    signatures.entry(signature).or_insert_with(Vec::new).push(sub_circuit);
+                    kept.push(sub_circuit);
+                    self.sub_circuit_wires.insert(sub_circuit, connections);
+                }
+            }
+        }
+
+        // Number the remaining sub circuits from zero and connect them again
+        let mut sub_circuit_wires = HashMap::new();
+        let mut sub_circuit_labels = HashMap::new();
+        let mut sub_circuit_tags = HashMap::new();
+        self.wire_assignments.clear();
+        for (id, old_id) in kept.into_iter().enumerate() {
+            let sub_circuit = SubCircuitId(id);
+            let connections = self.sub_circuit_wires.remove(&old_id).unwrap();
+
+            for &(weight, wire) in connections.left_inputs.iter() {
+                self.insert_connection(wire, Left(weight, sub_circuit));
+            }
+            for &(weight, wire) in connections.right_inputs.iter() {
+                self.insert_connection(wire, Right(weight, sub_circuit));
+            }
+            self.insert_connection(connections.output, Output(sub_circuit));
+
+            sub_circuit_wires.insert(sub_circuit, connections);
+            if let Some(label) = self.sub_circuit_labels.remove(&old_id) {
+                sub_circuit_labels.insert(sub_circuit, label);
+            }
+            if let Some(tags) = self.sub_circuit_tags.remove(&old_id) {
+                sub_circuit_tags.insert(sub_circuit, tags);
+            }
+        }
+        self.next_sub_circuit_id = SubCircuitId(sub_circuit_wires.len());
+        self.sub_circuit_wires = sub_circuit_wires;
+        self.sub_circuit_labels = sub_circuit_labels;
+        self.sub_circuit_tags = sub_circuit_tags;
+
+        // Nothing else may refer to the removed wires
+        let replace = |wire: &mut WireId| {
+            if let Some(&new) = replaced.get(wire) {
+                *wire = new;
+            }
+        };
+        for wire in replaced.keys() {
+            self.wire_values.remove(wire);
+        }
+        self.inverse_hints.values_mut().for_each(&replace);
+        self.wire_names.values_mut().for_each(&replace);
+        self.bit_hints.values_mut().for_each(|&mut (ref mut w, _)| replace(w));
+        self.div_hints.values_mut().for_each(|&mut (ref mut w, _, _)| replace(w));
+        for checks in vec![&mut self.nonzero_checks, &mut self.zero_checks] {
+            checks.iter_mut().for_each(&replace);
+            let mut seen = HashSet::new();
+            checks.retain(|w| seen.insert(*w));
+        }
+
+        replaced
+    }
+
     pub fn new_sub_circuit(
         &mut self,
         left_inputs: Vec<(T, WireId)>,
@@ -557,19 +1217,46 @@ where
     }
 
     pub fn evaluate(&mut self, wire: WireId) -> T {
-        use self::ConnectionType::Output;
-
         self.wire_values
             .get(&wire)
             .expect("cannot evaluate unknown wire")
             .unwrap_or_else(|| {
+                if let Some(&x) = self.inverse_hints.get(&wire) {
+                    // Zero has no inverse so the hint is left as zero, which
+                    // makes the constraint from `new_nonzero` fail
+                    let x_value = self.evaluate(x);
+                    let value = if x_value == T::zero() {
+                        T::zero()
+                    } else {
+                        x_value.mul_inv()
+                    };
+                    self.wire_values.insert(wire, Some(value));
+
+                    return value;
+                }
+
+                if let Some(&(x, i)) = self.bit_hints.get(&wire) {
+                    let x_value = saturating_u64(self.evaluate(x));
+                    let bit = if i < 64 { (x_value >> i) & 1 } else { 0 };
+                    let value = T::from_canonical_u64(bit);
+                    self.wire_values.insert(wire, Some(value));
+
+                    return value;
+                }
+
+                if let Some(&(x, m, part)) = self.div_hints.get(&wire) {
+                    let x_value = saturating_u64(self.evaluate(x));
+                    let value = T::from_canonical_u64(match part {
+                        DivPart::Quotient => x_value / m,
+                        DivPart::Remainder => x_value % m,
+                    });
+                    self.wire_values.insert(wire, Some(value));
+
+                    return value;
+                }
+
                 let output_sub_circuit = self
-                    .wire_assignments
-                    .get(&wire)
-                    .expect("a wire must be attached to something")
-                    .into_iter()
-                    .filter_map(|c| if let &Output(sc) = c { Some(sc) } else { None })
-                    .nth(0)
+                    .output_of(wire)
                     .expect("a wire with an unknown value must be the output of a sub circuit");
 
                 let value = self.evaluate_sub_circuit(output_sub_circuit);
@@ -579,6 +1266,103 @@ where
             })
     }
 
+    /// Evaluates every sub circuit whose output has no value yet, a level at
+    /// a time with the sub circuits of a level on the rayon thread pool. The
+    /// sub circuits of a level only have inputs from earlier levels, so the
+    /// values are the same as those from `evaluate`. Hints are still computed
+    /// one at a time, before the level that needs them.
+    #[cfg(feature = "parallel")]
+    pub fn evaluate_parallel(&mut self)
+    where
+        T: Send + Sync,
+    {
+        use rayon::prelude::*;
+
+        for level in self.levels() {
+            for sub_circuit in level.iter() {
+                let connections = &self.sub_circuit_wires[sub_circuit];
+                let unknown = connections
+                    .left_inputs
+                    .iter()
+                    .chain(connections.right_inputs.iter())
+                    .map(|&(_, wire)| wire)
+                    .filter(|wire| self.wire_values[wire].is_none())
+                    .collect::<Vec<_>>();
+                for wire in unknown {
+                    self.evaluate(wire);
+                }
+            }
+
+            let circuit = &*self;
+            let values = level
+                .par_iter()
+                .map(|sub_circuit| &circuit.sub_circuit_wires[sub_circuit])
+                .filter(|connections| circuit.wire_values[&connections.output].is_none())
+                .map(|connections| {
+                    let sum = |inputs: &[(T, WireId)]| {
+                        inputs.iter().fold(T::zero(), |acc, &(weight, wire)| {
+                            acc + weight
+                                * circuit.wire_values[&wire]
+                                    .expect("the inputs of a level are evaluated before it")
+                        })
+                    };
+                    let value = sum(&connections.left_inputs) * sum(&connections.right_inputs);
+                    (connections.output, value)
+                })
+                .collect::<Vec<_>>();
+
+            for (wire, value) in values {
+                self.wire_values.insert(wire, Some(value));
+            }
+        }
+    }
+
+    /// The sub circuits grouped so that the inputs of each are outputs of
+    /// sub circuits in earlier groups, or not outputs of a sub circuit at
+    /// all. A sub circuit is made after its inputs, so one pass in order
+    /// finds the group of each.
+    #[cfg(feature = "parallel")]
+    fn levels(&self) -> Vec<Vec<SubCircuitId>> {
+        let mut depths = HashMap::new();
+        let mut levels: Vec<Vec<SubCircuitId>> = Vec::new();
+
+        for sub_circuit in self.sub_circuits() {
+            let connections = &self.sub_circuit_wires[&sub_circuit];
+            let depth = connections
+                .left_inputs
+                .iter()
+                .chain(connections.right_inputs.iter())
+                .map(|&(_, wire)| self.depth(wire, &depths))
+                .max()
+                .unwrap_or(0);
+
+            depths.insert(connections.output, depth + 1);
+            if levels.len() <= depth {
+                levels.resize(depth + 1, Vec::new());
+            }
+            levels[depth].push(sub_circuit);
+        }
+
+        levels
+    }
+
+    /// The number of levels before `wire` has a value, where a hint is
+    /// computed as soon as the wire it comes from is.
+    #[cfg(feature = "parallel")]
+    fn depth(&self, wire: WireId, depths: &HashMap<WireId, usize>) -> usize {
+        let source = self
+            .inverse_hints
+            .get(&wire)
+            .cloned()
+            .or_else(|| self.bit_hints.get(&wire).map(|&(x, _)| x))
+            .or_else(|| self.div_hints.get(&wire).map(|&(x, _, _)| x));
+
+        match source {
+            Some(x) => self.depth(x, depths),
+            None => depths.get(&wire).cloned().unwrap_or(0),
+        }
+    }
+
     /// evaluates a container with `WireId`s that can only be set with
     /// either the field's 0 or the fields's 1 as input. Two examples
     /// are `Word8` or `Word64`.
@@ -647,84 +1431,646 @@ where
             .collect()
     }
 
-    /// evaluates a container of some container that has `WireId` that
-    /// will evaluate to either 0 or 1. Just lets you pre-allocate
-    /// where the result goes.
-    ///
-    /// NOTE: If the result slice is too small it will just fill the
-    /// slice and ignore the rest of the stream.
+    /// evaluates a container of some container that has `WireId` that
+    /// will evaluate to either 0 or 1. Just lets you pre-allocate
+    /// where the result goes.
+    ///
+    /// NOTE: If the result slice is too small it will just fill the
+    /// slice and ignore the rest of the stream.
+    ///
+    /// Common example inputs are `Vec<Word8>` or `[Word64; 32]`
+    ///
+    /// ```
+    /// use zksnark::field::z251::Z251;
+    /// use zksnark::groth16::circuit::*;
+    ///
+    /// // Create an empty circuit
+    /// let mut circuit = Circuit::<Z251>::new();
+    ///
+    /// let external_input: [u64; 7] = [9, 24, 45, 250, 99, 0, 7];
+    /// let new_set_circuit_input = &mut [Word64::default(); 7];
+    /// circuit.set_new_word64_array(external_input.iter()
+    ///                             , new_set_circuit_input);
+    ///
+    /// let evaluated_circuit = &mut [0; 7];
+    /// circuit.evaluate_to_array(new_set_circuit_input.iter()
+    ///                              , evaluated_circuit);
+    ///
+    /// assert_eq!(*evaluated_circuit, external_input);
+    /// ```
+    pub fn evaluate_to_array<'a, Z, W: 'a, N>(&mut self, stream: Z, output: &'a mut [N])
+    where
+        Z: IntoIterator<Item = W>,
+        W: IntoIterator<Item = &'a WireId> + BinaryInput + CanConvert<N>,
+        N: Sized + From<u8> + BitXor<Output = N> + Shl<Output = N>,
+    {
+        stream
+            .into_iter()
+            .zip(0..output.len())
+            .for_each(|(wrd8, i)| output[i] = self.evaluate_to_num(wrd8));
+    }
+
+    // NOTE: only used internally as a convenience for testing
+    fn evaluate_keccakmatrix(&mut self, matrix: &[Word64; 25]) -> [u64; 25] {
+        let mut arr: [u64; 25] = [0; 25];
+        (0..25).for_each(|x| arr[x] = self.evaluate_to_num(&matrix[x]));
+        arr
+    }
+
+    ////////////////////////////////////////////////////////////////////////////////
+    ///////////////////////////////////// Reset  ///////////////////////////////////
+    ////////////////////////////////////////////////////////////////////////////////
+
+    /// Clears all of the stored circuit wire values (except for the zero and
+    /// unity wires) so that the same circuit can be reused for different
+    /// inputs.
+    pub fn reset(&mut self) {
+        let zero = self.zero_wire();
+        let one = self.unity_wire();
+        let values = self.wire_values.iter_mut().filter_map(|(&k, v)| {
+            if k == zero || k == one {
+                None
+            } else {
+                Some(v)
+            }
+        });
+
+        for value in values {
+            *value = None;
+        }
+    }
+
+    /// Clears the values of the wires that the circuit computes, which are
+    /// the outputs of sub circuits and the hint wires. Unlike `reset` the
+    /// value of any other wire given with `set_value`, such as a constant,
+    /// is kept.
+    pub fn reset_computed(&mut self) {
+        let computed = self
+            .sub_circuit_wires
+            .values()
+            .map(|connections| connections.output)
+            .chain(self.hint_wires())
+            .collect::<Vec<_>>();
+
+        for wire in computed {
+            if let Some(value) = self.wire_values.get_mut(&wire) {
+                *value = None;
+            }
+        }
+    }
+
+    ////////////////////////////////////////////////////////////////////////////////
+    /////////////////////// Simple Binary Wire Functions ///////////////////////////
+    ////////////////////////////////////////////////////////////////////////////////
+
+    pub fn new_bit_checker(&mut self, input: WireId) -> WireId {
+        let lhs_inputs = vec![(T::one(), input)];
+        let rhs_inputs = vec![(T::one(), input), (-T::one(), self.unity_wire())];
+
+        self.new_sub_circuit(lhs_inputs, rhs_inputs)
+    }
+
+    /// Enforces that `input` is not zero. A new wire holding the inverse of
+    /// `input` is added as a hint and the returned wire is `input * inverse`,
+    /// which is one exactly when `input` is nonzero. The returned wire should
+    /// be verified to be one, and `check_constraints` reports it (labelled
+    /// "nonzero") when it is not.
+    ///
+    /// ```
+    /// use zksnark::field::z251::Z251;
+    /// use zksnark::groth16::circuit::*;
+    ///
+    /// let mut circuit = Circuit::<Z251>::new();
+    /// let x = circuit.new_wire();
+    /// let nonzero = circuit.new_nonzero(x);
+    ///
+    /// circuit.set_value(x, Z251::from(5));
+    /// assert_eq!(circuit.evaluate(nonzero), Z251::from(1));
+    ///
+    /// circuit.reset();
+    /// circuit.set_value(x, Z251::from(0));
+    /// assert!(circuit.check_constraints().is_err());
+    /// ```
+    pub fn new_nonzero(&mut self, input: WireId) -> WireId {
+        let inverse = self.new_wire();
+        self.inverse_hints.insert(inverse, input);
+
+        let lhs_inputs = vec![(T::one(), input)];
+        let rhs_inputs = vec![(T::one(), inverse)];
+        let check = self.new_sub_circuit(lhs_inputs, rhs_inputs);
+
+        self.set_label(check, "nonzero");
+        self.nonzero_checks.push(check);
+        check
+    }
+
+    /// Checks that `element` is a member of the set accumulated in `acc`.
+    ///
+    /// A set `{a_1, ..., a_n}` is accumulated by the polynomial `A(X) = (X -
+    /// a_1)...(X - a_n)`, and the accumulator value is its constant term
+    /// `acc = A(0) = (-a_1)...(-a_n)`. The remaining coefficients of `A`,
+    /// lowest degree first, are given as `witness_poly`. The returned wire is
+    /// `A(element)`, computed with Horner's rule, which is zero exactly when
+    /// `element` is a root of `A`. `check_constraints` reports it (labelled
+    /// "poly accumulator") when it is not zero.
+    ///
+    /// ```
+    /// use zksnark::field::z251::Z251;
+    /// use zksnark::groth16::circuit::*;
+    ///
+    /// // The set {3, 4}: A(X) = X^2 - 7X + 12
+    /// let mut circuit = Circuit::<Z251>::new();
+    /// let (element, acc) = (circuit.new_wire(), circuit.new_wire());
+    /// let witness = vec![circuit.new_wire(), circuit.new_wire()];
+    /// let check = circuit.new_poly_accumulator_check(element, &witness, acc);
+    ///
+    /// circuit.set_value(acc, Z251::from(12));
+    /// circuit.set_value(witness[0], -Z251::from(7));
+    /// circuit.set_value(witness[1], Z251::from(1));
+    /// circuit.set_value(element, Z251::from(4));
+    /// assert_eq!(circuit.evaluate(check), Z251::from(0));
+    /// ```
+    pub fn new_poly_accumulator_check(
+        &mut self,
+        element: WireId,
+        witness_poly: &[WireId],
+        acc: WireId,
+    ) -> WireId {
+        let one = T::one();
+        let tail = self.horner_tail(witness_poly, element);
+
+        let lhs_inputs = match tail {
+            Some(tail) => vec![(one, tail), (one, acc)],
+            None => vec![(one, acc)],
+        };
+        let check = self.new_sub_circuit(lhs_inputs, vec![(one, self.unity_wire())]);
+
+        self.set_label(check, "poly accumulator");
+        self.zero_checks.push(check);
+        check
+    }
+
+    /// Enforces that the polynomial with coefficients `coeffs`, lowest degree
+    /// first, evaluates to `y` at `z`. The polynomial is evaluated with
+    /// Horner's rule and the returned wire is `p(z) - y`, which is zero
+    /// exactly when the evaluation is correct. `check_constraints` reports it
+    /// (labelled "poly eval") when it is not.
+    ///
+    /// ```
+    /// use zksnark::field::z251::Z251;
+    /// use zksnark::groth16::circuit::*;
+    ///
+    /// // p(X) = 2 + 3X
+    /// let mut circuit = Circuit::<Z251>::new();
+    /// let coeffs = vec![circuit.new_wire(), circuit.new_wire()];
+    /// let (z, y) = (circuit.new_wire(), circuit.new_wire());
+    /// let check = circuit.new_poly_eval(&coeffs, z, y);
+    ///
+    /// circuit.set_value(coeffs[0], Z251::from(2));
+    /// circuit.set_value(coeffs[1], Z251::from(3));
+    /// circuit.set_value(z, Z251::from(5));
+    /// circuit.set_value(y, Z251::from(17));
+    /// assert_eq!(circuit.evaluate(check), Z251::from(0));
+    /// assert!(circuit.check_constraints().is_ok());
+    /// ```
+    pub fn new_poly_eval(&mut self, coeffs: &[WireId], z: WireId, y: WireId) -> WireId {
+        let one = T::one();
+        let tail = coeffs
+            .split_first()
+            .and_then(|(_, higher)| self.horner_tail(higher, z));
+
+        let mut lhs_inputs = vec![(-one, y)];
+        lhs_inputs.extend(tail.map(|tail| (one, tail)));
+        lhs_inputs.extend(coeffs.first().map(|&constant| (one, constant)));
+        let check = self.new_sub_circuit(lhs_inputs, vec![(one, self.unity_wire())]);
+
+        self.set_label(check, "poly eval");
+        self.zero_checks.push(check);
+        check
+    }
+
+    /// Enforces that `input` is less than `2^bits`. New wires holding the
+    /// bits of `input` are added as hints, and are returned least
+    /// significant first. Each bit is checked to be 0 or 1, and together they
+    /// are checked to make up `input`. `check_constraints` reports either
+    /// failing (labelled "range check").
+    ///
+    /// If the value of `input` does not fit in a `u64` the hints are computed
+    /// as if it were `u64::MAX`, so they fail the checks instead of panicking.
+    ///
+    /// ```
+    /// use zksnark::field::z251::Z251;
+    /// use zksnark::groth16::circuit::*;
+    ///
+    /// let mut circuit = Circuit::<Z251>::new();
+    /// let x = circuit.new_wire();
+    /// let bits = circuit.new_range_check(x, 4);
+    ///
+    /// circuit.set_value(x, Z251::from(6));
+    /// assert_eq!(circuit.evaluate(bits[1]), Z251::from(1));
+    /// assert!(circuit.check_constraints().is_ok());
+    ///
+    /// circuit.reset();
+    /// circuit.set_value(x, Z251::from(16));
+    /// assert!(circuit.check_constraints().is_err());
+    /// ```
+    pub fn new_range_check(&mut self, input: WireId, bits: usize) -> Vec<WireId> {
+        let one = T::one();
+
+        let bit_wires = (0..bits)
+            .map(|i| {
+                let bit = self.new_wire();
+                self.bit_hints.insert(bit, (input, i));
+                bit
+            }).collect::<Vec<_>>();
+
+        for &bit in bit_wires.iter() {
+            let check = self.new_bit_checker(bit);
+            self.set_label(check, "range check");
+            self.zero_checks.push(check);
+        }
+
+        // sum 2^i b_i - input
+        let mut lhs_inputs = vec![(-one, input)];
+        let mut weight = one;
+        for &bit in bit_wires.iter() {
+            lhs_inputs.push((weight, bit));
+            weight = weight + weight;
+        }
+        let check = self.new_sub_circuit(lhs_inputs, vec![(one, self.unity_wire())]);
+        self.set_label(check, "range check");
+        self.zero_checks.push(check);
+
+        bit_wires
+    }
+
+    /// Enforces that `input` is equal to the constant `k`. The returned wire
+    /// is `input - k`, which is zero exactly when they are equal, and
+    /// `check_constraints` reports it (labelled "constant eq") when it is not.
+    pub fn new_constant_eq(&mut self, input: WireId, k: T) -> WireId {
+        let lhs_inputs = vec![(T::one(), input), (-k, self.unity_wire())];
+        let rhs_inputs = vec![(T::one(), self.unity_wire())];
+        let check = self.new_sub_circuit(lhs_inputs, rhs_inputs);
+
+        self.set_label(check, "constant eq");
+        self.zero_checks.push(check);
+        check
+    }
+
+    /// Tests whether `x` is one of the constants in `set`. The returned wire
+    /// is 1 when it is and 0 when it is not. The product `p` of `x - s` over
+    /// the set is computed and, with the inverse of `p` added as a hint, the
+    /// returned wire is `1 - p * inverse`. It is checked that `p` times the
+    /// returned wire is zero, so it cannot be 1 unless the product is zero,
+    /// and `check_constraints` reports this (labelled "set membership") when
+    /// it fails. An empty set has no members.
+    ///
+    /// The inverse is a hint wire, so nothing stops a prover from making the
+    /// returned wire 1 for any `x` except that check, and the check is only in
+    /// `zero_checks`. In a proof the result is only enforced if the verifier
+    /// checks every wire of `zero_checks` as a public input that is zero.
+    ///
+    /// ```
+    /// use zksnark::field::z251::Z251;
+    /// use zksnark::groth16::circuit::*;
+    ///
+    /// let mut circuit = Circuit::<Z251>::new();
+    /// let x = circuit.new_wire();
+    /// let member = circuit.new_set_membership(x, &[Z251::from(2), Z251::from(9)]);
+    ///
+    /// circuit.set_value(x, Z251::from(9));
+    /// assert_eq!(circuit.evaluate(member), Z251::from(1));
+    /// ```
+    pub fn new_set_membership(&mut self, x: WireId, set: &[T]) -> WireId {
+        let one = T::one();
+        let unity = self.unity_wire();
+
+        let product = set.iter().fold(None, |acc, &s| {
+            let factor = vec![(one, x), (-s, unity)];
+            Some(match acc {
+                Some(acc) => self.new_sub_circuit(vec![(one, acc)], factor),
+                None => self.new_sub_circuit(factor, vec![(one, unity)]),
+            })
+        });
+        let product = match product {
+            Some(product) => product,
+            None => unity,
+        };
+
+        let inverse = self.new_wire();
+        self.inverse_hints.insert(inverse, product);
+        let nonzero = self.new_sub_circuit(vec![(one, product)], vec![(one, inverse)]);
+        let member = self.new_sub_circuit(
+            vec![(one, unity), (-one, nonzero)],
+            vec![(one, unity)],
+        );
+
+        let check = self.new_sub_circuit(vec![(one, product)], vec![(one, member)]);
+        self.set_label(check, "set membership");
+        self.zero_checks.push(check);
+        member
+    }
+
+    /// Enforces that the commitments `c1` and `c2` open to the same value. A
+    /// commitment is the hash of a value and some randomness, and each
+    /// opening is given as the wires `(value, randomness)`. The commitments
+    /// are recomputed with `hash` and checked against `c1` and `c2`, and the
+    /// two values are checked to be equal, with `check_constraints`
+    /// reporting any of these that fail (labelled "commitment equality").
+    /// The returned wire is the difference of the values. All three checks
+    /// are in `zero_checks` and must be verified to be zero for a proof to
+    /// enforce them.
+    ///
+    /// ```
+    /// use zksnark::field::z251::Z251;
+    /// use zksnark::groth16::circuit::*;
+    ///
+    /// let hash = MiMCHash::<Z251>::new(4);
+    /// let mut circuit = Circuit::<Z251>::new();
+    /// let (c1, c2) = (circuit.new_wire(), circuit.new_wire());
+    /// let opening1 = (circuit.new_wire(), circuit.new_wire());
+    /// let opening2 = (circuit.new_wire(), circuit.new_wire());
+    /// circuit.new_commitment_equality(&hash, c1, c2, opening1, opening2);
+    /// assert_eq!(circuit.zero_checks().len(), 3);
+    /// ```
+    pub fn new_commitment_equality<H>(
+        &mut self,
+        hash: &H,
+        c1: WireId,
+        c2: WireId,
+        opening1: (WireId, WireId),
+        opening2: (WireId, WireId),
+    ) -> WireId
+    where
+        H: HashFamily<T>,
+    {
+        let one = T::one();
+        let unity = self.unity_wire();
+
+        let mut checks = Vec::with_capacity(3);
+        for &(commitment, (value, randomness)) in [(c1, opening1), (c2, opening2)].iter() {
+            let digest = hash.hash(self, &[value, randomness]);
+            checks.push(self.new_sub_circuit(
+                vec![(one, digest), (-one, commitment)],
+                vec![(one, unity)],
+            ));
+        }
+        let equal = self.new_sub_circuit(
+            vec![(one, opening1.0), (-one, opening2.0)],
+            vec![(one, unity)],
+        );
+        checks.push(equal);
+
+        for check in checks {
+            self.set_label(check, "commitment equality");
+            self.zero_checks.push(check);
+        }
+        equal
+    }
+
+    /// Enforces that `input` is a `bits` bit value equal to `k`, by combining
+    /// `new_range_check` and `new_constant_eq`. Gives an error without
+    /// changing the circuit if `k` does not itself fit in `bits` bits, since
+    /// no value of `input` could then pass, or if `k` is not below the
+    /// characteristic of the field, since it would stand for a smaller
+    /// element. Otherwise returns the wire from `new_constant_eq`.
+    ///
+    /// ```
+    /// use zksnark::field::z251::Z251;
+    /// use zksnark::groth16::circuit::*;
+    ///
+    /// let mut circuit = Circuit::<Z251>::new();
+    /// let x = circuit.new_wire();
+    ///
+    /// assert!(circuit.new_checked_constant(x, 8, 3).is_err());
+    /// assert!(circuit.new_checked_constant(x, 7, 3).is_ok());
+    /// ```
+    pub fn new_checked_constant(
+        &mut self,
+        input: WireId,
+        k: u64,
+        bits: usize,
+    ) -> Result<WireId, ConstantOutOfRange> {
+        // The constant must also be below the characteristic, or it would be
+        // reduced to another element
+        let constant = T::from_canonical_u64(k);
+        if (bits < 64 && k >> bits != 0) || constant.to_canonical_u64() != k {
+            return Err(ConstantOutOfRange { constant: k, bits });
+        }
+
+        self.new_range_check(input, bits);
+        Ok(self.new_constant_eq(input, constant))
+    }
+
+    /// Enforces that `bits` is a one-hot encoding, which is that each wire is
+    /// 0 or 1 and exactly one of them is 1. Each wire gets a bit checker, and
+    /// the returned wire is the sum of the wires minus one, which is zero
+    /// exactly when the sum is one. `check_constraints` reports any of these
+    /// that fail (labelled "one hot").
+    ///
+    /// ```
+    /// use zksnark::field::z251::Z251;
+    /// use zksnark::groth16::circuit::*;
+    ///
+    /// let mut circuit = Circuit::<Z251>::new();
+    /// let bits = vec![circuit.new_wire(), circuit.new_wire()];
+    /// circuit.new_one_hot_check(&bits);
+    ///
+    /// circuit.set_value(bits[0], Z251::from(0));
+    /// circuit.set_value(bits[1], Z251::from(1));
+    /// assert!(circuit.check_constraints().is_ok());
+    /// ```
+    pub fn new_one_hot_check(&mut self, bits: &[WireId]) -> WireId {
+        let one = T::one();
+
+        for &bit in bits.iter() {
+            let check = self.new_bit_checker(bit);
+            self.set_label(check, "one hot");
+            self.zero_checks.push(check);
+        }
+
+        // sum b_i - 1
+        let mut lhs_inputs = vec![(-one, self.unity_wire())];
+        lhs_inputs.extend(bits.iter().map(|&bit| (one, bit)));
+        let check = self.new_sub_circuit(lhs_inputs, vec![(one, self.unity_wire())]);
+        self.set_label(check, "one hot");
+        self.zero_checks.push(check);
+
+        check
+    }
+
+    /// Returns a wire holding `x mod m`, for an `x` of at most `bits` bits.
+    /// The quotient `q` and remainder `r` are added as hints, and are
+    /// constrained by `x = q * m + r` with `q` and `r` range checked to `bits`
+    /// bits and `0 <= r < m`, which is checked by range checking `m - 1 - r`
+    /// to the bits of `m - 1`. `check_constraints` reports the first of these that
+    /// fails (labelled "mod" or "range check").
     ///
-    /// Common example inputs are `Vec<Word8>` or `[Word64; 32]`
+    /// This is only sound when `2^bits * m` is smaller than the
+    /// characteristic of the field, so that `q * m + r` cannot wrap around.
     ///
     /// ```
     /// use zksnark::field::z251::Z251;
     /// use zksnark::groth16::circuit::*;
     ///
-    /// // Create an empty circuit
     /// let mut circuit = Circuit::<Z251>::new();
+    /// let x = circuit.new_wire();
+    /// let r = circuit.new_mod(x, Z251::from(7), 5);
     ///
-    /// let external_input: [u64; 7] = [9, 24, 45, 250, 99, 0, 7];
-    /// let new_set_circuit_input = &mut [Word64::default(); 7];
-    /// circuit.set_new_word64_array(external_input.iter()
-    ///                             , new_set_circuit_input);
+    /// circuit.set_value(x, Z251::from(30));
+    /// assert_eq!(circuit.evaluate(r), Z251::from(2));
+    /// assert!(circuit.check_constraints().is_ok());
+    /// ```
+    pub fn new_mod(&mut self, x: WireId, m: T, bits: usize) -> WireId {
+        self.new_div_mod(x, m, bits).1
+    }
+
+    /// The same as `new_mod` but returns the wires holding both the quotient
+    /// and the remainder.
+    fn new_div_mod(&mut self, x: WireId, m: T, bits: usize) -> (WireId, WireId) {
+        let one = T::one();
+        let m_value = m.to_canonical_u64();
+        assert!(m_value != 0, "new_mod: cannot reduce modulo zero");
+
+        let (quotient, remainder) = (self.new_wire(), self.new_wire());
+        self.div_hints
+            .insert(quotient, (x, m_value, DivPart::Quotient));
+        self.div_hints
+            .insert(remainder, (x, m_value, DivPart::Remainder));
+
+        // x - (q * m + r)
+        let lhs_inputs = vec![(one, x), (-m, quotient), (-one, remainder)];
+        let check = self.new_sub_circuit(lhs_inputs, vec![(one, self.unity_wire())]);
+        self.set_label(check, "mod");
+        self.zero_checks.push(check);
+
+        self.new_range_check(quotient, bits);
+        self.new_range_check(remainder, bits);
+
+        // r < m exactly when m - 1 - r does not wrap around
+        let lhs_inputs = vec![(m - one, self.unity_wire()), (-one, remainder)];
+        let slack = self.new_sub_circuit(lhs_inputs, vec![(one, self.unity_wire())]);
+        let slack_bits = 64 - (m_value - 1).leading_zeros() as usize;
+        self.new_range_check(slack, slack_bits);
+
+        (quotient, remainder)
+    }
+
+    /// Fixed point multiplication of `a` and `b`, which are integers scaled
+    /// by `2^scale`. Returns a wire holding `(a * b) >> scale`, where the
+    /// truncated bits are the remainder of a `new_mod` by `2^scale` so they
+    /// are range checked and the truncation is sound.
     ///
-    /// let evaluated_circuit = &mut [0; 7];
-    /// circuit.evaluate_to_array(new_set_circuit_input.iter()
-    ///                              , evaluated_circuit);
+    /// Requires that `a * b` fits in a `u64`, and that `2^(64 + scale)` is
+    /// smaller than the characteristic of the field (see `new_mod`).
     ///
-    /// assert_eq!(*evaluated_circuit, external_input);
     /// ```
-    pub fn evaluate_to_array<'a, Z, W: 'a, N>(&mut self, stream: Z, output: &'a mut [N])
-    where
-        Z: IntoIterator<Item = W>,
-        W: IntoIterator<Item = &'a WireId> + BinaryInput + CanConvert<N>,
-        N: Sized + From<u8> + BitXor<Output = N> + Shl<Output = N>,
-    {
-        stream
-            .into_iter()
-            .zip(0..output.len())
-            .for_each(|(wrd8, i)| output[i] = self.evaluate_to_num(wrd8));
+    /// use zksnark::groth16::circuit::*;
+    /// use zksnark::groth16::fr::FrLocal;
+    ///
+    /// // 2.5 * 1.25 with 8 bits after the point
+    /// let mut circuit = Circuit::<FrLocal>::new();
+    /// let (a, b) = (circuit.new_wire(), circuit.new_wire());
+    /// let product = circuit.new_fixed_mul(a, b, 8);
+    ///
+    /// circuit.set_value(a, FrLocal::from(640));
+    /// circuit.set_value(b, FrLocal::from(320));
+    /// assert!(circuit.evaluate(product) == FrLocal::from(800));
+    /// assert!(circuit.check_constraints().is_ok());
+    /// ```
+    pub fn new_fixed_mul(&mut self, a: WireId, b: WireId, scale: usize) -> WireId {
+        assert!(scale < 64, "new_fixed_mul: the scale must be less than 64 bits");
+        let one = T::one();
+
+        let product = self.new_sub_circuit(vec![(one, a)], vec![(one, b)]);
+        let (quotient, _) = self.new_div_mod(product, T::from_canonical_u64(1 << scale), 64);
+
+        quotient
     }
 
-    // NOTE: only used internally as a convenience for testing
-    fn evaluate_keccakmatrix(&mut self, matrix: &[Word64; 25]) -> [u64; 25] {
-        let mut arr: [u64; 25] = [0; 25];
-        (0..25).for_each(|x| arr[x] = self.evaluate_to_num(&matrix[x]));
-        arr
+    /// Adds the points `p` and `q` of the twisted Edwards curve `a x^2 + y^2
+    /// = 1 + d x^2 y^2`, returning the wires holding the `x` and `y` of the
+    /// sum. The coordinates of `p` and `q` are linear combinations in the
+    /// same form as the inputs to `new_sub_circuit`, so constant points, or
+    /// points chosen by a bit, cost nothing extra.
+    ///
+    /// Each denominator of the addition law gets an inverse added as a hint,
+    /// and the wire `denominator * inverse - 1` (labelled "edwards add") is
+    /// added to `zero_checks`. The hints are free in a proof, so the sum is
+    /// only enforced if every wire of `zero_checks` is verified to be zero.
+    /// When `a` is a square and `d` is not the law is complete, and the
+    /// denominators are never zero for points on the curve.
+    pub fn new_edwards_add(
+        &mut self,
+        (x1, y1): (Vec<(T, WireId)>, Vec<(T, WireId)>),
+        (x2, y2): (Vec<(T, WireId)>, Vec<(T, WireId)>),
+        a: T,
+        d: T,
+    ) -> (WireId, WireId) {
+        let one = T::one();
+        let unity = self.unity_wire();
+
+        let x1y2 = self.new_sub_circuit(x1.clone(), y2.clone());
+        let y1x2 = self.new_sub_circuit(y1.clone(), x2.clone());
+        let x1x2 = self.new_sub_circuit(x1, x2);
+        let y1y2 = self.new_sub_circuit(y1, y2);
+        let t = self.new_sub_circuit(vec![(d, x1x2)], vec![(one, y1y2)]);
+
+        // x = (x1 y2 + y1 x2) / (1 + t) and y = (y1 y2 - a x1 x2) / (1 - t)
+        let x = self.new_quotient(
+            vec![(one, x1y2), (one, y1x2)],
+            vec![(one, unity), (one, t)],
+            "edwards add",
+        );
+        let y = self.new_quotient(
+            vec![(one, y1y2), (-a, x1x2)],
+            vec![(one, unity), (-one, t)],
+            "edwards add",
+        );
+
+        (x, y)
     }
 
-    ////////////////////////////////////////////////////////////////////////////////
-    ///////////////////////////////////// Reset  ///////////////////////////////////
-    ////////////////////////////////////////////////////////////////////////////////
+    /// `numerator / denominator` for two linear combinations, by adding the
+    /// inverse of the denominator as a hint. Its product with the denominator
+    /// less one is added to `zero_checks`, labelled `label`.
+    fn new_quotient(
+        &mut self,
+        numerator: Vec<(T, WireId)>,
+        denominator: Vec<(T, WireId)>,
+        label: &str,
+    ) -> WireId {
+        let one = T::one();
 
-    /// Clears all of the stored circuit wire values (except for the zero and
-    /// unity wires) so that the same circuit can be reused for different
-    /// inputs.
-    pub fn reset(&mut self) {
-        let zero = self.zero_wire();
-        let one = self.unity_wire();
-        let values = self.wire_values.iter_mut().filter_map(|(&k, v)| {
-            if k == zero || k == one {
-                None
-            } else {
-                Some(v)
-            }
-        });
+        let denominator = self.new_sub_circuit(denominator, vec![(one, self.unity_wire())]);
+        let inverse = self.new_wire();
+        self.inverse_hints.insert(inverse, denominator);
 
-        for value in values {
-            *value = None;
-        }
-    }
+        let product = self.new_sub_circuit(vec![(one, denominator)], vec![(one, inverse)]);
+        let check = self.new_sub_circuit(
+            vec![(one, product), (-one, self.unity_wire())],
+            vec![(one, self.unity_wire())],
+        );
+        self.set_label(check, label);
+        self.zero_checks.push(check);
 
-    ////////////////////////////////////////////////////////////////////////////////
-    /////////////////////// Simple Binary Wire Functions ///////////////////////////
-    ////////////////////////////////////////////////////////////////////////////////
+        self.new_sub_circuit(numerator, vec![(one, inverse)])
+    }
 
-    pub fn new_bit_checker(&mut self, input: WireId) -> WireId {
-        let lhs_inputs = vec![(T::one(), input)];
-        let rhs_inputs = vec![(T::one(), input), (-T::one(), self.unity_wire())];
+    /// `x * (c_1 + x * (c_2 + ... + x * c_d))` for the coefficients `c_1, ...,
+    /// c_d`, built from the inside out. This is everything but the constant
+    /// term of a Horner evaluation, and is `None` when there are no
+    /// coefficients.
+    fn horner_tail(&mut self, coeffs: &[WireId], x: WireId) -> Option<WireId> {
+        let one = T::one();
 
-        self.new_sub_circuit(lhs_inputs, rhs_inputs)
+        coeffs.iter().rev().fold(None, |inner, &coeff| {
+            let lhs_inputs = match inner {
+                Some(inner) => vec![(one, inner), (one, coeff)],
+                None => vec![(one, coeff)],
+            };
+            Some(self.new_sub_circuit(lhs_inputs, vec![(one, x)]))
+        })
     }
 
     /// Requires that both the left and right inputs are either 0 or 1
@@ -782,6 +2128,18 @@ where
         self.new_sub_circuit(lhs_inputs, rhs_inputs)
     }
 
+    /// Requires that both the left and right inputs are either 0 or 1. The
+    /// output is `1 - lhs + lhs * rhs`, which is 0 only when `lhs` is 1 and
+    /// `rhs` is 0.
+    pub fn new_implies(&mut self, lhs: WireId, rhs: WireId) -> WireId {
+        let lhs_and_rhs = self.new_and(lhs, rhs);
+        let one = T::one();
+        let lhs_inputs = vec![(one, self.unity_wire()), (-one, lhs), (one, lhs_and_rhs)];
+        let rhs_inputs = vec![(one, self.unity_wire())];
+
+        self.new_sub_circuit(lhs_inputs, rhs_inputs)
+    }
+
     /// Requires that both the left and right inputs are either 0 or 1
     pub fn new_xnor(&mut self, lhs: WireId, rhs: WireId) -> WireId {
         let lhs_inputs = vec![
@@ -894,6 +2252,19 @@ where
         wrd64
     }
 
+    pub fn u32_bitwise_op<F>(&mut self, left: &Word32, right: &Word32, mut gate: F) -> Word32
+    where
+        F: FnMut(&mut Self, WireId, WireId) -> WireId,
+    {
+        let mut wrd32 = Word32::default();
+        left.iter()
+            .zip(right.iter())
+            .flat_map(|(l, r)| l.iter().zip(r.iter()))
+            .zip(iproduct!(0..4, 0..8))
+            .for_each(|((&l, &r), (i, j))| wrd32[i][j] = gate(self, l, r));
+        wrd32
+    }
+
     pub fn u8_bitwise_op<F>(&mut self, left: &Word8, right: &Word8, mut gate: F) -> Word8
     where
         F: FnMut(&mut Self, WireId, WireId) -> WireId,
@@ -919,6 +2290,19 @@ where
         wrd64
     }
 
+    pub fn u32_unary_op<F>(&mut self, input: &Word32, mut gate: F) -> Word32
+    where
+        F: FnMut(&mut Self, WireId) -> WireId,
+    {
+        let mut wrd32 = Word32::default();
+        input
+            .iter()
+            .flat_map(|x| x.iter())
+            .zip(iproduct!(0..4, 0..8))
+            .for_each(|(&x, (i, j))| wrd32[i][j] = gate(self, x));
+        wrd32
+    }
+
     pub fn u8_unary_op<F>(&mut self, input: &Word8, mut gate: F) -> Word8
     where
         F: FnMut(&mut Self, WireId) -> WireId,
@@ -1246,68 +2630,74 @@ where
 
     fn keccakf_1600(&mut self, a: &mut [Word64; 25]) {
         for i in 0..24 {
-            let mut array: [Word64; 5] = [Word64::default(); 5];
-
-            // Theta
-            unroll! {
-                for x in 0..5 {
-                    unroll! {
-                        for y_count in 0..5 {
-                            let y = y_count * 5;
-                            array[x] = self.u64_bitwise_op(&array[x], &a[x + y], Circuit::new_xor);
-                        }
+            self.keccakf_1600_round(a, i);
+        }
+    }
+
+    /// The round numbered `i` of `keccakf_1600`, which matches
+    /// `types::keccak_f1600_round`.
+    fn keccakf_1600_round(&mut self, a: &mut [Word64; 25], i: usize) {
+        let mut array: [Word64; 5] = [Word64::default(); 5];
+
+        // Theta
+        unroll! {
+            for x in 0..5 {
+                unroll! {
+                    for y_count in 0..5 {
+                        let y = y_count * 5;
+                        array[x] = self.u64_bitwise_op(&array[x], &a[x + y], Circuit::new_xor);
                     }
                 }
             }
+        }
 
-            unroll! {
-                for x in 0..5 {
-                    unroll! {
-                        for y_count in 0..5 {
-                            let y = y_count * 5;
-                            a[y + x] = self.u64_fan_in([a[y + x], array[(x + 4) % 5],
-                                types::rotate_word64_left(array[(x + 1) % 5], 1)].iter(), Circuit::new_xor);
-                        }
+        unroll! {
+            for x in 0..5 {
+                unroll! {
+                    for y_count in 0..5 {
+                        let y = y_count * 5;
+                        a[y + x] = self.u64_fan_in([a[y + x], array[(x + 4) % 5],
+                            types::rotate_word64_left(array[(x + 1) % 5], 1)].iter(), Circuit::new_xor);
                     }
                 }
             }
+        }
 
-            // Rho and pi
-            let mut _last = a[1];
-            unroll! {
-                for x in 0..24 {
-                    array[0] = a[types::PI[x]];
-                    a[types::PI[x]] = types::rotate_word64_left(_last, types::RHO[x]);
-                    _last = array[0];
-                }
+        // Rho and pi
+        let mut _last = a[1];
+        unroll! {
+            for x in 0..24 {
+                array[0] = a[types::PI[x]];
+                a[types::PI[x]] = types::rotate_word64_left(_last, types::RHO[x]);
+                _last = array[0];
             }
+        }
 
-            // Chi
-            unroll! {
-                for y_step in 0..5 {
-                    let y = y_step * 5;
+        // Chi
+        unroll! {
+            for y_step in 0..5 {
+                let y = y_step * 5;
 
-                    unroll! {
-                        for x in 0..5 {
-                            array[x] = a[y + x];
-                        }
+                unroll! {
+                    for x in 0..5 {
+                        array[x] = a[y + x];
                     }
+                }
 
-                    unroll! {
-                        for x in 0..5 {
-                            let not = self.u64_unary_op(&array[(x + 1) % 5], Circuit::new_not);
-                            let and = self.u64_bitwise_op(&not, &(array[(x + 2) % 5]), Circuit::new_and);
-                            a[y + x] = self.u64_bitwise_op(&array[x], &and, Circuit::new_xor);
+                unroll! {
+                    for x in 0..5 {
+                        let not = self.u64_unary_op(&array[(x + 1) % 5], Circuit::new_not);
+                        let and = self.u64_bitwise_op(&not, &(array[(x + 2) % 5]), Circuit::new_and);
+                        a[y + x] = self.u64_bitwise_op(&array[x], &and, Circuit::new_xor);
 
-                        }
                     }
                 }
-            };
+            }
+        };
 
-            // Iota
-            let rc_num = self.const_word64(types::RC[i]);
-            a[0] = self.u64_bitwise_op(&a[0], &rc_num, Circuit::new_xor);
-        }
+        // Iota
+        let rc_num = self.const_word64(types::RC[i]);
+        a[0] = self.u64_bitwise_op(&a[0], &rc_num, Circuit::new_xor);
     }
 
     fn squeeze(&mut self, keccak: &mut KeccakInternal, output: &mut [Word8]) {
@@ -1456,6 +2846,423 @@ where
         *output
     }
 
+    /// Makes `len` new bytes of input and their keccak256 hash, returning
+    /// both. Every bit of the input gets a bit checker, labelled "bit check",
+    /// which is added to `zero_checks` so that `check_constraints` reports a
+    /// bit that is not 0 or 1. The checks are only enforced by a proof when
+    /// they are verified along with the hash.
+    ///
+    /// ```
+    /// use zksnark::field::z251::Z251;
+    /// use zksnark::groth16::circuit::*;
+    ///
+    /// let mut circuit = Circuit::<Z251>::new();
+    /// let (input, hash) = circuit.new_keccak_preimage(2);
+    /// assert_eq!(input.len(), 2);
+    /// assert_eq!(hash.len(), 32);
+    /// assert_eq!(circuit.zero_checks().len(), 16);
+    /// ```
+    pub fn new_keccak_preimage(&mut self, len: usize) -> (Vec<Word8>, [Word8; 32]) {
+        let input = self.new_word8_vec(len);
+        let hash = self.keccak256_stream(&input);
+
+        for check in self.bit_check(flatten_word8(&input)) {
+            self.set_label(check, "bit check");
+            self.zero_checks.push(check);
+        }
+
+        (input, hash)
+    }
+
+    /// The sum of `a` and `b` modulo `2^32`, for the additions in the rounds
+    /// of hashes such as SHA-256. The sum of the two as numbers is a single
+    /// linear combination, which `new_range_check` splits into 33 bits. The
+    /// low 32 bits are the result and the carry out is dropped.
+    ///
+    /// The bits of the result are hint wires, so nothing ties them to the sum
+    /// except the range check, and the range check is only in `zero_checks`.
+    /// `check_constraints` enforces it (labelled "range check"), but in a
+    /// proof the result is only enforced if every wire of `zero_checks` is
+    /// verified to be zero.
+    ///
+    /// Panics unless the field has more than `2^33` elements, since otherwise
+    /// the sum could wrap around. That rules out the small test fields such
+    /// as `Z251`.
+    ///
+    /// Requires that all of the input wires are either 0 or 1
+    ///
+    /// ```
+    /// use zksnark::groth16::circuit::*;
+    /// use zksnark::groth16::fr::FrLocal;
+    ///
+    /// let mut circuit = Circuit::<FrLocal>::new();
+    /// let (a, b) = (circuit.new_word32(), circuit.new_word32());
+    /// let sum = circuit.word32_add_mod(a, b);
+    ///
+    /// circuit.set_word32(&a, 0xffff_fff0);
+    /// circuit.set_word32(&b, 0x20);
+    /// assert_eq!(circuit.evaluate_to_num::<_, u32>(&sum), 0x10);
+    /// ```
+    pub fn word32_add_mod(&mut self, a: Word32, b: Word32) -> Word32 {
+        assert!(
+            field_exceeds_bits::<T>(33),
+            "word32_add_mod: the field must have more than 2^33 elements"
+        );
+        let one = T::one();
+
+        let mut weight = one;
+        let mut lhs_inputs = Vec::with_capacity(64);
+        for (&l, &r) in (&a).into_iter().zip(&b) {
+            lhs_inputs.push((weight, l));
+            lhs_inputs.push((weight, r));
+            weight = weight + weight;
+        }
+        let sum = self.new_sub_circuit(lhs_inputs, vec![(one, self.unity_wire())]);
+
+        let bits = self.new_range_check(sum, 33);
+        let mut wrd32: Word32 = Word32::default();
+        (0..4).for_each(|i| wrd32[i] = bits[8 * i..8 * i + 8].iter().collect());
+        wrd32
+    }
+
+    ////////////////////////////////////////////////////////////////////////////////
+    ///////////////////////////// SHA-256 Functions ////////////////////////////////
+    ////////////////////////////////////////////////////////////////////////////////
+
+    fn u32_xor3(&mut self, a: Word32, b: Word32, c: Word32) -> Word32 {
+        let ab = self.u32_bitwise_op(&a, &b, Circuit::new_xor);
+        self.u32_bitwise_op(&ab, &c, Circuit::new_xor)
+    }
+
+    /// The sum of `words` modulo `2^32`, see `word32_add_mod`.
+    fn word32_sum(&mut self, words: &[Word32]) -> Word32 {
+        let first = words[0];
+        words[1..]
+            .iter()
+            .fold(first, |acc, &word| self.word32_add_mod(acc, word))
+    }
+
+    /// The compression function of SHA-256 on a block of 64 bytes. The words
+    /// of SHA-256 are big-endian, so the first byte of each group of four is
+    /// the most significant byte of its `Word32`.
+    fn sha256_compress(&mut self, h: &mut [Word32; 8], block: &[Word8]) {
+        use self::types::{rotate_word32_right as rotr, shift_word32_right as shr};
+
+        let mut w = block
+            .chunks(4)
+            .map(|bytes| {
+                let mut wrd32 = Word32::default();
+                bytes
+                    .iter()
+                    .rev()
+                    .enumerate()
+                    .for_each(|(i, &wrd8)| wrd32[i] = wrd8);
+                wrd32
+            }).collect::<Vec<_>>();
+
+        // The message schedule
+        for t in 16..64 {
+            let (x, y) = (w[t - 15], w[t - 2]);
+            let s0 = self.u32_xor3(rotr(x, 7), rotr(x, 18), shr(x, 3));
+            let s1 = self.u32_xor3(rotr(y, 17), rotr(y, 19), shr(y, 10));
+            let next = self.word32_sum(&[s1, w[t - 7], s0, w[t - 16]]);
+            w.push(next);
+        }
+
+        let mut v = *h;
+        for (t, &k) in types::SHA256_K.iter().enumerate() {
+            let (a, b, c, d) = (v[0], v[1], v[2], v[3]);
+            let (e, f, g, hh) = (v[4], v[5], v[6], v[7]);
+
+            let big_s1 = self.u32_xor3(rotr(e, 6), rotr(e, 11), rotr(e, 25));
+            let e_and_f = self.u32_bitwise_op(&e, &f, Circuit::new_and);
+            let not_e = self.u32_unary_op(&e, Circuit::new_not);
+            let not_e_and_g = self.u32_bitwise_op(&not_e, &g, Circuit::new_and);
+            let ch = self.u32_bitwise_op(&e_and_f, &not_e_and_g, Circuit::new_xor);
+            let k = self.const_word32(k);
+            let t1 = self.word32_sum(&[hh, big_s1, ch, k, w[t]]);
+
+            let big_s0 = self.u32_xor3(rotr(a, 2), rotr(a, 13), rotr(a, 22));
+            let a_and_b = self.u32_bitwise_op(&a, &b, Circuit::new_and);
+            let a_and_c = self.u32_bitwise_op(&a, &c, Circuit::new_and);
+            let b_and_c = self.u32_bitwise_op(&b, &c, Circuit::new_and);
+            let maj = self.u32_xor3(a_and_b, a_and_c, b_and_c);
+            let t2 = self.word32_add_mod(big_s0, maj);
+
+            let new_a = self.word32_add_mod(t1, t2);
+            let new_e = self.word32_add_mod(d, t1);
+            v = [new_a, a, b, c, new_e, e, f, g];
+        }
+
+        for (word, &x) in h.iter_mut().zip(v.iter()) {
+            *word = self.word32_add_mod(*word, x);
+        }
+    }
+
+    /// The SHA-256 hash of `input`. The padding depends only on the length
+    /// of the input, so it is made of constant wires. The additions are made
+    /// with `word32_add_mod`, so the field must have more than `2^33`
+    /// elements.
+    ///
+    /// As with `blake2s_stream`, the bits of every addition are hint wires
+    /// that are only tied to the sum by the range checks in `zero_checks`.
+    /// A proof says nothing about the hash unless all of `zero_checks` are
+    /// verified to be zero along with the digest.
+    ///
+    /// Requires that all of the input wires are either 0 or 1
+    ///
+    /// ```
+    /// use zksnark::groth16::circuit::*;
+    /// use zksnark::groth16::fr::FrLocal;
+    ///
+    /// let mut circuit = Circuit::<FrLocal>::new();
+    /// let input = circuit.set_new_word8_vec(b"abc");
+    /// let hash = circuit.sha256(&input);
+    ///
+    /// let output: Vec<u8> = circuit.evaluate_to_vec(hash.iter());
+    /// assert_eq!(output,
+    ///     vec![186, 120, 22, 191, 143, 1, 207, 234, 65, 65, 64, 222, 93, 174, 34, 35,
+    ///     176, 3, 97, 163, 150, 23, 122, 156, 180, 16, 255, 97, 242, 0, 21, 173]);
+    /// ```
+    pub fn sha256(&mut self, input: &[Word8]) -> [Word8; 32] {
+        let mut message = input.to_vec();
+        message.push(self.const_word8(0x80));
+        while message.len() % 64 != 56 {
+            message.push(self.const_word8(0));
+        }
+        let bits = input.len() as u64 * 8;
+        for i in (0..8).rev() {
+            message.push(self.const_word8((bits >> (8 * i)) as u8));
+        }
+
+        let mut h = [Word32::default(); 8];
+        types::SHA256_IV
+            .iter()
+            .enumerate()
+            .for_each(|(i, &word)| h[i] = self.const_word32(word));
+
+        for block in message.chunks(64) {
+            self.sha256_compress(&mut h, block);
+        }
+
+        let mut output = [Word8::default(); 32];
+        h.iter()
+            .flat_map(|wrd32| wrd32.iter().rev())
+            .zip(output.iter_mut())
+            .for_each(|(&wrd8, out)| *out = wrd8);
+        output
+    }
+
+    ////////////////////////////////////////////////////////////////////////////////
+    ///////////////////////////// BLAKE2s Functions ////////////////////////////////
+    ////////////////////////////////////////////////////////////////////////////////
+
+    /// The mixing function G of BLAKE2s, which mixes the message words `x`
+    /// and `y` into the words of `v` at the indices `(a, b, c, d)`.
+    fn blake2s_g(
+        &mut self,
+        v: &mut [Word32; 16],
+        (a, b, c, d): (usize, usize, usize, usize),
+        x: Word32,
+        y: Word32,
+    ) {
+        for &(m, r1, r2) in [(x, 16, 12), (y, 8, 7)].iter() {
+            v[a] = self.word32_add_mod(v[a], v[b]);
+            v[a] = self.word32_add_mod(v[a], m);
+            let xor = self.u32_bitwise_op(&v[d], &v[a], Circuit::new_xor);
+            v[d] = types::rotate_word32_right(xor, r1);
+            v[c] = self.word32_add_mod(v[c], v[d]);
+            let xor = self.u32_bitwise_op(&v[b], &v[c], Circuit::new_xor);
+            v[b] = types::rotate_word32_right(xor, r2);
+        }
+    }
+
+    /// The compression function of BLAKE2s on a block of 64 bytes, where `t`
+    /// is the number of bytes hashed so far including this block. The counter
+    /// and the final block flag are known when the circuit is built, so they
+    /// are folded into the constant half of the working vector.
+    fn blake2s_compress(&mut self, h: &mut [Word32; 8], block: &[Word8], t: u64, last: bool) {
+        let mut m = [Word32::default(); 16];
+        block
+            .iter()
+            .enumerate()
+            .for_each(|(i, &wrd8)| m[i / 4][i % 4] = wrd8);
+
+        let mut iv = types::BLAKE2S_IV;
+        iv[4] ^= t as u32;
+        iv[5] ^= (t >> 32) as u32;
+        if last {
+            iv[6] = !iv[6];
+        }
+
+        let mut v = [Word32::default(); 16];
+        v[..8].copy_from_slice(&h[..]);
+        iv.iter()
+            .enumerate()
+            .for_each(|(i, &word)| v[8 + i] = self.const_word32(word));
+
+        for sigma in types::BLAKE2S_SIGMA.iter() {
+            self.blake2s_g(&mut v, (0, 4, 8, 12), m[sigma[0]], m[sigma[1]]);
+            self.blake2s_g(&mut v, (1, 5, 9, 13), m[sigma[2]], m[sigma[3]]);
+            self.blake2s_g(&mut v, (2, 6, 10, 14), m[sigma[4]], m[sigma[5]]);
+            self.blake2s_g(&mut v, (3, 7, 11, 15), m[sigma[6]], m[sigma[7]]);
+            self.blake2s_g(&mut v, (0, 5, 10, 15), m[sigma[8]], m[sigma[9]]);
+            self.blake2s_g(&mut v, (1, 6, 11, 12), m[sigma[10]], m[sigma[11]]);
+            self.blake2s_g(&mut v, (2, 7, 8, 13), m[sigma[12]], m[sigma[13]]);
+            self.blake2s_g(&mut v, (3, 4, 9, 14), m[sigma[14]], m[sigma[15]]);
+        }
+
+        for (i, word) in h.iter_mut().enumerate() {
+            let xor = self.u32_bitwise_op(word, &v[i], Circuit::new_xor);
+            *word = self.u32_bitwise_op(&xor, &v[i + 8], Circuit::new_xor);
+        }
+    }
+
+    /// The unkeyed BLAKE2s hash of `input` with a 32 byte digest. The
+    /// additions are made with `word32_add_mod`, so the field must have more
+    /// than `2^33` elements.
+    ///
+    /// Every bit of every addition is a hint wire, which is only tied to the
+    /// sum by the range checks that `word32_add_mod` adds to `zero_checks`.
+    /// A proof says nothing about the hash unless all of `zero_checks` are
+    /// verified to be zero along with the digest, otherwise a prover can
+    /// choose any digest bits.
+    ///
+    /// Requires that all of the input wires are either 0 or 1
+    ///
+    /// ```
+    /// use zksnark::groth16::circuit::*;
+    /// use zksnark::groth16::fr::FrLocal;
+    ///
+    /// let mut circuit = Circuit::<FrLocal>::new();
+    /// let input = circuit.set_new_word8_vec(b"abc");
+    /// let hash = circuit.blake2s_stream(&input);
+    ///
+    /// let output: Vec<u8> = circuit.evaluate_to_vec(hash.iter());
+    /// assert_eq!(output,
+    ///     vec![80, 140, 94, 140, 50, 124, 20, 226, 225, 167, 43, 163, 78, 235, 69, 47,
+    ///     55, 69, 139, 32, 158, 214, 58, 41, 77, 153, 155, 76, 134, 103, 89, 130]);
+    /// ```
+    pub fn blake2s_stream<'a>(&mut self, input: impl IntoIterator<Item = &'a Word8>) -> [Word8; 32] {
+        let input = input.into_iter().cloned().collect::<Vec<_>>();
+
+        // The parameter block for a 32 byte digest without a key
+        let mut iv = types::BLAKE2S_IV;
+        iv[0] ^= 0x0101_0020;
+        let mut h = [Word32::default(); 8];
+        iv.iter()
+            .enumerate()
+            .for_each(|(i, &word)| h[i] = self.const_word32(word));
+
+        // The last block is padded with zeros, and an empty input is hashed
+        // as a single block of zeros
+        let zero = self.const_word8(0);
+        let blocks = ::std::cmp::max(1, (input.len() + 63) / 64);
+        for i in 0..blocks {
+            let chunk = &input[64 * i..input.len().min(64 * i + 64)];
+            let mut block = [zero; 64];
+            block[..chunk.len()].copy_from_slice(chunk);
+
+            let last = i + 1 == blocks;
+            let t = if last { input.len() } else { 64 * (i + 1) };
+            self.blake2s_compress(&mut h, &block, t as u64, last);
+        }
+
+        let mut output = [Word8::default(); 32];
+        h.iter()
+            .flat_map(|wrd32| wrd32.iter())
+            .zip(output.iter_mut())
+            .for_each(|(&wrd8, out)| *out = wrd8);
+        output
+    }
+
+    /// Sorts the inputs, which are numbers less than `2^bits`, into
+    /// non-decreasing order. Each input is split into its bits with
+    /// `new_range_check`, so the inputs are also checked to fit in `bits`
+    /// bits and the field must have more than `2^bits` elements. The bits go
+    /// through an odd-even transposition sorting network, so the output is a
+    /// permutation of the inputs by construction, and each output is the
+    /// recombination of its bits. Each compare-swap costs a `greater_than`
+    /// and three gates per bit.
+    ///
+    /// The bits are hint wires that are only tied to the inputs by the range
+    /// checks in `zero_checks`, so a proof of the sorted outputs means nothing
+    /// unless all of `zero_checks` are verified to be zero as well.
+    ///
+    /// ```
+    /// use zksnark::groth16::circuit::*;
+    /// use zksnark::field::z251::Z251;
+    ///
+    /// let mut circuit = Circuit::<Z251>::new();
+    /// let inputs = (0..3).map(|_| circuit.new_wire()).collect::<Vec<_>>();
+    /// let sorted = circuit.new_sort(&inputs, 4);
+    ///
+    /// for (&wire, &x) in inputs.iter().zip([3, 1, 2].iter()) {
+    ///     circuit.set_value(wire, Z251::from(x));
+    /// }
+    /// let output = sorted.iter().map(|&wire| circuit.evaluate(wire)).collect::<Vec<_>>();
+    /// assert_eq!(output, vec![Z251::from(1), Z251::from(2), Z251::from(3)]);
+    /// assert!(circuit.check_constraints().is_ok());
+    /// ```
+    pub fn new_sort(&mut self, inputs: &[WireId], bits: usize) -> Vec<WireId> {
+        assert!(bits > 0, "new_sort: the inputs must have at least one bit");
+
+        let mut words = inputs
+            .iter()
+            .map(|&input| self.new_range_check(input, bits))
+            .collect::<Vec<_>>();
+
+        for round in 0..words.len() {
+            let mut i = round % 2;
+            while i + 1 < words.len() {
+                let (min, max) = self.compare_swap(&words[i], &words[i + 1]);
+                words[i] = min;
+                words[i + 1] = max;
+                i += 2;
+            }
+        }
+
+        let one = T::one();
+        words
+            .iter()
+            .map(|word| {
+                let mut weight = one;
+                let packed = word
+                    .iter()
+                    .map(|&bit| {
+                        let term = (weight, bit);
+                        weight = weight + weight;
+                        term
+                    }).collect::<Vec<_>>();
+                self.new_sub_circuit(packed, vec![(one, self.unity_wire())])
+            }).collect()
+    }
+
+    /// Returns `(min, max)` of the two numbers given by their bits, least
+    /// significant first. With `s = left > right`, each bit of the minimum is
+    /// `left + s * (right - left)` and the maximum is whatever is left over,
+    /// `left + right - min`.
+    fn compare_swap(&mut self, left: &[WireId], right: &[WireId]) -> (Vec<WireId>, Vec<WireId>) {
+        let swap = self.greater_than(left, right);
+        let one = T::one();
+
+        let mut min = Vec::with_capacity(left.len());
+        let mut max = Vec::with_capacity(left.len());
+        for (&l, &r) in left.iter().zip(right.iter()) {
+            let diff = self.new_sub_circuit(vec![(one, swap)], vec![(one, r), (-one, l)]);
+            min.push(self.new_sub_circuit(
+                vec![(one, l), (one, diff)],
+                vec![(one, self.unity_wire())],
+            ));
+            max.push(self.new_sub_circuit(
+                vec![(one, r), (-one, diff)],
+                vec![(one, self.unity_wire())],
+            ));
+        }
+
+        (min, max)
+    }
+
     pub fn validate_order(
         &mut self,
         input_x: &Word64,
@@ -1494,3 +3301,52 @@ where
     //     }
     // }
 }
+
+/// The inputs with each wire replaced by its entry in `replaced`, if it has
+/// one, and the weights of each wire summed so that it appears once, in the
+/// order of the wires.
+fn merge_inputs<T>(inputs: &[(T, WireId)], replaced: &HashMap<WireId, WireId>) -> Vec<(T, WireId)>
+where
+    T: Field,
+{
+    let mut merged: Vec<(T, WireId)> = Vec::with_capacity(inputs.len());
+    for &(weight, wire) in inputs {
+        let wire = replaced.get(&wire).cloned().unwrap_or(wire);
+        match merged.iter_mut().find(|term| term.1 == wire) {
+            Some(term) => term.0 = term.0 + weight,
+            None => merged.push((weight, wire)),
+        }
+    }
+    merged.sort_by_key(|&(_, wire)| wire.inner_id());
+    merged
+}
+
+/// The integer that `value` represents, or `u64::MAX` if it does not fit in
+/// a `u64`. Hints are computed from this so that a value that is too large
+/// gives hints that fail their checks, rather than a panic.
+fn saturating_u64<T>(value: T) -> u64
+where
+    T: Field,
+{
+    let bytes = value.to_bytes();
+    let split = bytes.len().saturating_sub(8);
+    if bytes[..split].iter().any(|&b| b != 0) {
+        u64::MAX
+    } else {
+        bytes[split..].iter().fold(0, |acc, &b| acc << 8 | b as u64)
+    }
+}
+
+/// Whether the field has more than `2^bits` elements, which is when the
+/// largest element `-1` needs more than `bits` bits.
+fn field_exceeds_bits<T>(bits: usize) -> bool
+where
+    T: Field,
+{
+    let bytes = (-T::one()).to_bytes();
+    let len = bytes
+        .iter()
+        .position(|&b| b != 0)
+        .map_or(0, |i| 8 * (bytes.len() - i) - bytes[i].leading_zeros() as usize);
+    len > bits
+}
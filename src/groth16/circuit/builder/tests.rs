@@ -49,6 +49,113 @@ fn bit_check_stream() {
         .for_each(|x| assert_eq!(circuit.evaluate(x), Z251::from(0)));
 }
 
+#[test]
+fn alias_test() {
+    let mut circuit = Circuit::<Z251>::new();
+    let (a, b) = (circuit.new_wire(), circuit.new_wire());
+    let product = circuit.new_and(a, b);
+
+    circuit.alias(product, "a_and_b").unwrap();
+    circuit.alias(product, "carry").unwrap();
+    circuit.alias(product, "carry").unwrap();
+    assert_eq!(circuit.named_output("a_and_b"), Some(product));
+    assert_eq!(circuit.named_output("carry"), Some(product));
+    assert_eq!(circuit.named_output("sum"), None);
+
+    assert_eq!(
+        circuit.alias(a, "carry"),
+        Err(AliasErr::NameTaken {
+            name: "carry".to_string(),
+            wire: product
+        })
+    );
+    let unknown = WireId(circuit.num_wires());
+    assert_eq!(
+        circuit.alias(unknown, "sum"),
+        Err(AliasErr::UnknownWire(unknown))
+    );
+}
+
+#[test]
+fn commitment_equality_test() {
+    let hash = MiMCHash::<Z251>::new(4);
+    let commit = |value: usize, randomness: usize| {
+        let mut circuit = Circuit::<Z251>::new();
+        let (v, r) = (circuit.new_wire(), circuit.new_wire());
+        let digest = hash.hash(&mut circuit, &[v, r]);
+        circuit.set_value(v, Z251::from(value));
+        circuit.set_value(r, Z251::from(randomness));
+        circuit.evaluate(digest)
+    };
+
+    let mut circuit = Circuit::<Z251>::new();
+    let (c1, c2) = (circuit.new_wire(), circuit.new_wire());
+    let (v1, r1) = (circuit.new_wire(), circuit.new_wire());
+    let (v2, r2) = (circuit.new_wire(), circuit.new_wire());
+    circuit.new_commitment_equality(&hash, c1, c2, (v1, r1), (v2, r2));
+
+    let mut open = |(value1, randomness1), (value2, randomness2)| {
+        circuit.reset();
+        circuit.set_value(c1, commit(value1, randomness1));
+        circuit.set_value(c2, commit(value2, randomness2));
+        circuit.set_value(v1, Z251::from(value1));
+        circuit.set_value(r1, Z251::from(randomness1));
+        circuit.set_value(v2, Z251::from(value2));
+        circuit.set_value(r2, Z251::from(randomness2));
+        circuit.check_constraints()
+    };
+
+    // The same value under different randomness
+    assert!(open((42, 7), (42, 100)).is_ok());
+    // Different values, each opening its own commitment
+    assert!(open((42, 7), (43, 7)).is_err());
+}
+
+#[test]
+fn set_membership_test() {
+    let mut circuit = Circuit::<Z251>::new();
+    let x = circuit.new_wire();
+    let set = [Z251::from(3), Z251::from(5), Z251::from(7)];
+    let member = circuit.new_set_membership(x, &set);
+
+    for value in 0..10 {
+        circuit.reset();
+        circuit.set_value(x, Z251::from(value));
+        let expected = if value == 3 || value == 5 || value == 7 { 1 } else { 0 };
+        assert_eq!(circuit.evaluate(member), Z251::from(expected));
+        assert!(circuit.check_constraints().is_ok());
+    }
+
+    // Claiming membership for a value outside of the set is caught
+    circuit.reset();
+    circuit.set_value(x, Z251::from(4));
+    circuit.set_value(member, Z251::from(1));
+    assert!(circuit.check_constraints().is_err());
+}
+
+#[test]
+fn validate_test() {
+    let mut circuit = Circuit::<Z251>::new();
+    let (a, b) = (circuit.new_wire(), circuit.new_wire());
+    let and = circuit.new_and(a, b);
+    circuit.new_or(and, b);
+    assert_eq!(circuit.validate(), Ok(()));
+
+    // A wire id that was never made by `new_wire`
+    let never_made = WireId(circuit.num_wires() + 10);
+    circuit.new_sub_circuit(
+        vec![(Z251::one(), a)],
+        vec![(Z251::one(), never_made)],
+    );
+    assert_eq!(
+        circuit.validate(),
+        Err(CircuitError::UndefinedWire {
+            sub_circuit: SubCircuitId(3),
+            wire: never_made,
+        })
+    );
+}
+
 #[test]
 fn and_test() {
     let logic_table = [(0, 0, 0), (0, 1, 0), (1, 0, 0), (1, 1, 1)];
@@ -79,6 +186,22 @@ fn not_test() {
     }
 }
 
+#[test]
+fn implies_test() {
+    let logic_table = [(0, 0, 1), (0, 1, 1), (1, 0, 0), (1, 1, 1)];
+    let mut circuit = Circuit::<Z251>::new();
+    let l_wire = circuit.new_wire();
+    let r_wire = circuit.new_wire();
+    let implies = circuit.new_implies(l_wire, r_wire);
+
+    for (l, r, l_implies_r) in logic_table.iter() {
+        circuit.reset();
+        circuit.set_value(l_wire, Z251::from(*l));
+        circuit.set_value(r_wire, Z251::from(*r));
+        assert!(circuit.evaluate(implies) == Z251::from(*l_implies_r));
+    }
+}
+
 #[test]
 fn or_test() {
     let logic_table = [(0, 0, 0), (0, 1, 1), (1, 0, 1), (1, 1, 1)];
@@ -498,6 +621,26 @@ fn u64_fan_in_prop() {
     quickcheck(prop as fn(Vec<u64>) -> bool);
 }
 
+#[test]
+fn word32_add_mod_test() {
+    fn prop(a: u32, b: u32) -> bool {
+        let mut circuit = Circuit::<FrLocal>::new();
+        let (wa, wb) = (circuit.new_word32(), circuit.new_word32());
+        let sum = circuit.word32_add_mod(wa, wb);
+
+        circuit.set_word32(&wa, a);
+        circuit.set_word32(&wb, b);
+        circuit.evaluate_to_num::<_, u32>(&sum) == a.wrapping_add(b)
+            && circuit.check_constraints().is_ok()
+    }
+    quickcheck(prop as fn(u32, u32) -> bool);
+
+    // With and without a carry out
+    assert!(prop(u32::max_value(), 1));
+    assert!(prop(0x8000_0000, 0x8000_0000));
+    assert!(prop(12, 30));
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 //////////////////////////////////// Keccak Tests //////////////////////////////
 ////////////////////////////////////////////////////////////////////////////////
@@ -651,6 +794,33 @@ fn keccakf_1600_single_test() {
     );
 }
 
+#[test]
+fn keccakf_1600_round_test() {
+    let native: &mut [u64; 25] = &mut [0; 25];
+    for (i, lane) in native.iter_mut().enumerate() {
+        *lane = (i as u64).wrapping_mul(0x9e3779b97f4a7c15);
+    }
+
+    let mut circuit = Circuit::<Z251>::new();
+    let matrix = &mut circuit.new_keccakmatrix();
+    circuit.set_keccakmatrix(matrix, native);
+
+    // Compare the state after each round rather than only at the end
+    let expected = &mut native.clone();
+    for round in 0..24 {
+        circuit.keccakf_1600_round(matrix, round);
+        keccak_f1600_round(native, round);
+        assert_eq!(circuit.evaluate_keccakmatrix(matrix), *native, "round {}", round);
+    }
+
+    keccakf(expected);
+    assert_eq!(*native, *expected);
+
+    keccak_f1600(expected);
+    keccakf(native);
+    assert_eq!(*native, *expected);
+}
+
 #[test]
 fn keccak_absorb_squeeze_prop() {
     fn prop(rand: Vec<u8>) -> bool {
@@ -1066,3 +1236,354 @@ quickcheck! {
             circuit.evaluate_to_vec(word8s.iter()) == u8_nums
     }
 }
+
+#[test]
+fn sort_test() {
+    let cases: [[u8; 4]; 5] = [
+        [4, 3, 2, 1],
+        [1, 2, 3, 4],
+        [200, 7, 200, 0],
+        [9, 9, 9, 9],
+        [13, 255, 128, 64],
+    ];
+
+    let mut circuit = Circuit::<FrLocal>::new();
+    let inputs = (0..4).map(|_| circuit.new_wire()).collect::<Vec<_>>();
+    let sorted = circuit.new_sort(&inputs, 8);
+
+    for case in cases.iter() {
+        circuit.reset();
+        inputs
+            .iter()
+            .zip(case.iter())
+            .for_each(|(&wire, &num)| circuit.set_value(wire, FrLocal::from(num as usize)));
+
+        let output = sorted
+            .iter()
+            .map(|&wire| circuit.evaluate(wire).to_canonical_u64() as u8)
+            .collect::<Vec<_>>();
+
+        // Non-decreasing
+        assert!(output.windows(2).all(|w| w[0] <= w[1]));
+
+        // A permutation of the inputs
+        let mut expected = case.to_vec();
+        expected.sort();
+        assert_eq!(output, expected);
+
+        // Along with the range checks of the inputs
+        assert!(circuit.check_constraints().is_ok());
+    }
+
+    // An input that does not fit in 8 bits is caught by the range checks
+    circuit.reset();
+    inputs
+        .iter()
+        .for_each(|&wire| circuit.set_value(wire, FrLocal::from(1)));
+    circuit.set_value(inputs[2], FrLocal::from(256));
+    assert_eq!(
+        circuit.check_constraints().unwrap_err().label,
+        Some("range check".to_string())
+    );
+}
+
+#[test]
+fn poly_accumulator_test() {
+    // The set {2, 5, 7}: A(X) = X^3 - 14X^2 + 59X - 70
+    let mut circuit = Circuit::<Z251>::new();
+    let element = circuit.new_wire();
+    let acc = circuit.new_wire();
+    let witness = vec![circuit.new_wire(), circuit.new_wire(), circuit.new_wire()];
+    let check = circuit.new_poly_accumulator_check(element, &witness, acc);
+
+    let set_accumulator = |circuit: &mut Circuit<Z251>| {
+        circuit.set_value(acc, -Z251::from(70));
+        circuit.set_value(witness[0], Z251::from(59));
+        circuit.set_value(witness[1], -Z251::from(14));
+        circuit.set_value(witness[2], Z251::from(1));
+    };
+
+    for &member in [2, 5, 7].iter() {
+        circuit.reset();
+        set_accumulator(&mut circuit);
+        circuit.set_value(element, Z251::from(member));
+        assert_eq!(circuit.evaluate(check), Z251::zero());
+        assert!(circuit.check_constraints().is_ok());
+    }
+
+    // A(3) = (1)(-2)(-4) = 8
+    circuit.reset();
+    set_accumulator(&mut circuit);
+    circuit.set_value(element, Z251::from(3));
+    assert_eq!(circuit.evaluate(check), Z251::from(8));
+    let err = circuit.check_constraints().unwrap_err();
+    assert_eq!(err.label, Some("poly accumulator".to_string()));
+}
+
+#[test]
+fn flatten_word8_order_test() {
+    let mut circuit = Circuit::<Z251>::new();
+    let words = circuit.new_word8_vec(3);
+
+    let little = flatten_word8_with_order(&words, ByteOrder::LittleEndian);
+    let big = flatten_word8_with_order(&words, ByteOrder::BigEndian);
+
+    // Little endian is the default
+    assert_eq!(little, flatten_word8(&words));
+
+    // The bytes stay in order but the bits within each byte are reversed
+    assert_eq!(little.len(), big.len());
+    for (l, b) in little.chunks(8).zip(big.chunks(8)) {
+        assert_eq!(l.iter().rev().collect::<Vec<_>>(), b.iter().collect::<Vec<_>>());
+    }
+    assert_eq!(big[0], words[0][7]);
+    assert_eq!(big[8], words[1][7]);
+}
+
+#[test]
+fn poly_eval_test() {
+    // p(X) = 4 + 3X + 2X^2 + X^3, p(2) = 4 + 6 + 8 + 8 = 26
+    let mut circuit = Circuit::<Z251>::new();
+    let coeffs = (0..4).map(|_| circuit.new_wire()).collect::<Vec<_>>();
+    let (z, y) = (circuit.new_wire(), circuit.new_wire());
+    let check = circuit.new_poly_eval(&coeffs, z, y);
+
+    let set_inputs = |circuit: &mut Circuit<Z251>, y_value: usize| {
+        circuit.reset();
+        for (&coeff, &value) in coeffs.iter().zip([4, 3, 2, 1].iter()) {
+            circuit.set_value(coeff, Z251::from(value));
+        }
+        circuit.set_value(z, Z251::from(2));
+        circuit.set_value(y, Z251::from(y_value));
+    };
+
+    set_inputs(&mut circuit, 26);
+    assert_eq!(circuit.evaluate(check), Z251::zero());
+    assert!(circuit.check_constraints().is_ok());
+
+    set_inputs(&mut circuit, 25);
+    assert_eq!(circuit.evaluate(check), Z251::from(1));
+    let err = circuit.check_constraints().unwrap_err();
+    assert_eq!(err.label, Some("poly eval".to_string()));
+}
+
+#[test]
+fn checked_constant_test() {
+    let mut circuit = Circuit::<Z251>::new();
+    let x = circuit.new_wire();
+
+    // 16 does not fit in 4 bits, and nothing is added to the circuit
+    assert_eq!(
+        circuit.new_checked_constant(x, 16, 4),
+        Err(ConstantOutOfRange {
+            constant: 16,
+            bits: 4
+        })
+    );
+    assert_eq!(circuit.sub_circuits().count(), 0);
+
+    // 255 fits in 8 bits but would be reduced to 4 in Z251
+    assert_eq!(
+        circuit.new_checked_constant(x, 255, 8),
+        Err(ConstantOutOfRange {
+            constant: 255,
+            bits: 8
+        })
+    );
+    assert_eq!(circuit.sub_circuits().count(), 0);
+
+    let check = circuit.new_checked_constant(x, 13, 4).unwrap();
+
+    circuit.set_value(x, Z251::from(13));
+    assert_eq!(circuit.evaluate(check), Z251::zero());
+    assert!(circuit.check_constraints().is_ok());
+
+    circuit.reset();
+    circuit.set_value(x, Z251::from(12));
+    assert_eq!(
+        circuit.check_constraints().unwrap_err().label,
+        Some("constant eq".to_string())
+    );
+}
+
+#[test]
+fn fixed_mul_test() {
+    let scale = 16;
+    let mut circuit = Circuit::<FrLocal>::new();
+    let (a, b) = (circuit.new_wire(), circuit.new_wire());
+    let product = circuit.new_fixed_mul(a, b, scale);
+
+    // 2.5 * 1.25, 3.3 * 0.7 and 1000.001 * 0.5 rounded to 16 bits
+    for &(x, y) in [(163840u64, 81920u64), (216268, 45875), (65536065, 32768)].iter() {
+        circuit.reset();
+        circuit.set_value(a, FrLocal::from(x as usize));
+        circuit.set_value(b, FrLocal::from(y as usize));
+        assert!(circuit.evaluate(product) == FrLocal::from(((x * y) >> scale) as usize));
+        assert!(circuit.check_constraints().is_ok());
+    }
+
+    // Rounding up instead of truncating needs a negative remainder
+    circuit.reset();
+    circuit.set_value(a, FrLocal::from(216268));
+    circuit.set_value(b, FrLocal::from(45875));
+    circuit.set_value(product, FrLocal::from(((216268 * 45875) >> scale) + 1));
+    assert!(circuit.check_constraints().is_err());
+}
+
+#[test]
+fn mod_test() {
+    // 2^5 * 5 = 160 is below 251 so nothing can wrap around
+    let mut circuit = Circuit::<Z251>::new();
+    let x = circuit.new_wire();
+    let (q, r) = circuit.new_div_mod(x, Z251::from(5), 5);
+
+    for &value in [0, 4, 5, 13, 24, 31].iter() {
+        circuit.reset();
+        circuit.set_value(x, Z251::from(value));
+        assert_eq!(circuit.evaluate(r), Z251::from(value % 5));
+        assert_eq!(circuit.evaluate(q), Z251::from(value / 5));
+        assert!(circuit.check_constraints().is_ok());
+    }
+
+    // 13 = 1 * 5 + 8 satisfies x = q * m + r but not r < m
+    circuit.reset();
+    circuit.set_value(x, Z251::from(13));
+    circuit.set_value(q, Z251::from(1));
+    circuit.set_value(r, Z251::from(8));
+    assert_eq!(
+        circuit.check_constraints().unwrap_err().label,
+        Some("range check".to_string())
+    );
+}
+
+#[test]
+fn mod_larger_than_bits_test() {
+    // A remainder below 100 can need more than the 5 bits of x
+    let mut circuit = Circuit::<FrLocal>::new();
+    let x = circuit.new_wire();
+    let r = circuit.new_mod(x, FrLocal::from(100), 5);
+
+    circuit.set_value(x, FrLocal::from(31));
+    assert!(circuit.evaluate(r) == FrLocal::from(31));
+    assert!(circuit.check_constraints().is_ok());
+
+    // A value that does not fit in a u64 gives hints that fail the checks
+    circuit.reset();
+    circuit.set_value(x, -FrLocal::one());
+    assert_eq!(
+        circuit.check_constraints().unwrap_err().label,
+        Some("mod".to_string())
+    );
+}
+
+#[test]
+fn one_hot_check_test() {
+    let mut circuit = Circuit::<Z251>::new();
+    let bits = vec![circuit.new_wire(), circuit.new_wire(), circuit.new_wire()];
+    let check = circuit.new_one_hot_check(&bits);
+
+    let set = |circuit: &mut Circuit<Z251>, values: [usize; 3]| {
+        circuit.reset();
+        for (&bit, &value) in bits.iter().zip(values.iter()) {
+            circuit.set_value(bit, Z251::from(value));
+        }
+    };
+
+    set(&mut circuit, [0, 1, 0]);
+    assert_eq!(circuit.evaluate(check), Z251::zero());
+    assert!(circuit.check_constraints().is_ok());
+
+    set(&mut circuit, [1, 1, 0]);
+    assert_eq!(
+        circuit.check_constraints().unwrap_err().label,
+        Some("one hot".to_string())
+    );
+
+    set(&mut circuit, [0, 0, 0]);
+    assert_eq!(
+        circuit.check_constraints().unwrap_err().label,
+        Some("one hot".to_string())
+    );
+
+    // The sum is one but the wires are not all bits
+    set(&mut circuit, [2, 250, 0]);
+    assert_eq!(circuit.evaluate(check), Z251::zero());
+    assert!(circuit.check_constraints().is_err());
+}
+
+#[test]
+fn with_tag_test() {
+    let mut circuit = Circuit::<Z251>::new();
+    let (x, y) = (circuit.new_wire(), circuit.new_wire());
+
+    let (and, xor) = circuit.with_tag("hash", |c| {
+        let and = c.new_and(x, y);
+        let xor = c.with_tag("xor", |c| c.new_xor(x, y));
+        (and, xor)
+    });
+    let or = circuit.new_or(and, xor);
+
+    assert_eq!(circuit.sub_circuits().count(), 4);
+    assert_eq!(circuit.tagged_sub_circuits("hash").len(), 2);
+    assert_eq!(circuit.tagged_sub_circuits("xor").len(), 1);
+    assert!(circuit.tagged_sub_circuits("or").is_empty());
+    assert_eq!(
+        circuit.tags(SubCircuitId(1)),
+        &["hash".to_string(), "xor".to_string()]
+    );
+
+    // The untagged product of x and y is not merged with the tagged one
+    let untagged = circuit.new_and(y, x);
+    let replaced = circuit.common_subexpr_elim_tagged("hash");
+    assert!(replaced.is_empty());
+
+    let again = circuit.with_tag("hash", |c| c.new_and(y, x));
+    let replaced = circuit.common_subexpr_elim_tagged("hash");
+    assert_eq!(replaced.len(), 1);
+    assert_eq!(replaced[&again], and);
+    assert_eq!(circuit.tagged_sub_circuits("hash").len(), 2);
+
+    circuit.set_value(x, Z251::from(1));
+    circuit.set_value(y, Z251::from(0));
+    assert_eq!(circuit.evaluate(or), Z251::from(1));
+    assert_eq!(circuit.evaluate(untagged), Z251::from(0));
+}
+
+#[test]
+fn sha256_test() {
+    let cases: [(&[u8], [u8; 32]); 2] = [
+        (
+            &b"abc"[..],
+            [
+                186, 120, 22, 191, 143, 1, 207, 234, 65, 65, 64, 222, 93, 174, 34, 35, 176, 3, 97,
+                163, 150, 23, 122, 156, 180, 16, 255, 97, 242, 0, 21, 173,
+            ],
+        ),
+        // Padding this one takes a second block
+        (
+            &b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"[..],
+            [
+                36, 141, 106, 97, 210, 6, 56, 184, 229, 192, 38, 147, 12, 62, 96, 57, 163, 60, 228,
+                89, 100, 255, 33, 103, 246, 236, 237, 212, 25, 219, 6, 193,
+            ],
+        ),
+    ];
+
+    for (input, expected) in cases.iter() {
+        let mut circuit = Circuit::<FrLocal>::new();
+        let input = circuit.set_new_word8_vec(input.iter());
+        let hash = circuit.sha256(&input);
+
+        let output: Vec<u8> = circuit.evaluate_to_vec(hash.iter());
+        assert_eq!(output, expected.to_vec());
+        assert!(circuit.check_constraints().is_ok());
+    }
+}
+
+#[test]
+#[should_panic(expected = "more than 2^33 elements")]
+fn word32_add_mod_small_field_test() {
+    let mut circuit = Circuit::<Z251>::new();
+    let (a, b) = (circuit.new_word32(), circuit.new_word32());
+    circuit.word32_add_mod(a, b);
+}
@@ -129,7 +129,53 @@ pub fn to_word8(input: impl Iterator<Item = WireId>) -> Word8 {
 // references. The reason you don't now is the way this function
 // interacts with to_word8 and the way you are using to_word8
 pub fn flatten_word8<'a>(input: impl IntoIterator<Item = &'a Word8>) -> Vec<WireId> {
-    input.into_iter().flat_map(|x| x.iter()).cloned().collect()
+    flatten_word8_with_order(input, ByteOrder::default())
+}
+
+/// The order of the bits within each byte when a stream of `Word8` is
+/// flattened. The bytes themselves always stay in the order they are given.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ByteOrder {
+    /// Least significant bit first. This is how `Word8` stores its bits and
+    /// is the order used by `flatten_word8` and `to_field_bits`.
+    LittleEndian,
+    /// Most significant bit first.
+    BigEndian,
+}
+
+impl Default for ByteOrder {
+    fn default() -> Self {
+        ByteOrder::LittleEndian
+    }
+}
+
+/// Flattens a stream of `Word8` into their `WireId`s, with the bits of each
+/// byte in the given order.
+///
+/// ```
+/// use zksnark::field::z251::Z251;
+/// use zksnark::groth16::circuit::*;
+///
+/// let mut circuit = Circuit::<Z251>::new();
+/// let word = circuit.new_word8();
+///
+/// let little = flatten_word8_with_order(&[word], ByteOrder::LittleEndian);
+/// let big = flatten_word8_with_order(&[word], ByteOrder::BigEndian);
+/// assert_eq!(little[0], big[7]);
+/// ```
+pub fn flatten_word8_with_order<'a>(
+    input: impl IntoIterator<Item = &'a Word8>,
+    order: ByteOrder,
+) -> Vec<WireId> {
+    input
+        .into_iter()
+        .flat_map(|x| {
+            let mut bits = x.iter().cloned().collect::<Vec<_>>();
+            if order == ByteOrder::BigEndian {
+                bits.reverse();
+            }
+            bits
+        }).collect()
 }
 
 /// ## Usage Details:
@@ -219,7 +265,89 @@ impl IndexMut<usize> for Word64 {
     }
 }
 
+/// `Word32` is a placeholder for a u32 in the same way that `Word64` is for a
+/// u64, and is stored the same way: the first `Word8` holds the least
+/// significant byte.
+///
+/// IMPORTANT: Only input either 0 or 1 as inputs to `Word32` wires, or just
+/// use the provided constructor in `Circuit`!
+#[derive(Clone, Copy, Debug)]
+pub struct Word32([Word8; 4]);
+
+impl Word32 {
+    pub fn iter(&self) -> Iter<Word8> {
+        self.0.iter()
+    }
+    pub fn iter_mut(&mut self) -> IterMut<Word8> {
+        self.0.iter_mut()
+    }
+}
+
+pub struct Word32Iter<'a> {
+    count: usize,
+    wrd32: &'a Word32,
+}
+
+impl<'a> Iterator for Word32Iter<'a> {
+    type Item = &'a WireId;
+
+    fn next(&mut self) -> Option<&'a WireId> {
+        if self.count < 32 {
+            let x = Some(&self.wrd32[self.count / 8][self.count % 8]);
+            self.count += 1;
+            x
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a Word32 {
+    type Item = &'a WireId;
+    type IntoIter = Word32Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Word32Iter {
+            count: 0,
+            wrd32: &self,
+        }
+    }
+}
+
+impl<'a> BinaryInput for &'a Word32 {}
+impl<'a> CanConvert<u32> for &'a Word32 {}
+
+impl PartialEq for Word32 {
+    fn eq(&self, other: &Word32) -> bool {
+        self.0 == other.0
+    }
+}
+impl Eq for Word32 {}
+
+impl Default for Word32 {
+    fn default() -> Word32 {
+        Word32([Word8::default(); 4])
+    }
+}
+
+impl Index<usize> for Word32 {
+    type Output = Word8;
+
+    fn index<'a>(&'a self, index: usize) -> &'a Word8 {
+        &self.0[index]
+    }
+}
+
+impl IndexMut<usize> for Word32 {
+    fn index_mut<'a>(&'a mut self, index: usize) -> &'a mut Word8 {
+        &mut self.0[index]
+    }
+}
+
 impl<T> BinaryInput for [T] where T: BinaryInput {}
+// A slice of wires is taken to be bits, least significant first, such as the
+// inputs of `build_comparator`
+impl<'a> BinaryInput for &'a [WireId] {}
 impl<T> BinaryInput for Vec<T> where T: BinaryInput {}
 impl<'a, T> BinaryInput for Iter<'a, T> where T: BinaryInput {}
 
@@ -268,6 +396,34 @@ pub fn rotate_word64_right(input: Word64, by: usize) -> Word64 {
     wrd64
 }
 
+/// Rotates a Word32's bits by moving bit a position `i` into position `i-by`
+/// modulo the word size, in the same way as `rotate_word64_right`.
+pub fn rotate_word32_right(input: Word32, by: usize) -> Word32 {
+    let mut wrd32 = Word32::default();
+    input
+        .iter()
+        .flat_map(|x| x.iter())
+        .cycle()
+        .skip(by % 32)
+        .take(32)
+        .zip(iproduct!(0..4, 0..8))
+        .for_each(|(&wire_id, (i, j))| wrd32[i][j] = wire_id);
+    wrd32
+}
+
+/// Shifts a Word32's bits by moving bit at position `i` into position `i-by`,
+/// filling the most significant bits with the zero wire.
+pub fn shift_word32_right(input: Word32, by: usize) -> Word32 {
+    let mut wrd32 = Word32::default();
+    input
+        .iter()
+        .flat_map(|x| x.iter())
+        .skip(by)
+        .zip(iproduct!(0..4, 0..8))
+        .for_each(|(&wire_id, (i, j))| wrd32[i][j] = wire_id);
+    wrd32
+}
+
 /// This is a convenience function to create a `Word64` from exactly
 /// 64 WireId any more or less will cause a panic
 pub fn to_word64(input: impl Iterator<Item = WireId>) -> Word64 {
@@ -327,6 +483,98 @@ pub const PI: [usize; 24] = [
     10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1,
 ];
 
+/// The round constants of SHA-256.
+pub const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1,
+    0x923f82a4, 0xab1c5ed5, 0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3,
+    0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174, 0xe49b69c1, 0xefbe4786,
+    0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147,
+    0x06ca6351, 0x14292967, 0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13,
+    0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85, 0xa2bfe8a1, 0xa81a664b,
+    0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a,
+    0x5b9cca4f, 0x682e6ff3, 0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208,
+    0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// The initialization vector of BLAKE2s, which is the same as that of SHA-256.
+pub const BLAKE2S_IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+    0x5be0cd19,
+];
+
+/// The initial hash value of SHA-256.
+pub const SHA256_IV: [u32; 8] = BLAKE2S_IV;
+
+/// The permutation of the message words used by each of the 10 rounds of
+/// BLAKE2s.
+pub const BLAKE2S_SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+/// The keccak-f[1600] permutation computed natively, outside of a circuit.
+/// This is the same permutation that `Circuit::keccak256` builds, so it can
+/// be used to check the circuit one round at a time with
+/// `keccak_f1600_round`.
+///
+/// ```
+/// use zksnark::groth16::circuit::keccak_f1600;
+///
+/// let mut state = [0u64; 25];
+/// keccak_f1600(&mut state);
+/// assert_eq!(state[0], 0xf1258f7940e1dde7);
+/// ```
+pub fn keccak_f1600(state: &mut [u64; 25]) {
+    for round in 0..24 {
+        keccak_f1600_round(state, round);
+    }
+}
+
+/// The round numbered `round` (from 0 to 23) of `keccak_f1600`.
+pub fn keccak_f1600_round(a: &mut [u64; 25], round: usize) {
+    let mut array = [0u64; 5];
+
+    // Theta
+    for (x, column) in array.iter_mut().enumerate() {
+        *column = (0..5).fold(0, |acc, y| acc ^ a[x + 5 * y]);
+    }
+    for x in 0..5 {
+        for y in 0..5 {
+            a[5 * y + x] ^= array[(x + 4) % 5] ^ array[(x + 1) % 5].rotate_left(1);
+        }
+    }
+
+    // Rho and pi
+    let mut last = a[1];
+    for (&pi, &rho) in PI.iter().zip(RHO.iter()) {
+        array[0] = a[pi];
+        a[pi] = last.rotate_left(rho as u32);
+        last = array[0];
+    }
+
+    // Chi
+    for y in 0..5 {
+        let row = &mut a[5 * y..5 * y + 5];
+        array.copy_from_slice(row);
+        for (x, lane) in row.iter_mut().enumerate() {
+            *lane = array[x] ^ (!array[(x + 1) % 5] & array[(x + 2) % 5]);
+        }
+    }
+
+    // Iota
+    a[0] ^= RC[round];
+}
+
 /// This is copied from the std library because it was marked as a nightly only
 /// feature, not because it is unstable, but because they were not sure if it
 /// should be added and named where it was.
@@ -370,6 +618,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn rotate_word32_right_test() {
+        let a_wrd32: Word32 = (0..4)
+            .map(|i| to_word8((8 * i..8 * i + 8).map(WireId)))
+            .enumerate()
+            .fold(Word32::default(), |mut acc, (i, wrd8)| {
+                acc[i] = wrd8;
+                acc
+            });
+        let rotated = rotate_word32_right(a_wrd32, 7);
+        assert_eq!(rotated[0][0], WireId(7));
+        assert_eq!(rotated[3][7], WireId(6));
+        assert_eq!(rotate_word32_right(rotated, 25), a_wrd32);
+    }
+
+    #[test]
+    fn shift_word32_right_test() {
+        let a_wrd32: Word32 = (0..4)
+            .map(|i| to_word8((8 * i..8 * i + 8).map(WireId)))
+            .enumerate()
+            .fold(Word32::default(), |mut acc, (i, wrd8)| {
+                acc[i] = wrd8;
+                acc
+            });
+        let shifted = shift_word32_right(a_wrd32, 3);
+        assert_eq!(shifted[0][0], WireId(3));
+        assert_eq!(shifted[3][4], WireId(31));
+        assert_eq!(shifted[3][5], WireId(0));
+    }
+
     #[test]
     fn rotate_single_test() {
         let a_wrd64: Word64 = to_word64((0..64).map(WireId));
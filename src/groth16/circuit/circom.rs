@@ -0,0 +1,193 @@
+//! Importing the R1CS of a circom circuit from the JSON that
+//! `snarkjs r1cs export json` writes, so that circuits written in circom can
+//! be proven with the groth16 of this crate.
+
+use super::super::fr::{FrLocal, FR_MODULUS};
+use super::builder::SubCircuitId;
+use super::{gate_root, Circuit, CircuitInstance, WireId};
+use field::{FieldIdentity, FieldParseErr};
+use serde_json::Value;
+use std::convert::TryFrom;
+use std::fmt;
+use std::iter::once;
+
+/// The error from `import_circom_r1cs`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CircomImportErr {
+    /// The input is not valid JSON.
+    Json(String),
+    /// The JSON is not laid out as circom's R1CS, the string says where.
+    Malformed(String),
+    /// The R1CS is over the field with the given characteristic rather than
+    /// that of `FrLocal`.
+    WrongPrime(String),
+    /// A coefficient is not an element of the field of `FrLocal`.
+    Coefficient(FieldParseErr),
+    /// A constraint uses a signal that is not below `nVars`.
+    UnknownSignal(usize),
+}
+
+impl fmt::Display for CircomImportErr {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CircomImportErr::Json(ref e) => write!(fmt, "invalid JSON: {}", e),
+            CircomImportErr::Malformed(ref at) => write!(fmt, "malformed R1CS at {}", at),
+            CircomImportErr::WrongPrime(ref p) => {
+                write!(fmt, "the R1CS is over {} rather than the field of FrLocal", p)
+            }
+            CircomImportErr::Coefficient(ref e) => write!(fmt, "bad coefficient: {}", e),
+            CircomImportErr::UnknownSignal(s) => write!(fmt, "unknown signal {}", s),
+        }
+    }
+}
+
+fn malformed(at: &str) -> CircomImportErr {
+    CircomImportErr::Malformed(at.to_string())
+}
+
+/// Builds a `CircuitInstance` from the R1CS of a circom circuit, as the JSON
+/// from `snarkjs r1cs export json`. Each signal other than the constant one
+/// (signal 0, which is the unity wire) is an input wire, so the weights are
+/// given by the whole circom witness without its leading one:
+///
+/// ```text
+/// instance.weights(witness[1..].to_vec())
+/// ```
+///
+/// A constraint `A * B = C` becomes the gate `A * B` followed by the check
+/// `(A * B - C) * 1`, whose output is verified to be zero. The verifier gives
+/// the public signals in circom's order, the outputs and then the public
+/// inputs, followed by a zero for each constraint.
+pub fn import_circom_r1cs(
+    json: &str,
+) -> Result<CircuitInstance<FrLocal, fn(SubCircuitId) -> FrLocal>, CircomImportErr> {
+    let r1cs: Value =
+        ::serde_json::from_str(json).map_err(|e| CircomImportErr::Json(e.to_string()))?;
+
+    if let Some(prime) = r1cs.get("prime") {
+        let prime = prime.as_str().ok_or_else(|| malformed("prime"))?;
+        if prime != FR_MODULUS {
+            return Err(CircomImportErr::WrongPrime(prime.to_string()));
+        }
+    }
+
+    let count = |key: &str| {
+        r1cs.get(key)
+            .and_then(Value::as_u64)
+            .map(|n| n as usize)
+            .ok_or_else(|| malformed(key))
+    };
+    let n_vars = count("nVars")?;
+    let public = count("nOutputs")? + count("nPubInputs")?;
+    if n_vars == 0 || public >= n_vars {
+        return Err(malformed("nVars"));
+    }
+    let constraints = r1cs
+        .get("constraints")
+        .and_then(Value::as_array)
+        .ok_or_else(|| malformed("constraints"))?;
+
+    let mut circuit = Circuit::new();
+    let one = circuit.unity_wire();
+    let signals = once(one)
+        .chain((1..n_vars).map(|_| circuit.new_wire()))
+        .collect::<Vec<_>>();
+
+    // An empty linear combination is zero, which is the zero weight of the
+    // unity wire since a sub circuit needs an input on each side
+    let linear_combination = |terms: &Value| -> Result<Vec<(FrLocal, WireId)>, CircomImportErr> {
+        let terms = terms
+            .as_object()
+            .ok_or_else(|| malformed("a linear combination"))?;
+        if terms.is_empty() {
+            return Ok(vec![(FrLocal::zero(), one)]);
+        }
+
+        terms
+            .iter()
+            .map(|(signal, coefficient)| {
+                let signal = signal
+                    .parse::<usize>()
+                    .map_err(|_| malformed(&format!("signal '{}'", signal)))?;
+                let wire = *signals
+                    .get(signal)
+                    .ok_or(CircomImportErr::UnknownSignal(signal))?;
+                let coefficient = coefficient
+                    .as_str()
+                    .ok_or_else(|| malformed(&format!("the coefficient of signal {}", signal)))?;
+                let coefficient =
+                    FrLocal::try_from(coefficient).map_err(CircomImportErr::Coefficient)?;
+
+                Ok((coefficient, wire))
+            })
+            .collect()
+    };
+
+    let mut checks = Vec::with_capacity(constraints.len());
+    for (i, constraint) in constraints.iter().enumerate() {
+        let abc = match constraint.as_array() {
+            Some(abc) if abc.len() == 3 => abc,
+            _ => return Err(malformed(&format!("constraint {}", i))),
+        };
+
+        let product =
+            circuit.new_sub_circuit(linear_combination(&abc[0])?, linear_combination(&abc[1])?);
+        let difference = once((FrLocal::one(), product))
+            .chain(
+                linear_combination(&abc[2])?
+                    .into_iter()
+                    .map(|(coefficient, wire)| (-coefficient, wire)),
+            )
+            .collect();
+        checks.push(circuit.new_sub_circuit(difference, vec![(FrLocal::one(), one)]));
+    }
+
+    let verification_wires = signals[1..public + 1]
+        .iter()
+        .cloned()
+        .chain(checks)
+        .collect();
+
+    Ok(CircuitInstance::new(
+        circuit,
+        verification_wires,
+        signals[1..].to_vec(),
+        gate_root::<FrLocal> as fn(SubCircuitId) -> FrLocal,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn import_errors() {
+        let err = |json: &str| match import_circom_r1cs(json) {
+            Err(e) => e,
+            Ok(_) => panic!("imported {}", json),
+        };
+
+        match err("{") {
+            CircomImportErr::Json(_) => (),
+            e => panic!("unexpected error {:?}", e),
+        }
+        assert_eq!(
+            err(r#"{"prime": "251", "nVars": 2, "nOutputs": 1, "nPubInputs": 0, "constraints": []}"#),
+            CircomImportErr::WrongPrime("251".to_string())
+        );
+        assert_eq!(
+            err(r#"{"nVars": 2, "nOutputs": 1, "nPubInputs": 0}"#),
+            malformed("constraints")
+        );
+        assert_eq!(
+            err(r#"{"nVars": 2, "nOutputs": 1, "nPubInputs": 0,
+                   "constraints": [[{"5": "1"}, {"1": "1"}, {}]]}"#),
+            CircomImportErr::UnknownSignal(5)
+        );
+        assert_eq!(
+            err(r#"{"nVars": 2, "nOutputs": 1, "nPubInputs": 0,
+                   "constraints": [[{"1": "1"}, {"1": "1"}]]}"#),
+            malformed("constraint 0")
+        );
+    }
+}
@@ -62,19 +62,32 @@
 //!
 
 use super::super::field::*;
-use std::collections::HashMap;
+use super::fr::FrLocal;
+use super::MaybeSync;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::str::FromStr;
 
 mod ast;
 mod builder;
+mod circom;
 pub mod dummy_rep;
 
 use self::ast::TokenList;
-use self::ast::{Expression, ParseErr};
+use self::ast::Expression;
+pub(crate) use self::ast::ParseErr;
 use self::builder::{ConnectionType, SubCircuitId};
 use self::dummy_rep::DummyRep;
 
-pub use self::builder::{BinaryInput, Circuit, WireId, Word64, Word8};
+pub use self::builder::{
+    BinaryInput, Circuit, UnsatisfiedConstraint, WireId, Word32, Word64, Word8,
+};
+pub use self::builder::{AliasErr, CircuitError, ConstantOutOfRange};
+pub use self::builder::{Gadget, GadgetWires, GreaterThanGadget, Keccak256Gadget, OrGadget};
+pub use self::builder::{HashFamily, Keccak256Hash, MiMCHash, Sha256Hash};
+pub use self::builder::{flatten_word8, flatten_word8_with_order, ByteOrder};
+pub use self::builder::{keccak_f1600, keccak_f1600_round};
+pub use self::circom::{import_circom_r1cs, CircomImportErr};
 
 pub struct CircuitInstance<T, F>
 where
@@ -85,56 +98,184 @@ where
     verification_wires: Vec<WireId>,
     input_wires: Vec<WireId>,
     ordered_wires: Vec<WireId>,
+    witness_order: Vec<WireId>,
     sub_circuit_point: F,
 }
 
+/// A wire given more than once as a verification wire of a
+/// `CircuitInstance`. Its weight would be counted once for each time it is
+/// given when the verifier combines the public inputs, which is unsound.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DuplicatePublicInput {
+    pub wire: WireId,
+}
+
+impl fmt::Display for DuplicatePublicInput {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "wire {:?} is a public input more than once", self.wire)
+    }
+}
+
+/// The ways that `CircuitInstance::weights_with_hints` can fail.
+#[derive(Clone, Debug, PartialEq)]
+pub enum HintErr {
+    /// A wire given a value is not a hint wire, so its value has to be
+    /// computed from the inputs.
+    NotAHint(WireId),
+    /// A constraint does not hold for the inputs and hints.
+    Unsatisfied(UnsatisfiedConstraint),
+}
+
+impl fmt::Display for HintErr {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HintErr::NotAHint(wire) => write!(fmt, "wire {:?} is not a hint wire", wire),
+            HintErr::Unsatisfied(ref err) => write!(fmt, "{}", err),
+        }
+    }
+}
+
 impl<T, F> CircuitInstance<T, F>
 where
     T: Copy + Field,
     F: Fn(SubCircuitId) -> T,
 {
+    /// The same as `new` but gives an error if a wire appears more than once
+    /// in `verification_wires`, which `new` does not check.
+    pub fn try_new(
+        circuit: Circuit<T>,
+        verification_wires: Vec<WireId>,
+        input_wires: Vec<WireId>,
+        sub_circuit_point: F,
+    ) -> Result<Self, DuplicatePublicInput> {
+        let mut seen = HashSet::new();
+        if let Some(&wire) = verification_wires.iter().find(|w| !seen.insert(**w)) {
+            return Err(DuplicatePublicInput { wire });
+        }
+
+        Ok(Self::new(
+            circuit,
+            verification_wires,
+            input_wires,
+            sub_circuit_point,
+        ))
+    }
+
     pub fn new(
         circuit: Circuit<T>,
         verification_wires: Vec<WireId>,
         input_wires: Vec<WireId>,
         sub_circuit_point: F,
     ) -> Self {
-        let mut ordered_wires = Vec::with_capacity(circuit.num_wires());
-        ordered_wires.push(circuit.unity_wire());
-
-        let (verification_ids, witness_ids) = circuit
-            .wire_assignments()
-            .keys()
-            .filter(|w| **w != circuit.unity_wire())
-            .partition::<Vec<_>, _>(|k| verification_wires.contains(k));
-
-        // Assign the wires that are to be verified to the lower indices
-        ordered_wires.append(
-            &mut verification_ids
-                .into_iter()
-                .chain(witness_ids.into_iter())
-                .collect::<Vec<_>>(),
-        );
+        let ordered_wires = order_wires(&circuit, &verification_wires, &[]);
 
         CircuitInstance {
             circuit,
             verification_wires,
             input_wires,
             ordered_wires,
+            witness_order: Vec::new(),
             sub_circuit_point,
         }
     }
 
-    pub fn weights(&mut self, inputs: Vec<T>) -> Vec<T> {
+    /// The values of every wire for these values of the input wires. The
+    /// first is always one, since it is the value of `Circuit::one_wire`,
+    /// then come the verification wires in the order they were given.
+    ///
+    /// With the `parallel` feature the sub circuits are evaluated with
+    /// `Circuit::evaluate_parallel`, which gives the same weights.
+    pub fn weights(&mut self, inputs: Vec<T>) -> Vec<T>
+    where
+        T: MaybeSync,
+    {
+        self.set_inputs(inputs);
+        #[cfg(feature = "parallel")]
+        self.circuit.evaluate_parallel();
+        self.current_weights()
+    }
+
+    /// The values in `weights` that `verify` expects as its inputs, which
+    /// are those of the verification wires in the order they were given.
+    /// The value of the unity wire is left out since `verify` supplies it.
+    ///
+    /// Panics if `weights` are too few to be the weights of this instance.
+    pub fn public_inputs_from_witness(&self, weights: &[T]) -> Vec<T> {
+        weights[1..self.verification_wires.len() + 1].to_vec()
+    }
+
+    /// The same as `weights` but also checks that every constraint of the
+    /// circuit holds for these inputs, such as those added by
+    /// `Circuit::new_nonzero`.
+    pub fn try_weights(&mut self, inputs: Vec<T>) -> Result<Vec<T>, UnsatisfiedConstraint>
+    where
+        T: MaybeSync,
+    {
+        let weights = self.weights(inputs);
+        self.circuit.check_constraints()?;
+
+        Ok(weights)
+    }
+
+    /// The same as `try_weights` but the hint wires (see
+    /// `Circuit::hint_wires`) in `hints` take the given values instead of
+    /// being computed, such as when they come from another party. The checks
+    /// on the hints still have to hold, and any hint that is not given is
+    /// computed as usual.
+    ///
+    /// Gives `HintErr::NotAHint` if a wire in `hints` is not a hint wire,
+    /// since the value of any other wire is computed from the inputs.
+    pub fn weights_with_hints(
+        &mut self,
+        inputs: Vec<T>,
+        hints: HashMap<WireId, T>,
+    ) -> Result<Vec<T>, HintErr> {
+        let hint_wires = self.circuit.hint_wires();
+        if let Some(&wire) = hints.keys().find(|w| !hint_wires.contains(w)) {
+            return Err(HintErr::NotAHint(wire));
+        }
+
+        self.set_inputs(inputs);
+        for (wire, value) in hints {
+            self.circuit.set_value(wire, value);
+        }
+
+        let weights = self.current_weights();
+        self.circuit
+            .check_constraints()
+            .map_err(HintErr::Unsatisfied)?;
+
+        Ok(weights)
+    }
+
+    /// Changes the value of a single input wire and returns the new weights.
+    /// Only the wires that depend on `wire` are recomputed, the rest keep the
+    /// values from the last call to `weights` or `update_input`.
+    pub fn update_input(&mut self, wire: WireId, value: T) -> Vec<T> {
+        if !self.input_wires.contains(&wire) {
+            panic!("update_input: wire is not an input wire of this instance")
+        }
+
+        self.circuit.update_value(wire, value);
+        self.current_weights()
+    }
+
+    fn set_inputs(&mut self, inputs: Vec<T>) {
         if inputs.len() != self.input_wires.len() {
             panic!("must have the same number of input wires and assignments")
         }
 
+        // Forget anything evaluated with previous inputs, but keep the values
+        // that were set on the circuit itself such as constants
+        self.circuit.reset_computed();
+
         // Set the values of the input wires of the circuit
         for (wire, value) in self.input_wires.iter().zip(inputs.iter()) {
             self.circuit.set_value(*wire, *value);
         }
+    }
 
+    fn current_weights(&mut self) -> Vec<T> {
         // Iterate through all of the wires and collect the values
         let CircuitInstance {
             ordered_wires,
@@ -149,6 +290,114 @@ where
     }
 }
 
+impl<F> CircuitInstance<FrLocal, F>
+where
+    F: Fn(SubCircuitId) -> FrLocal,
+{
+    /// Replaces `wires` as public inputs with a single commitment to them,
+    /// which is a `MiMCHash` of their values computed in the circuit. The
+    /// returned commitment wire is verified after the remaining verification
+    /// wires, so the verifier can check the proof against the commitment
+    /// without learning the values themselves.
+    ///
+    /// The hash is only a permutation for the exponent and rounds of the
+    /// field of `FrLocal`, so only circuits over `FrLocal` can commit.
+    ///
+    /// The commitment is deterministic, so if the values could be guessed
+    /// then a random private input should be included in `wires` to blind it.
+    pub fn commit_output(&mut self, wires: &[WireId]) -> WireId {
+        let commitment = commitment_hash().hash(&mut self.circuit, wires);
+
+        self.verification_wires.retain(|w| !wires.contains(w));
+        self.verification_wires.push(commitment);
+        self.ordered_wires = order_wires(
+            &self.circuit,
+            &self.verification_wires,
+            &self.witness_order,
+        );
+
+        commitment
+    }
+
+    /// Replaces every public input with a single hash of them all, made with
+    /// `commit_output`, so that the verifier only has one input term to
+    /// compute. The verifier recomputes the hash from the values of the
+    /// inputs with `aggregate_public_inputs`, as `verify_aggregated` does.
+    pub fn aggregate_inputs(&mut self) -> WireId {
+        let wires = self.verification_wires.clone();
+        self.commit_output(&wires)
+    }
+}
+
+/// The exponent of the MiMC used by `CircuitInstance::commit_output`. For
+/// the cipher to be a permutation it has to be coprime to `r - 1`, where `r`
+/// is the order of the field of `FrLocal`, and 3 divides `r - 1`.
+const COMMITMENT_EXPONENT: usize = 5;
+
+/// The number of bits in the order of the field of `FrLocal`, which sets the
+/// number of rounds of the commitment.
+const COMMITMENT_FIELD_BITS: usize = 254;
+
+/// The MiMC used by `CircuitInstance::commit_output`.
+fn commitment_hash() -> MiMCHash<FrLocal> {
+    MiMCHash::for_field(COMMITMENT_EXPONENT, COMMITMENT_FIELD_BITS)
+}
+
+/// The hash that `CircuitInstance::aggregate_inputs` makes of the public
+/// inputs, given their values in the order they would be verified.
+pub fn aggregate_public_inputs(inputs: &[FrLocal]) -> FrLocal {
+    commitment_hash().hash_values(inputs)
+}
+
+/// The unity wire, then the wires that are to be verified, then every other
+/// wire in the circuit. Those other wires start with the ones in
+/// `witness_order` in that order, and the rest follow in no particular order.
+fn order_wires<T>(
+    circuit: &Circuit<T>,
+    verification_wires: &[WireId],
+    witness_order: &[WireId],
+) -> Vec<WireId>
+where
+    T: Copy + Field,
+{
+    let mut ordered_wires = Vec::with_capacity(circuit.num_wires());
+    ordered_wires.push(circuit.unity_wire());
+
+    // Assign the wires that are to be verified to the lower indices, in
+    // the same order they were given so that the verifier knows the order
+    // of its inputs
+    let verification_ids = verification_wires
+        .iter()
+        .filter(|w| **w != circuit.unity_wire())
+        .filter(|w| circuit.wire_assignments().contains_key(w))
+        .cloned();
+    let is_witness = |w: &&WireId| {
+        **w != circuit.unity_wire()
+            && !verification_wires.contains(w)
+            && circuit.wire_assignments().contains_key(w)
+    };
+    // The rest are in the order they were made, rather than the order of
+    // the map, so that building the same circuit twice gives the same QAP
+    let mut rest = circuit
+        .wire_assignments()
+        .keys()
+        .filter(&is_witness)
+        .filter(|w| !witness_order.contains(w))
+        .cloned()
+        .collect::<Vec<_>>();
+    rest.sort_by_key(|w| w.inner_id());
+    let witness_ids = witness_order
+        .iter()
+        .filter(&is_witness)
+        .cloned()
+        .chain(rest);
+
+    ordered_wires.extend(verification_ids.chain(witness_ids));
+    ordered_wires
+}
+
+/// The wires are in the same order as the weights of the instance, so the
+/// wire of `Circuit::one_wire` is at index 0, even if no sub circuit uses it.
 impl<'a, T, F> From<&'a CircuitInstance<T, F>> for DummyRep<T>
 where
     T: Field + Copy,
@@ -157,9 +406,9 @@ where
     fn from(instance: &CircuitInstance<T, F>) -> Self {
         use self::ConnectionType::*;
 
-        let mut u = vec![vec![]; instance.circuit.num_wires()];
-        let mut v = vec![vec![]; instance.circuit.num_wires()];
-        let mut w = vec![vec![]; instance.circuit.num_wires()];
+        let mut u = Vec::with_capacity(instance.ordered_wires.len());
+        let mut v = Vec::with_capacity(instance.ordered_wires.len());
+        let mut w = Vec::with_capacity(instance.ordered_wires.len());
         let roots = instance
             .circuit
             .sub_circuits()
@@ -170,7 +419,15 @@ where
         for wire in instance.ordered_wires.iter() {
             let (mut ui, mut vi, mut wi) = (Vec::new(), Vec::new(), Vec::new());
 
-            for connection in instance.circuit.assignments(wire) {
+            // The unity wire is always present even if no sub circuit uses it
+            let connections = instance
+                .circuit
+                .wire_assignments()
+                .get(wire)
+                .map(|c| c.as_slice())
+                .unwrap_or(&[]);
+
+            for connection in connections {
                 match connection {
                     Left(weight, sc_id) => ui.push(((instance.sub_circuit_point)(*sc_id), *weight)),
                     Right(weight, sc_id) => {
@@ -195,6 +452,162 @@ where
     }
 }
 
+/// A `Circuit` built from a .zk program by `ASTParser::to_circuit`, along with
+/// the wires named in the program's `in` and `verify` expressions.
+pub struct ParsedCircuit<T>
+where
+    T: Copy,
+{
+    pub circuit: Circuit<T>,
+    pub input_wires: Vec<WireId>,
+    pub verification_wires: Vec<WireId>,
+    /// The wires of the program's variables in the order that `weights`
+    /// gives their values, after the unity wire.
+    pub variable_wires: Vec<WireId>,
+}
+
+impl<T> ParsedCircuit<T>
+where
+    T: Field + From<usize>,
+{
+    /// Wraps the circuit up into a `CircuitInstance`. The nth gate of the
+    /// program gets the root n just as it does with `ASTParser::try_parse`,
+    /// and the weights are in the same order as those from `weights`.
+    pub fn into_instance(self) -> CircuitInstance<T, fn(SubCircuitId) -> T> {
+        let mut instance = CircuitInstance::new(
+            self.circuit,
+            self.verification_wires,
+            self.input_wires,
+            gate_root::<T> as fn(SubCircuitId) -> T,
+        );
+        instance.ordered_wires = order_wires(
+            &instance.circuit,
+            &instance.verification_wires,
+            &self.variable_wires,
+        );
+        instance.witness_order = self.variable_wires;
+
+        instance
+    }
+}
+
+/// What a position of the public inputs of a `VerifyingKey` holds, as given
+/// by `VerifyingKey::describe_inputs`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum InputRole {
+    /// The unity wire, which is always one. `verify` supplies it itself, so
+    /// it is not among the inputs that are passed to it.
+    ConstantOne,
+    /// A variable from the `in` list of the program.
+    PublicInput(String),
+    /// A variable that the program assigns, such as one from its `out` list.
+    DeclaredOutput(String),
+}
+
+/// The roles of the `verify` variables of the .zk program `code`, in order,
+/// for `VerifyingKey::describe_inputs`.
+pub(crate) fn verified_roles<T>(code: &str) -> Result<Vec<InputRole>, ParseErr>
+where
+    T: FromStr,
+{
+    use self::Expression::*;
+
+    let names = |vars: Vec<Expression<T>>| {
+        vars.into_iter()
+            .filter_map(|var| match var {
+                Var(vr) => Some(vr),
+                _ => None,
+            }).collect::<Vec<_>>()
+    };
+
+    let mut inputs = HashSet::new();
+    let mut verify = Vec::new();
+    for expression in ast::expressions::<T>(code)? {
+        match expression {
+            In(vars) => inputs.extend(names(vars)),
+            Verify(vars) => verify.extend(names(vars)),
+            _ => (),
+        }
+    }
+
+    Ok(verify
+        .into_iter()
+        .map(|var| {
+            if inputs.contains(&var) {
+                InputRole::PublicInput(var)
+            } else {
+                InputRole::DeclaredOutput(var)
+            }
+        }).collect())
+}
+
+/// The size of the `Circuit` that a .zk program would compile to, as given
+/// by `ASTParser::estimate_size`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SizeEstimate {
+    /// The number of sub circuits.
+    pub constraints: usize,
+    /// The number of wires, including the zero and unity wires, so that it is
+    /// comparable with `Circuit::num_wires`.
+    pub wires: usize,
+}
+
+/// The circuit for `a > b` on `bits` bit numbers, the same as
+/// `8bit_comparator.zk` is for 8 bits. The inputs are the bits of `a` and
+/// then the bits of `b`, each least significant first, and the verification
+/// wires are the result, then the bits of `b`, then a bit checker for each
+/// input in the same order. The result is only right when every input is 0
+/// or 1, so the verifier must give zero for each of the `2 * bits` bit
+/// checkers.
+///
+/// ```
+/// use zksnark::field::z251::Z251;
+/// use zksnark::groth16::circuit::build_comparator;
+///
+/// // 9 > 3, with 4 bits each
+/// let mut instance = build_comparator::<Z251>(4);
+/// let inputs = [1, 0, 0, 1, 1, 1, 0, 0].iter().map(|&b| Z251::from(b)).collect();
+///
+/// // The result is the first weight after the unity wire
+/// assert_eq!(instance.weights(inputs)[1], Z251::from(1));
+/// ```
+pub fn build_comparator<T>(bits: usize) -> CircuitInstance<T, fn(SubCircuitId) -> T>
+where
+    T: Field + From<usize>,
+{
+    assert!(bits > 0, "build_comparator: there must be at least one bit");
+
+    let mut circuit = Circuit::<T>::new();
+    let a = (0..bits).map(|_| circuit.new_wire()).collect::<Vec<_>>();
+    let b = (0..bits).map(|_| circuit.new_wire()).collect::<Vec<_>>();
+
+    let checks = circuit.bit_check(a.iter().chain(b.iter()));
+    for &check in checks.iter() {
+        circuit.set_label(check, "bit check");
+    }
+    let greater = circuit.greater_than(&a[..], &b[..]);
+
+    let verification_wires = ::std::iter::once(greater)
+        .chain(b.iter().cloned())
+        .chain(checks.into_iter())
+        .collect();
+    let input_wires = a.into_iter().chain(b.into_iter()).collect();
+
+    CircuitInstance::new(
+        circuit,
+        verification_wires,
+        input_wires,
+        gate_root::<T> as fn(SubCircuitId) -> T,
+    )
+}
+
+fn gate_root<T>(sub_circuit: SubCircuitId) -> T
+where
+    T: From<usize>,
+{
+    T::from(sub_circuit.inner_id() + 1)
+}
+
 pub trait RootRepresentation<F>
 where
     F: Field,
@@ -218,8 +631,410 @@ where
     fn try_parse(&str) -> Result<T, E>;
 }
 
+/// Parses .zk programs. The program is walked left to right and depth
+/// first, so the gates are numbered in the order their assignments appear,
+/// and the wires in the order their variables are first met: the `verify`
+/// variables, then each assignment's output followed by the variables of its
+/// left and then right input, each in the order written. The same program
+/// text therefore always gives the same QAP, and a setup made for it can be
+/// reused.
 pub struct ASTParser {}
 
+impl ASTParser {
+    /// Builds the `Circuit` described by a .zk program instead of going
+    /// straight to a `RootRepresentation`. This gives a chance to inspect or
+    /// transform the circuit before it is turned into a `QAP`, which can be
+    /// done with `ParsedCircuit::into_instance` and `DummyRep::from`.
+    ///
+    /// ```
+    /// use zksnark::field::z251::Z251;
+    /// use zksnark::groth16::circuit::ASTParser;
+    ///
+    /// let code = "(in a b c)
+    ///             (out x)
+    ///             (verify b x)
+    ///             (program
+    ///                 (= temp
+    ///                     (* a b))
+    ///                 (= x
+    ///                     (* 1 (+ (* 4 temp) c 6))))";
+    ///
+    /// let parsed = ASTParser::to_circuit::<Z251>(code).unwrap();
+    /// assert_eq!(parsed.circuit.sub_circuits().count(), 2);
+    /// assert_eq!(parsed.input_wires.len(), 3);
+    /// assert_eq!(parsed.verification_wires.len(), 2);
+    /// ```
+    pub fn to_circuit<F>(code: &str) -> Result<ParsedCircuit<F>, ParseErr>
+    where
+        F: Field + FromStr + From<usize>,
+    {
+        Self::compile(code, None)
+    }
+
+    /// The same as `to_circuit`, but programs may also use the `hash`
+    /// builtin, which is built with the given `HashFamily`. Its arguments
+    /// must all be variables.
+    ///
+    /// ```
+    /// use zksnark::field::z251::Z251;
+    /// use zksnark::groth16::circuit::{ASTParser, MiMCHash};
+    ///
+    /// let code = "(in a b)
+    ///             (out h)
+    ///             (verify h)
+    ///             (program
+    ///                 (= h
+    ///                     (hash a b)))";
+    ///
+    /// let parsed = ASTParser::to_circuit_with_hash::<Z251, _>(code, &MiMCHash::new(4)).unwrap();
+    /// assert_eq!(parsed.verification_wires.len(), 1);
+    /// ```
+    pub fn to_circuit_with_hash<F, H>(code: &str, hash: &H) -> Result<ParsedCircuit<F>, ParseErr>
+    where
+        F: Field + FromStr + From<usize>,
+        H: HashFamily<F>,
+    {
+        Self::compile(code, Some(hash))
+    }
+
+    /// Estimates the size of the `Circuit` that `to_circuit` would build
+    /// without building it, by walking the program. For a program without
+    /// the `hash` builtin the estimate is exact. Each `hash` is counted as a
+    /// single constraint, since its real cost depends on the `HashFamily`,
+    /// so with hashes the estimate is only a lower bound.
+    ///
+    /// ```
+    /// use zksnark::field::z251::Z251;
+    /// use zksnark::groth16::circuit::ASTParser;
+    ///
+    /// let code = "(in a b c)
+    ///             (out x)
+    ///             (verify b x)
+    ///             (program
+    ///                 (= temp
+    ///                     (* a b))
+    ///                 (= x
+    ///                     (* 1 (+ (* 4 temp) c 6))))";
+    ///
+    /// let estimate = ASTParser::estimate_size::<Z251>(code).unwrap();
+    /// assert_eq!(estimate.constraints, 2);
+    /// ```
+    pub fn estimate_size<F>(code: &str) -> Result<SizeEstimate, ParseErr>
+    where
+        F: FromStr,
+    {
+        use self::Expression::*;
+
+        let mut constraints = 0;
+        let mut variables = HashSet::new();
+
+        for expression in ast::expressions::<F>(code)? {
+            match expression {
+                In(vars) => vars
+                    .iter()
+                    .for_each(|var| collect_variables(var, &mut variables)),
+                Program(program) => {
+                    for assignment in program.iter() {
+                        constraints += 1;
+                        collect_variables(assignment, &mut variables);
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        Ok(SizeEstimate {
+            constraints,
+            // Every variable gets one wire, on top of the zero and unity
+            // wires
+            wires: variables.len() + 2,
+        })
+    }
+
+    fn compile<F>(
+        code: &str,
+        hash: Option<&dyn HashFamily<F>>,
+    ) -> Result<ParsedCircuit<F>, ParseErr>
+    where
+        F: Field + FromStr + From<usize>,
+    {
+        use self::Expression::*;
+        use self::ParseErr::*;
+
+        let expressions = ast::folded_expressions(code)?;
+        let (inputs, verify, program) = program_parts(expressions)?;
+
+        let mut circuit = Circuit::<F>::new();
+        let mut variables: HashMap<String, WireId> = HashMap::new();
+
+        for (n, assignment) in program.into_iter().enumerate() {
+            let gate_number = n + 1;
+
+            let (var, right) = match assignment {
+                Assign(left, right) => match *left {
+                    Var(var) => (var, *right),
+                    _ => return Err(not_a_variable(Some(gate_number), "=")),
+                },
+                _ => {
+                    return Err(StructureErr(
+                        Some(gate_number),
+                        "Program expression must be a list of '=' expressions".to_string(),
+                    ))
+                }
+            };
+
+            let output = match (right, hash) {
+                (Mul(left, right), _) => {
+                    let left_inputs =
+                        linear_combination(&mut circuit, &mut variables, *left, gate_number)?;
+                    let right_inputs =
+                        linear_combination(&mut circuit, &mut variables, *right, gate_number)?;
+
+                    if variables.contains_key(&var) {
+                        return Err(already_declared(gate_number));
+                    }
+
+                    circuit.new_sub_circuit(left_inputs, right_inputs)
+                }
+                (Hash(args), Some(hash)) => {
+                    let mut inputs = Vec::with_capacity(args.len());
+                    for arg in args.into_iter() {
+                        match arg {
+                            Var(vr) => inputs.push(
+                                *variables
+                                    .entry(vr)
+                                    .or_insert_with(|| circuit.new_wire()),
+                            ),
+                            _ => return Err(not_a_variable(Some(gate_number), "hash")),
+                        }
+                    }
+
+                    if variables.contains_key(&var) {
+                        return Err(already_declared(gate_number));
+                    }
+
+                    hash.hash(&mut circuit, &inputs)
+                }
+                (Hash(_), None) => {
+                    return Err(StructureErr(
+                        Some(gate_number),
+                        "'hash' needs a HashFamily, see ASTParser::to_circuit_with_hash"
+                            .to_string(),
+                    ))
+                }
+                _ => {
+                    return Err(StructureErr(
+                        Some(gate_number),
+                        "Expected a '*' expression to be assigned".to_string(),
+                    ))
+                }
+            };
+
+            circuit.set_label(output, &format!("(= {} ...) at gate {}", var, gate_number));
+            variables.insert(var, output);
+        }
+
+        let mut input_wires = Vec::with_capacity(inputs.len());
+        for input in inputs.into_iter() {
+            match input {
+                // An input that no gate uses still needs a wire to assign to
+                Var(vr) => input_wires.push(
+                    *variables
+                        .entry(vr)
+                        .or_insert_with(|| circuit.new_wire()),
+                ),
+                _ => return Err(not_a_variable(None, "in")),
+            }
+        }
+
+        let mut verification_wires = Vec::with_capacity(verify.len());
+        for var in verify.into_iter() {
+            match var {
+                Var(vr) => match variables.get(&vr) {
+                    Some(&wire) => verification_wires.push(wire),
+                    None => {
+                        return Err(StructureErr(
+                            None,
+                            "Verify variable must appear in the program".to_string(),
+                        ))
+                    }
+                },
+                _ => return Err(not_a_variable(None, "verify")),
+            }
+        }
+
+        // The same order as `weights` gives the values of the variables in
+        let token_list: TokenList<F> = ast::try_to_list(code.to_string())?;
+        let variable_wires = ast::variable_order(token_list)
+            .into_iter()
+            .filter_map(|var| variables.get(&var).cloned())
+            .collect::<Vec<_>>();
+
+        Ok(ParsedCircuit {
+            circuit,
+            input_wires,
+            verification_wires,
+            variable_wires,
+        })
+    }
+}
+
+/// Splits a program into the variables of its `in` and `verify` expressions
+/// and the assignments of its `program` expression. This is the check of the
+/// overall layout that `ASTParser::try_parse` and `ASTParser::to_circuit`
+/// share. Only the following format is accepted (empty lines don't matter):
+///
+/// ```text
+/// (in ...)
+/// (out ...)
+/// (verify ...)
+///
+/// (program ...)
+/// ```
+fn program_parts<F>(
+    expressions: Vec<Expression<F>>,
+) -> Result<(Vec<Expression<F>>, Vec<Expression<F>>, Vec<Expression<F>>), ParseErr> {
+    use self::Expression::*;
+    use self::ParseErr::*;
+
+    if expressions.len() != 4 {
+        return Err(StructureErr(
+            Some(0),
+            "Expected exactly one each of 'in', 'out', 'verify' and 'program'".to_string(),
+        ));
+    }
+
+    let mut exp_iter = expressions.into_iter();
+
+    let inputs = match exp_iter.next() {
+        Some(In(vars)) => vars,
+        _ => {
+            return Err(StructureErr(
+                Some(0),
+                "Expected first expression to be 'in'".to_string(),
+            ))
+        }
+    };
+    match exp_iter.next() {
+        Some(Out(_)) => (),
+        _ => {
+            return Err(StructureErr(
+                Some(0),
+                "Expected second expression to be 'out'".to_string(),
+            ))
+        }
+    }
+    let verify = match exp_iter.next() {
+        Some(Verify(vars)) => vars,
+        _ => {
+            return Err(StructureErr(
+                Some(0),
+                "Expected third expression to be 'verify'".to_string(),
+            ))
+        }
+    };
+    let program = match exp_iter.next() {
+        Some(Program(program)) => program,
+        _ => {
+            return Err(StructureErr(
+                Some(0),
+                "Expected fourth expression to be 'program'".to_string(),
+            ))
+        }
+    };
+
+    Ok((inputs, verify, program))
+}
+
+/// Adds the name of every variable in `expression` to `variables`.
+fn collect_variables<F>(expression: &Expression<F>, variables: &mut HashSet<String>) {
+    use self::Expression::*;
+
+    match *expression {
+        Var(ref vr) => {
+            variables.insert(vr.clone());
+        }
+        Assign(ref left, ref right) | Mul(ref left, ref right) => {
+            collect_variables(left, variables);
+            collect_variables(right, variables);
+        }
+        In(ref exps) | Out(ref exps) | Verify(ref exps) | Program(ref exps) | Add(ref exps)
+        | Sub(ref exps) | Hash(ref exps) => exps
+            .iter()
+            .for_each(|exp| collect_variables(exp, variables)),
+        Literal(_) => (),
+    }
+}
+
+fn already_declared(gate_number: usize) -> ParseErr {
+    ParseErr::StructureErr(
+        Some(gate_number),
+        "Already declared variable cannot be the output wire of a gate".to_string(),
+    )
+}
+
+/// The error for an argument of `keyword` that is not a variable, which
+/// `ast::parse_expression` should already have rejected.
+fn not_a_variable(gate_number: Option<usize>, keyword: &str) -> ParseErr {
+    ParseErr::StructureErr(
+        gate_number,
+        format!("Arguments of '{}' must be variables", keyword),
+    )
+}
+
+/// Turns one side of a multiplication gate into the `(weight, wire)` pairs
+/// that `Circuit::new_sub_circuit` expects. Variables that have not been seen
+/// before are given new wires.
+fn linear_combination<F>(
+    circuit: &mut Circuit<F>,
+    variables: &mut HashMap<String, WireId>,
+    expression: Expression<F>,
+    gate_number: usize,
+) -> Result<Vec<(F, WireId)>, ParseErr>
+where
+    F: Field,
+{
+    use self::Expression::*;
+    use self::ParseErr::*;
+
+    let mut wire = |circuit: &mut Circuit<F>, vr: String| {
+        *variables.entry(vr).or_insert_with(|| circuit.new_wire())
+    };
+
+    match expression {
+        Literal(lit) => Ok(vec![(lit, circuit.unity_wire())]),
+        Var(vr) => Ok(vec![(F::one(), wire(circuit, vr))]),
+        Add(a) => a
+            .into_iter()
+            .map(|exp| match exp {
+                Literal(lit) => Ok((lit, circuit.unity_wire())),
+                Var(vr) => Ok((F::one(), wire(circuit, vr))),
+                Mul(left, right) => match (*left, *right) {
+                    (Literal(lit), Var(vr)) => Ok((lit, wire(circuit, vr))),
+                    (Literal(_), _) => Err(StructureErr(
+                        Some(gate_number),
+                        "RHS of a '*' expression in a '+' expression must be a variable"
+                            .to_string(),
+                    )),
+                    _ => Err(StructureErr(
+                        Some(gate_number),
+                        "LHS of a '*' expression in a '+' expression must be a literal"
+                            .to_string(),
+                    )),
+                },
+                _ => Err(StructureErr(
+                    Some(gate_number),
+                    "Invalid expression found in '+' expression".to_string(),
+                )),
+            })
+            .collect(),
+        _ => Err(StructureErr(
+            Some(gate_number),
+            "Invalid expression found in '*' expression".to_string(),
+        )),
+    }
+}
+
 impl<F> TryParse<DummyRep<F>, F, ParseErr> for ASTParser
 where
     F: Field + Clone + FromStr + From<usize>,
@@ -228,7 +1043,7 @@ where
         use self::Expression::*;
         use self::ParseErr::*;
 
-        let expressions = ast::expressions(code)?;
+        let expressions = ast::folded_expressions(code)?;
 
         let mut variables: HashMap<String, usize> = HashMap::new();
         let mut gate_number = 0;
@@ -237,278 +1052,239 @@ where
         let mut w: Vec<Vec<(F, F)>> = vec![Vec::new()];
         let mut input: usize = 0;
 
-        // Only accept the following format (empty lines don't matter):
-        //
-        // (in ...)
-        // (out ...)
-        // (verify ...)
-        //
-        // (program ...)
+        let (_, verify, program) = program_parts(expressions)?;
 
-        if expressions.len() != 4 {
-            return Err(StructureErr(
-                Some(gate_number),
-                "Expected exactly one each of 'in', 'out', 'verify' and 'program'".to_string(),
-            ));
+        for var in verify.into_iter() {
+            match var {
+                Var(vr) => {
+                    let index = u.len();
+                    variables.insert(vr, index);
+
+                    u.push(Vec::new());
+                    v.push(Vec::new());
+                    w.push(Vec::new());
+                    input += 1;
+                }
+                _ => return Err(not_a_variable(None, "verify")),
+            }
         }
 
-        let mut exp_iter = expressions.clone().into_iter();
+        for assignment in program.into_iter() {
+            gate_number += 1;
 
-        match exp_iter.next() {
-            Some(In(_)) => (),
-            _ => {
-                return Err(StructureErr(
-                    Some(gate_number),
-                    "Expected first expression to be 'in'".to_string(),
-                ))
-            }
-        }
-        match exp_iter.next() {
-            Some(Out(_)) => (),
-            _ => {
-                return Err(StructureErr(
-                    Some(gate_number),
-                    "Expected second expression to be 'out'".to_string(),
-                ))
-            }
-        }
-        if let Some(Verify(vars)) = exp_iter.next() {
-            for var in vars.into_iter() {
-                match var {
-                    Var(vr) => {
+            if let Assign(left, right) = assignment {
+                if let Var(vr) = *left {
+                    // If this is the first appearance of the variable, add it to the list
+                    if !variables.contains_key(&vr) {
                         let index = u.len();
                         variables.insert(vr, index);
 
                         u.push(Vec::new());
                         v.push(Vec::new());
-                        w.push(Vec::new());
-                        input += 1;
-                    }
-                    _ => panic!("parse_expression() did not correctly parse 'verify'"),
-                }
-            }
-        } else {
-            return Err(StructureErr(
-                Some(gate_number),
-                "Expected third expression to be 'verify'".to_string(),
-            ));
-        }
-        if let Some(Program(program)) = exp_iter.next() {
-            for assignment in program.into_iter() {
-                gate_number += 1;
-
-                if let Assign(left, right) = assignment {
-                    if let Var(vr) = *left {
-                        // If this is the first appearance of the variable, add it to the list
-                        if !variables.contains_key(&vr) {
-                            let index = u.len();
-                            variables.insert(vr, index);
-
-                            u.push(Vec::new());
-                            v.push(Vec::new());
-                            w.push(vec![(gate_number.into(), 1.into())]);
-                        } else {
-                            // We can unwrap because we just checked that the key exists
-                            if *variables.get(&vr).unwrap() <= input {
-                                let index = variables.get(&vr).unwrap();
-                                if w[*index].len() != 0 {
-                                    return Err(StructureErr(
-                                        Some(gate_number),
-                                        "Varify variable cannot be the output of two different gates"
-                                            .to_string(),
-                                    ));
-                                }
-                                w[*index].push((gate_number.into(), 1.into()));
-                            } else {
+                        w.push(vec![(gate_number.into(), 1.into())]);
+                    } else {
+                        // We can unwrap because we just checked that the key exists
+                        if *variables.get(&vr).unwrap() <= input {
+                            let index = variables.get(&vr).unwrap();
+                            if w[*index].len() != 0 {
                                 return Err(StructureErr(
                                     Some(gate_number),
-                                    "Already declared variable cannot be the output wire of a gate"
+                                    "Varify variable cannot be the output of two different gates"
                                         .to_string(),
                                 ));
                             }
+                            w[*index].push((gate_number.into(), 1.into()));
+                        } else {
+                            return Err(StructureErr(
+                                Some(gate_number),
+                                "Already declared variable cannot be the output wire of a gate"
+                                    .to_string(),
+                            ));
                         }
-                    } else {
-                        panic!("parse_expression() did not correctly parse '='");
                     }
+                } else {
+                    return Err(not_a_variable(Some(gate_number), "="));
+                }
 
-                    let right = *right;
-                    if let Mul(left, right) = right {
-                        // Handle the left inputs
-                        match *left {
-                            Literal(lit) => u[0].push((gate_number.into(), lit)),
-                            Var(vr) => {
-                                if !variables.contains_key(&vr) {
-                                    let index = u.len();
-                                    variables.insert(vr, index);
-
-                                    u.push(vec![(gate_number.into(), 1.into())]);
-                                    v.push(Vec::new());
-                                    w.push(Vec::new());
-                                } else {
-                                    // We can unwrap because we just checked that the key exists
-                                    let index = variables.get(&vr).unwrap();
-                                    u[*index].push((gate_number.into(), 1.into()));
-                                }
+                let right = *right;
+                if let Hash(_) = right {
+                    return Err(StructureErr(
+                        Some(gate_number),
+                        "'hash' is only supported by ASTParser::to_circuit_with_hash"
+                            .to_string(),
+                    ));
+                }
+                if let Mul(left, right) = right {
+                    // Handle the left inputs
+                    match *left {
+                        Literal(lit) => u[0].push((gate_number.into(), lit)),
+                        Var(vr) => {
+                            if !variables.contains_key(&vr) {
+                                let index = u.len();
+                                variables.insert(vr, index);
+
+                                u.push(vec![(gate_number.into(), 1.into())]);
+                                v.push(Vec::new());
+                                w.push(Vec::new());
+                            } else {
+                                // We can unwrap because we just checked that the key exists
+                                let index = variables.get(&vr).unwrap();
+                                u[*index].push((gate_number.into(), 1.into()));
                             }
-                            Add(a) => {
-                                for exp in a.into_iter() {
-                                    match exp {
-                                        Literal(lit) => u[0].push((gate_number.into(), lit)),
-                                        Var(vr) => {
-                                            if !variables.contains_key(&vr) {
-                                                let index = u.len();
-                                                variables.insert(vr, index);
-
-                                                u.push(vec![(gate_number.into(), 1.into())]);
-                                                v.push(Vec::new());
-                                                w.push(Vec::new());
-                                            } else {
-                                                // We can unwrap because we just checked that the key exists
-                                                let index = variables.get(&vr).unwrap();
-                                                u[*index].push((gate_number.into(), 1.into()));
-                                            }
-                                        }
-                                        Mul(left, right) => {
-                                            let left = match *left {
-                                                Literal(lit) => lit,
-                                                _ => return Err(StructureErr(
-                                                    Some(gate_number),
-                                                    "LHS of a '*' expression in a '+' expression must be a literal".to_string()
-                                                )),
-                                            };
-                                            let right = match *right {
-                                                Var(vr) => vr,
-                                                _ => return Err(StructureErr(
-                                                    Some(gate_number),
-                                                    "RHS of a '*' expression in a '+' expression must be a variable".to_string()
-                                                )),
-                                            };
-
-                                            if !variables.contains_key(&right) {
-                                                let index = u.len();
-                                                variables.insert(right, index);
-
-                                                u.push(vec![(gate_number.into(), left)]);
-                                                v.push(Vec::new());
-                                                w.push(Vec::new());
-                                            } else {
-                                                // We can unwrap because we just checked that the key exists
-                                                let index = variables.get(&right).unwrap();
-                                                u[*index].push((gate_number.into(), left));
-                                            }
+                        }
+                        Add(a) => {
+                            for exp in a.into_iter() {
+                                match exp {
+                                    Literal(lit) => u[0].push((gate_number.into(), lit)),
+                                    Var(vr) => {
+                                        if !variables.contains_key(&vr) {
+                                            let index = u.len();
+                                            variables.insert(vr, index);
+
+                                            u.push(vec![(gate_number.into(), 1.into())]);
+                                            v.push(Vec::new());
+                                            w.push(Vec::new());
+                                        } else {
+                                            // We can unwrap because we just checked that the key exists
+                                            let index = variables.get(&vr).unwrap();
+                                            u[*index].push((gate_number.into(), 1.into()));
                                         }
-                                        _ => {
-                                            return Err(StructureErr(
+                                    }
+                                    Mul(left, right) => {
+                                        let left = match *left {
+                                            Literal(lit) => lit,
+                                            _ => return Err(StructureErr(
+                                                Some(gate_number),
+                                                "LHS of a '*' expression in a '+' expression must be a literal".to_string()
+                                            )),
+                                        };
+                                        let right = match *right {
+                                            Var(vr) => vr,
+                                            _ => return Err(StructureErr(
                                                 Some(gate_number),
-                                                "Invalid expression found in '+' expression"
-                                                    .to_string(),
-                                            ))
+                                                "RHS of a '*' expression in a '+' expression must be a variable".to_string()
+                                            )),
+                                        };
+
+                                        if !variables.contains_key(&right) {
+                                            let index = u.len();
+                                            variables.insert(right, index);
+
+                                            u.push(vec![(gate_number.into(), left)]);
+                                            v.push(Vec::new());
+                                            w.push(Vec::new());
+                                        } else {
+                                            // We can unwrap because we just checked that the key exists
+                                            let index = variables.get(&right).unwrap();
+                                            u[*index].push((gate_number.into(), left));
                                         }
                                     }
+                                    _ => {
+                                        return Err(StructureErr(
+                                            Some(gate_number),
+                                            "Invalid expression found in '+' expression"
+                                                .to_string(),
+                                        ))
+                                    }
                                 }
                             }
-                            _ => {
-                                return Err(StructureErr(
-                                    Some(gate_number),
-                                    "Invalid expression found in '*' expression".to_string(),
-                                ))
-                            }
                         }
+                        _ => {
+                            return Err(StructureErr(
+                                Some(gate_number),
+                                "Invalid expression found in '*' expression".to_string(),
+                            ))
+                        }
+                    }
 
-                        // Handle the right inputs
-                        match *right {
-                            Literal(lit) => v[0].push((gate_number.into(), lit)),
-                            Var(vr) => {
-                                if !variables.contains_key(&vr) {
-                                    let index = v.len();
-                                    variables.insert(vr, index);
-
-                                    u.push(Vec::new());
-                                    v.push(vec![(gate_number.into(), 1.into())]);
-                                    w.push(Vec::new());
-                                } else {
-                                    // We can unwrap because we just checked that the key exists
-                                    let index = variables.get(&vr).unwrap();
-                                    v[*index].push((gate_number.into(), 1.into()));
-                                }
+                    // Handle the right inputs
+                    match *right {
+                        Literal(lit) => v[0].push((gate_number.into(), lit)),
+                        Var(vr) => {
+                            if !variables.contains_key(&vr) {
+                                let index = v.len();
+                                variables.insert(vr, index);
+
+                                u.push(Vec::new());
+                                v.push(vec![(gate_number.into(), 1.into())]);
+                                w.push(Vec::new());
+                            } else {
+                                // We can unwrap because we just checked that the key exists
+                                let index = variables.get(&vr).unwrap();
+                                v[*index].push((gate_number.into(), 1.into()));
                             }
-                            Add(a) => {
-                                for exp in a.into_iter() {
-                                    match exp {
-                                        Literal(lit) => v[0].push((gate_number.into(), lit)),
-                                        Var(vr) => {
-                                            if !variables.contains_key(&vr) {
-                                                let index = v.len();
-                                                variables.insert(vr, index);
-
-                                                u.push(Vec::new());
-                                                v.push(vec![(gate_number.into(), 1.into())]);
-                                                w.push(Vec::new());
-                                            } else {
-                                                // We can unwrap because we just checked that the key exists
-                                                let index = variables.get(&vr).unwrap();
-                                                v[*index].push((gate_number.into(), 1.into()));
-                                            }
-                                        }
-                                        Mul(left, right) => {
-                                            let left = match *left {
-                                                Literal(lit) => lit,
-                                                _ => return Err(StructureErr(
-                                                    Some(gate_number),
-                                                    "LHS of a '*' expression in a '+' expression must be a literal".to_string()
-                                                )),
-                                            };
-                                            let right = match *right {
-                                                Var(vr) => vr,
-                                                _ => return Err(StructureErr(
-                                                    Some(gate_number),
-                                                    "RHS of a '*' expression in a '+' expression must be a variable".to_string()
-                                                )),
-                                            };
-
-                                            if !variables.contains_key(&right) {
-                                                let index = v.len();
-                                                variables.insert(right, index);
-
-                                                u.push(Vec::new());
-                                                v.push(vec![(gate_number.into(), left)]);
-                                                w.push(Vec::new());
-                                            } else {
-                                                // We can unwrap because we just checked that the key exists
-                                                let index = variables.get(&right).unwrap();
-                                                v[*index].push((gate_number.into(), left));
-                                            }
+                        }
+                        Add(a) => {
+                            for exp in a.into_iter() {
+                                match exp {
+                                    Literal(lit) => v[0].push((gate_number.into(), lit)),
+                                    Var(vr) => {
+                                        if !variables.contains_key(&vr) {
+                                            let index = v.len();
+                                            variables.insert(vr, index);
+
+                                            u.push(Vec::new());
+                                            v.push(vec![(gate_number.into(), 1.into())]);
+                                            w.push(Vec::new());
+                                        } else {
+                                            // We can unwrap because we just checked that the key exists
+                                            let index = variables.get(&vr).unwrap();
+                                            v[*index].push((gate_number.into(), 1.into()));
                                         }
-                                        _ => {
-                                            return Err(StructureErr(
+                                    }
+                                    Mul(left, right) => {
+                                        let left = match *left {
+                                            Literal(lit) => lit,
+                                            _ => return Err(StructureErr(
+                                                Some(gate_number),
+                                                "LHS of a '*' expression in a '+' expression must be a literal".to_string()
+                                            )),
+                                        };
+                                        let right = match *right {
+                                            Var(vr) => vr,
+                                            _ => return Err(StructureErr(
                                                 Some(gate_number),
-                                                "Invalid expression found in '+' expression"
-                                                    .to_string(),
-                                            ))
+                                                "RHS of a '*' expression in a '+' expression must be a variable".to_string()
+                                            )),
+                                        };
+
+                                        if !variables.contains_key(&right) {
+                                            let index = v.len();
+                                            variables.insert(right, index);
+
+                                            u.push(Vec::new());
+                                            v.push(vec![(gate_number.into(), left)]);
+                                            w.push(Vec::new());
+                                        } else {
+                                            // We can unwrap because we just checked that the key exists
+                                            let index = variables.get(&right).unwrap();
+                                            v[*index].push((gate_number.into(), left));
                                         }
                                     }
+                                    _ => {
+                                        return Err(StructureErr(
+                                            Some(gate_number),
+                                            "Invalid expression found in '+' expression"
+                                                .to_string(),
+                                        ))
+                                    }
                                 }
                             }
-                            _ => {
-                                return Err(StructureErr(
-                                    Some(gate_number),
-                                    "Invalid expression found in '*' expression".to_string(),
-                                ))
-                            }
+                        }
+                        _ => {
+                            return Err(StructureErr(
+                                Some(gate_number),
+                                "Invalid expression found in '*' expression".to_string(),
+                            ))
                         }
                     }
-                } else {
-                    return Err(StructureErr(
-                        Some(gate_number),
-                        "Program expression must be a list of '=' expressions".to_string(),
-                    ));
                 }
+            } else {
+                return Err(StructureErr(
+                    Some(gate_number),
+                    "Program expression must be a list of '=' expressions".to_string(),
+                ));
             }
-        } else {
-            return Err(StructureErr(
-                Some(gate_number),
-                "Expected fourth expression to be 'program'".to_string(),
-            ));
         }
 
         let roots = (1..gate_number + 1).map(|r| r.into()).collect::<Vec<_>>();
@@ -523,18 +1299,103 @@ where
     }
 }
 
+/// Runs `ASTParser::try_parse` on arbitrary bytes, for fuzzing. Whatever the
+/// input this must never panic. Returns whether the bytes were a valid
+/// program.
+#[doc(hidden)]
+pub fn fuzz_parse(data: &[u8]) -> bool {
+    use super::fr::FrLocal;
+
+    match ::std::str::from_utf8(data) {
+        Ok(code) => {
+            <ASTParser as TryParse<DummyRep<FrLocal>, _, _>>::try_parse(code).is_ok()
+        }
+        Err(_) => false,
+    }
+}
+
+/// The same as `weights` but for a program that has already been compiled
+/// with `ASTParser::to_circuit` and `ParsedCircuit::into_instance`, so the
+/// program is not parsed again. The weights are in the order of the wires of
+/// `instance`, which for such an instance is the order `weights` uses.
+///
+/// ```
+/// use zksnark::field::z251::Z251;
+/// use zksnark::groth16::circuit::{weights, weights_from_instance, ASTParser};
+///
+/// let code = "(in a b c)
+///             (out x)
+///             (verify b x)
+///             (program
+///                 (= temp
+///                     (* a b))
+///                 (= x
+///                     (* 1 (+ (* 4 temp) c 6))))";
+///
+/// let mut instance = ASTParser::to_circuit::<Z251>(code).unwrap().into_instance();
+/// let assignments = &[3.into(), 2.into(), 4.into()];
+///
+/// assert_eq!(
+///     weights_from_instance(&mut instance, assignments),
+///     weights(code, assignments)
+/// );
+/// ```
+pub fn weights_from_instance<T, F>(
+    instance: &mut CircuitInstance<T, F>,
+    values: &[T],
+) -> Result<Vec<T>, ParseErr>
+where
+    T: Copy + Field + MaybeSync,
+    F: Fn(SubCircuitId) -> T,
+{
+    if instance.input_wires.len() != values.len() {
+        return Err(ParseErr::StructureErr(
+            None,
+            "Wrong number of values supplied".to_string(),
+        ));
+    }
+
+    Ok(instance.weights(values.to_vec()))
+}
+
 pub fn weights<F>(code: &str, values: &[F]) -> Result<Vec<F>, ParseErr>
 where
     F: Clone + Field + FromStr + PartialEq,
+{
+    weights_batch(code, &[values]).map(|mut batch| batch.remove(0))
+}
+
+/// The same as calling `weights` with each set of `values` in turn, but the
+/// program is only parsed once. Gives the first error that any set of values
+/// gives, if there is one.
+pub fn weights_batch<F>(code: &str, values: &[&[F]]) -> Result<Vec<Vec<F>>, ParseErr>
+where
+    F: Clone + Field + FromStr + PartialEq,
+{
+    let expressions = ast::folded_expressions(code)?;
+    let token_list: TokenList<F> = ast::try_to_list(code.to_string())?;
+    let variables = ast::variable_order(token_list);
+
+    values
+        .iter()
+        .map(|values| evaluate_program(&expressions, &variables, values))
+        .collect()
+}
+
+/// The weights of a parsed program, see `weights`.
+fn evaluate_program<F>(
+    expressions: &[Expression<F>],
+    variables: &[String],
+    values: &[F],
+) -> Result<Vec<F>, ParseErr>
+where
+    F: Clone + Field,
 {
     use self::Expression::*;
     use self::ParseErr::*;
 
     let mut assignments: HashMap<String, F> = HashMap::new();
-    let expressions = ast::expressions(code)?;
-    let mut exp_iter = expressions.as_slice().iter();
-    let token_list: TokenList<F> = ast::try_to_list(code.to_string())?;
-    let variables = ast::variable_order(token_list);
+    let mut exp_iter = expressions.iter();
 
     let inputs = match exp_iter.next() {
         Some(In(i)) => i,
@@ -553,13 +1414,13 @@ where
         ));
     }
 
-    inputs.as_slice().iter().zip(values).for_each(|(e, val)| {
+    for (e, val) in inputs.iter().zip(values) {
         if let Var(var) = e {
             assignments.insert(var.clone(), val.clone());
         } else {
-            panic!("Under constained or malformed inputs".to_string());
+            return Err(not_a_variable(None, "in"));
         }
-    });
+    }
 
     match exp_iter.next() {
         Some(Out(_)) => (),
@@ -575,7 +1436,7 @@ where
         for var in vars.into_iter() {
             match var {
                 Var(_) => (),
-                _ => panic!("parse_expression() did not correctly parse 'verify'"),
+                _ => return Err(not_a_variable(None, "verify")),
             }
         }
     } else {
@@ -586,13 +1447,18 @@ where
     }
 
     if let Some(Program(program)) = exp_iter.next() {
-        for assignment in program.into_iter() {
+        for (n, assignment) in program.into_iter().enumerate() {
+            let gate_number = n + 1;
+
             if let Assign(left, right) = assignment {
                 if let Var(ref var) = **left {
                     if assignments.contains_key(var) {
                         return Err(StructureErr(
-                            None,
-                            "Attempted to assign to an already assigned variable".to_string(),
+                            Some(gate_number),
+                            format!(
+                                "Attempted to assign to an already assigned variable in (= {} ...)",
+                                var
+                            ),
                         ));
                     }
 
@@ -600,13 +1466,13 @@ where
                         Some(value) => assignments.insert(var.clone(), value),
                         None => {
                             return Err(StructureErr(
-                                None,
-                                "Under constrained expression".to_string(),
+                                Some(gate_number),
+                                format!("Under constrained expression in (= {} ...)", var),
                             ))
                         }
                     };
                 } else {
-                    panic!("parse_expression() did not correctly parse '='");
+                    return Err(not_a_variable(Some(gate_number), "="));
                 }
             } else {
                 return Err(StructureErr(
@@ -622,9 +1488,9 @@ where
         ));
     }
 
-    let weights = variables.into_iter().map(|v| {
+    let weights = variables.iter().map(|v| {
         assignments
-            .remove(&v)
+            .remove(v)
             .expect("Every variable should have an assignment")
     });
 
@@ -764,4 +1630,230 @@ mod tests {
 
         assert_eq!(Ok(expected), weights(&code, assignments));
     }
+
+    #[test]
+    fn weights_batch_test() {
+        let code = &*::std::fs::read_to_string("test_programs/simple.zk").unwrap();
+        let assignments: &[&[Z251]] = &[
+            &[3.into(), 2.into(), 4.into()],
+            &[0.into(), 7.into(), 1.into()],
+            &[250.into(), 5.into(), 100.into()],
+        ];
+
+        let batch = weights_batch(code, assignments).unwrap();
+        assert_eq!(batch.len(), 3);
+        for (values, batch_weights) in assignments.iter().zip(batch) {
+            assert_eq!(weights(code, values), Ok(batch_weights));
+        }
+
+        // A wrong number of values in any set is an error
+        assert!(weights_batch(code, &[&assignments[0][..], &assignments[1][..2]]).is_err());
+    }
+
+    #[test]
+    fn constraint_label_test() {
+        let code = "(in a b c)
+                    (out x)
+                    (verify b x)
+
+                    (program
+                        (= temp
+                            (* a b))
+                        (= x
+                            (* 1 (+ (* 4 temp) c 6))))";
+
+        let parsed = ASTParser::to_circuit::<Z251>(code).unwrap();
+        let (a, b, c) = (
+            parsed.input_wires[0],
+            parsed.input_wires[1],
+            parsed.input_wires[2],
+        );
+        let x = parsed.verification_wires[1];
+        let mut circuit = parsed.circuit;
+
+        circuit.set_value(a, 3.into());
+        circuit.set_value(b, 2.into());
+        circuit.set_value(c, 4.into());
+        assert_eq!(circuit.check_constraints(), Ok(()));
+
+        // Claim the wrong output for the second statement
+        circuit.set_value(x, 35.into());
+        let err = circuit.check_constraints().unwrap_err();
+        assert_eq!(err.label, Some("(= x ...) at gate 2".to_string()));
+        assert!(err.to_string().contains("(= x ...)"));
+    }
+
+    #[test]
+    fn weights_under_constrained_label_test() {
+        let code = "(in a b)
+                    (out x)
+                    (verify x)
+
+                    (program
+                        (= temp
+                            (* a b))
+                        (= x
+                            (* temp y)))";
+
+        assert_eq!(
+            weights::<Z251>(code, &[3.into(), 2.into()]),
+            Err(ParseErr::StructureErr(
+                Some(2),
+                "Under constrained expression in (= x ...)".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn nonzero_weights_test() {
+        let mut circuit = Circuit::<Z251>::new();
+        let x = circuit.new_wire();
+        let nonzero = circuit.new_nonzero(x);
+        let mut instance = CircuitInstance::new(circuit, vec![nonzero], vec![x], |w| {
+            Z251::from(w.inner_id() + 1)
+        });
+
+        // x = 5 has the inverse 201 in Z251
+        let weights = instance.try_weights(vec![5.into()]).unwrap();
+        assert!(weights.contains(&201.into()));
+        assert_eq!(weights[1], Z251::from(1));
+
+        let err = instance.try_weights(vec![0.into()]).unwrap_err();
+        assert_eq!(err.label, Some("nonzero".to_string()));
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn parallel_weights_test() {
+        // 32 independent products, each checked to be nonzero, summed in a
+        // tree so that the levels get narrower
+        let mut instance = {
+            let mut circuit = Circuit::<Z251>::new();
+            let inputs = (0..64).map(|_| circuit.new_wire()).collect::<Vec<_>>();
+            let mut layer = inputs
+                .chunks(2)
+                .map(|pair| {
+                    circuit.new_sub_circuit(vec![(Z251::one(), pair[0])], vec![(Z251::one(), pair[1])])
+                })
+                .collect::<Vec<_>>();
+            let checks = layer.iter().map(|&w| circuit.new_nonzero(w)).collect::<Vec<_>>();
+            while layer.len() > 1 {
+                let one = circuit.one_wire();
+                layer = layer
+                    .chunks(2)
+                    .map(|pair| {
+                        circuit.new_sub_circuit(
+                            vec![(Z251::one(), pair[0]), (Z251::one(), pair[1])],
+                            vec![(Z251::one(), one)],
+                        )
+                    })
+                    .collect();
+            }
+
+            let verified = ::std::iter::once(layer[0]).chain(checks).collect();
+            CircuitInstance::new(circuit, verified, inputs, |w| Z251::from(w.inner_id() + 1))
+        };
+        let values = (1..65).map(Z251::from).collect::<Vec<_>>();
+
+        // Setting the inputs again forgets the values, and `current_weights`
+        // then evaluates each wire in turn
+        let parallel = instance.try_weights(values.clone()).unwrap();
+        instance.set_inputs(values);
+        assert_eq!(parallel, instance.current_weights());
+    }
+
+    #[test]
+    fn weights_keeps_constants_test() {
+        // y = x * k where k is a constant wire set on the circuit
+        let mut circuit = Circuit::<Z251>::new();
+        let (x, k) = (circuit.new_wire(), circuit.new_wire());
+        let y = circuit.new_sub_circuit(vec![(Z251::one(), x)], vec![(Z251::one(), k)]);
+        circuit.set_value(k, 7.into());
+
+        let mut instance = CircuitInstance::new(circuit, vec![y], vec![x], |w| {
+            Z251::from(w.inner_id() + 1)
+        });
+
+        assert_eq!(instance.weights(vec![2.into()])[1], Z251::from(14));
+        assert_eq!(instance.weights(vec![3.into()])[1], Z251::from(21));
+    }
+
+    #[test]
+    fn instance_wire_order_test() {
+        // z = x * y, verified as (z, x) which is not the order the wires
+        // were made in, and nothing uses the unity wire
+        let mut circuit = Circuit::<Z251>::new();
+        let (x, y) = (circuit.new_wire(), circuit.new_wire());
+        let z = circuit.new_sub_circuit(vec![(Z251::one(), x)], vec![(Z251::one(), y)]);
+
+        let mut instance = CircuitInstance::new(circuit, vec![z, x], vec![x, y], |w| {
+            Z251::from(w.inner_id() + 1)
+        });
+
+        // The unity wire, then the verification wires as given, then the rest
+        let weights = instance.weights(vec![3.into(), 5.into()]);
+        assert_eq!(
+            weights,
+            vec![Z251::one(), Z251::from(15), Z251::from(3), Z251::from(5)]
+        );
+
+        // One column per weight, with an empty one for the unused unity wire
+        let rep = DummyRep::from(&instance);
+        assert_eq!(rep.u.len(), weights.len());
+        assert!(rep.u[0].is_empty() && rep.v[0].is_empty() && rep.w[0].is_empty());
+        assert_eq!(rep.w[1].len(), 1);
+        assert_eq!(rep.u[2].len(), 1);
+        assert_eq!(rep.v[3].len(), 1);
+    }
+
+    #[test]
+    fn evaluate_program_non_variable_test() {
+        use self::Expression::*;
+
+        // The parser never makes an 'in' with a literal, but evaluating one
+        // is an error rather than a panic
+        let expressions = vec![
+            In(vec![Literal(Z251::from(3))]),
+            Out(vec![]),
+            Verify(vec![]),
+            Program(vec![]),
+        ];
+
+        assert_eq!(
+            evaluate_program(&expressions, &[], &[Z251::from(3)]),
+            Err(ParseErr::StructureErr(
+                None,
+                "Arguments of 'in' must be variables".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn update_input_test() {
+        let code = "(in a b c)
+                    (out x)
+                    (verify b x)
+
+                    (program
+                        (= temp
+                            (* a b))
+                        (= x
+                            (* 1 (+ (* 4 temp) c 6))))";
+
+        let parsed = ASTParser::to_circuit::<Z251>(code).unwrap();
+        let (a, c) = (parsed.input_wires[0], parsed.input_wires[2]);
+        let mut instance = parsed.into_instance();
+
+        instance.weights(vec![3.into(), 2.into(), 4.into()]);
+
+        // Changing a has to flow through temp to x
+        let incremental = instance.update_input(a, 5.into());
+        let full = instance.weights(vec![5.into(), 2.into(), 4.into()]);
+        assert_eq!(incremental, full);
+
+        // Changing c only touches x
+        let incremental = instance.update_input(c, 9.into());
+        let full = instance.weights(vec![5.into(), 2.into(), 9.into()]);
+        assert_eq!(incremental, full);
+    }
 }
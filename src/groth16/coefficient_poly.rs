@@ -1,5 +1,5 @@
 use super::*;
-use std::ops::Deref;
+use std::ops::{Deref, Index, IndexMut};
 use serde_derive::{Serialize, Deserialize};
 
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
@@ -22,6 +22,57 @@ impl<T> Deref for CoefficientPoly<T> {
 
 impl<T> Polynomial<T> for CoefficientPoly<T> where T: Field {}
 
+impl<T> CoefficientPoly<T>
+where
+    T: Field,
+{
+    /// The coefficient of `x^power`, which is zero past the end of the
+    /// coefficients.
+    pub fn coefficient(&self, power: usize) -> T {
+        self.coeffs.get(power).cloned().unwrap_or_else(T::zero)
+    }
+}
+
+/// The coefficient of `x^power`. Past the end of the coefficients there is
+/// no zero to refer to, so this panics; `coefficient` gives zero instead.
+impl<T> Index<usize> for CoefficientPoly<T> {
+    type Output = T;
+
+    fn index(&self, power: usize) -> &T {
+        assert!(
+            power < self.coeffs.len(),
+            "CoefficientPoly::index: no coefficient of x^{}, use coefficient() to read zero",
+            power
+        );
+        &self.coeffs[power]
+    }
+}
+
+/// The coefficient of `x^power`, padding with zero coefficients first if
+/// `power` is past the end, so that any coefficient can be written.
+impl<T> IndexMut<usize> for CoefficientPoly<T>
+where
+    T: FieldIdentity + Clone,
+{
+    fn index_mut(&mut self, power: usize) -> &mut T {
+        if power >= self.coeffs.len() {
+            self.coeffs.resize(power + 1, T::zero());
+        }
+        &mut self.coeffs[power]
+    }
+}
+
+impl<T> CoefficientPoly<T>
+where
+    T: Field + From<usize>,
+{
+    /// The monic polynomial `(x - r_1)(x - r_2)...(x - r_n)` with the given
+    /// roots. An empty slice gives the constant one.
+    pub fn from_roots(roots: &[T]) -> Self {
+        root_poly(roots.iter().cloned())
+    }
+}
+
 impl<T> Add for CoefficientPoly<T>
 where
     T: Clone + From<usize> + Add<Output = T>,
@@ -219,6 +270,54 @@ mod tests {
         }
     }
 
+    #[test]
+    fn from_roots_test() {
+        let roots = [Z251::from(1), Z251::from(2), Z251::from(3)];
+        let poly = CoefficientPoly::from_roots(&roots);
+
+        for &r in roots.iter() {
+            assert_eq!(poly.evaluate(r), Z251::zero());
+        }
+
+        // (x - 1)(x - 2)(x - 3) = x^3 - 6x^2 + 11x - 6
+        assert_eq!(
+            poly,
+            vec![-Z251::from(6), Z251::from(11), -Z251::from(6), Z251::from(1)].into()
+        );
+
+        assert_eq!(
+            CoefficientPoly::<Z251>::from_roots(&[]),
+            vec![Z251::from(1)].into()
+        );
+    }
+
+    #[test]
+    fn index_coefficients() {
+        // 1 + 2x + 3x^2
+        let mut poly = CoefficientPoly::<Z251>::from(vec![1.into(), 2.into(), 3.into()]);
+        assert_eq!(poly[1], Z251::from(2));
+        assert_eq!(poly.coefficient(2), Z251::from(3));
+        assert_eq!(poly.coefficient(7), Z251::zero());
+        assert_eq!(poly.evaluate(Z251::from(2)), Z251::from(17));
+
+        // 1 + 5x + 3x^2
+        poly[1] = Z251::from(5);
+        assert_eq!(poly.evaluate(Z251::from(2)), Z251::from(23));
+
+        // Writing past the end pads with zeros, 1 + 5x + 3x^2 + x^4
+        poly[4] = Z251::from(1);
+        assert_eq!(poly.degree(), 4);
+        assert_eq!(poly.coefficient(3), Z251::zero());
+        assert_eq!(poly.evaluate(Z251::from(2)), Z251::from(39));
+    }
+
+    #[test]
+    #[should_panic]
+    fn index_past_the_end() {
+        let poly = CoefficientPoly::<Z251>::from(vec![1.into()]);
+        let _ = poly[1];
+    }
+
     #[test]
     fn dummy_add() {
         // Trivial addition
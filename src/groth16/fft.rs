@@ -2,73 +2,188 @@ use crate::groth16::coefficient_poly::CoefficientPoly;
 use std::ops::{Add, Mul, Sub};
 use std::iter::FromIterator;
 
+#[derive(Debug, PartialEq)]
 pub struct PointWise<P> { points: Vec<Points<P>> }
 
+#[derive(Debug, PartialEq)]
 pub struct Points<P> { degree: P, y: P }
 
+/// The ways that two `PointWise` can fail to be combined.
+#[derive(Debug, PartialEq)]
+pub enum PointWiseErr {
+    /// The two sides were not evaluated over the same set of x-coordinates.
+    MismatchedDomain,
+}
+
 impl<P> From<Vec<(P, P)>> for PointWise<P> {
     fn from(object: Vec<(P, P)>) -> Self {
         Self {
-            points: 
+            points:
                 object.into_iter()
                 .map( |(degree,y)| Points::from( (degree, y ) ) )
                 .collect::<Vec<_>>()
-        }   
+        }
     }
 }
 
-impl<P> From<(P, P)> for Points<P> { 
+impl<P> From<(P, P)> for Points<P> {
     fn from((degree, y): (P, P)) -> Self { Self { degree , y } }
 }
 
-impl<P> Add<Self> for PointWise<P> 
+/// Two values at the same x-coordinate are combined by combining the values.
+/// Panics if the x-coordinates differ, since the result would not be a value
+/// of either.
+fn assert_same_degree<P: PartialEq>(a: &Points<P>, b: &Points<P>, op: &str) {
+    assert!(a.degree == b.degree, "Points::{}: the points have different x-coordinates", op);
+}
+
+impl<P> Add<Self> for Points<P>
 where
-    P: Add<P, Output=P>,
+    P: Add<P, Output=P> + PartialEq,
 {
-    type Output = Self;    
+    type Output = Self;
     fn add(self, rhs: Self) -> Self {
-        Self::from(
-            self.points
-                .into_iter()
-                .zip(rhs.points.into_iter())
-                .map(|(a, b)| {
-                    (a.degree, a.y + b.y)
-                })
-                .collect::<Vec<_>>()
-        )
+        assert_same_degree(&self, &rhs, "add");
+        Self { degree: self.degree, y: self.y + rhs.y }
+    }
+}
+
+impl<P> Sub<Self> for Points<P>
+where
+    P: Sub<P, Output=P> + PartialEq,
+{
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        assert_same_degree(&self, &rhs, "sub");
+        Self { degree: self.degree, y: self.y - rhs.y }
+    }
+}
+
+impl<P> Mul<Self> for Points<P>
+where
+    P: Mul<P, Output=P> + PartialEq,
+{
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        assert_same_degree(&self, &rhs, "mul");
+        Self { degree: self.degree, y: self.y * rhs.y }
+    }
+}
+
+impl<P> PointWise<P>
+where
+    P: PartialEq,
+{
+    /// Multiplies the two evaluations point by point. Unlike addition a
+    /// missing point cannot be filled in, so both sides must be evaluated over
+    /// the same x-coordinates, although not necessarily in the same order.
+    pub fn try_mul(self, rhs: Self) -> Result<Self, PointWiseErr>
+    where
+        P: Mul<P, Output = P>,
+    {
+        if self.points.len() != rhs.points.len() {
+            return Err(PointWiseErr::MismatchedDomain);
+        }
+
+        let mut rhs = rhs.points.into_iter().map(Some).collect::<Vec<_>>();
+        let points = self
+            .points
+            .into_iter()
+            .map(|a| match take_at_degree(&mut rhs, &a.degree) {
+                Some(b) => Ok(a * b),
+                None => Err(PointWiseErr::MismatchedDomain),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self { points })
+    }
+}
+
+/// Removes and returns the point with the x-coordinate `degree`, if there is
+/// one that has not already been taken.
+fn take_at_degree<P>(points: &mut [Option<Points<P>>], degree: &P) -> Option<Points<P>>
+where
+    P: PartialEq,
+{
+    points
+        .iter()
+        .position(|p| p.as_ref().map_or(false, |p| p.degree == *degree))
+        .and_then(|i| points[i].take())
+}
+
+/// Points are matched up by their x-coordinate rather than their position.
+/// A point that only one side has is kept as is, the same as adding it to a
+/// zero at that x-coordinate.
+impl<P> Add<Self> for PointWise<P>
+where
+    P: Add<P, Output=P> + PartialEq,
+{
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        let mut rhs = rhs.points.into_iter().map(Some).collect::<Vec<_>>();
+        let mut points = self
+            .points
+            .into_iter()
+            .map(|a| match take_at_degree(&mut rhs, &a.degree) {
+                Some(b) => a + b,
+                None => a,
+            })
+            .collect::<Vec<_>>();
+        points.extend(rhs.into_iter().filter_map(|b| b));
+
+        Self { points }
     }
 }
 
 
 #[cfg(test)]
 mod tests {
-    use crate::groth16::fft::{Points, PointWise};
+    use crate::groth16::fft::{Points, PointWise, PointWiseErr};
 
+    #[test]
     fn pointwise_addition() {
-        let Ax = PointWise::from(
-            vec![
-                Points::from( (0, 1) ),
-                Points::from( (1, 0) ),
-                Points::from( (2, 5) ),
-                Points::from( (3, 22) ),
-            ]
-        );
-        let Bx = PointWise::from(
-            vec![
-                Points::from( (0, 1) ),
-                Points::from( (1, 3) ),
-                Points::from( (2, 13) ),
-                Points::from( (3, 37) ),
-            ]
-        );
-        let Cx = PointWise::from(
-            vec![
-                Points::from( (0, 2) ),
-                Points::from( (1, 3) ),
-                Points::from( (2, 18) ),
-                Points::from( (3, 59) ),
-            ]
-        );
+        let Ax = PointWise::from(vec![(0, 1), (1, 0), (2, 5), (3, 22)]);
+        let Bx = PointWise::from(vec![(0, 1), (1, 3), (2, 13), (3, 37)]);
+        let Cx = PointWise::from(vec![(0, 2), (1, 3), (2, 18), (3, 59)]);
         assert_eq!(Ax + Bx, Cx);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn pointwise_addition_unordered() {
+        let Ax = PointWise::from(vec![(0, 1), (1, 0), (2, 5), (3, 22)]);
+        let Bx = PointWise::from(vec![(3, 37), (1, 3), (0, 1), (2, 13)]);
+        let Cx = PointWise::from(vec![(0, 2), (1, 3), (2, 18), (3, 59)]);
+        assert_eq!(Ax + Bx, Cx);
+
+        // Points missing from one side are carried over from the other
+        let Ax = PointWise::from(vec![(2, 5), (0, 1)]);
+        let Bx = PointWise::from(vec![(1, 3), (2, 13)]);
+        let Cx = PointWise::from(vec![(2, 18), (0, 1), (1, 3)]);
+        assert_eq!(Ax + Bx, Cx);
+    }
+
+    #[test]
+    fn pointwise_multiplication() {
+        let Ax = PointWise::from(vec![(0, 1), (1, 2), (2, 5)]);
+        let Bx = PointWise::from(vec![(2, 3), (0, 7), (1, 4)]);
+        let Cx = PointWise::from(vec![(0, 7), (1, 8), (2, 15)]);
+        assert_eq!(Ax.try_mul(Bx), Ok(Cx));
+
+        let Ax = PointWise::from(vec![(0, 1), (1, 2), (2, 5)]);
+        let Bx = PointWise::from(vec![(0, 7), (1, 4), (3, 3)]);
+        assert_eq!(Ax.try_mul(Bx), Err(PointWiseErr::MismatchedDomain));
+    }
+
+    #[test]
+    fn points_arithmetic() {
+        assert_eq!(Points::from((2, 5)) + Points::from((2, 13)), Points::from((2, 18)));
+        assert_eq!(Points::from((2, 13)) - Points::from((2, 5)), Points::from((2, 8)));
+        assert_eq!(Points::from((2, 3)) * Points::from((2, 4)), Points::from((2, 12)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn points_mismatched_degree() {
+        let _ = Points::from((1, 5)) + Points::from((2, 13));
+    }
+}
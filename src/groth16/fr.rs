@@ -1,7 +1,9 @@
 
 use bn::{Fr, Group, Gt, G1, G2};
+use field::{FieldParseErr, TwoAdicField};
 use encryption::rand::FromEntropy;
 pub use super::*;
+use std::convert::TryFrom;
 use std::str::FromStr;
 use serde_derive::{Serialize, Deserialize};
 
@@ -12,7 +14,7 @@ pub struct FrLocal(Fr);
 pub struct G1Local(G1);
 #[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct G2Local(G2);
-#[derive(PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct GtLocal(Gt);
 
 impl Add for FrLocal {
@@ -68,6 +70,57 @@ impl Field for FrLocal {
     fn mul_inv(self) -> Self {
         FrLocal(self.0.inverse().expect("Tried to get mul inv of zero"))
     }
+    fn to_canonical_u64(self) -> u64 {
+        let n = self.0.into_u256();
+        assert!(
+            (64..256).all(|i| n.get_bit(i) != Some(true)),
+            "FrLocal: element does not fit in a u64"
+        );
+
+        (0..64)
+            .filter(|&i| n.get_bit(i) == Some(true))
+            .fold(0, |acc, i| acc | 1 << i)
+    }
+    fn from_canonical_u64(n: u64) -> Self {
+        FrLocal(Fr::from_str(n.to_string().as_str()).expect("Could not convert string to Fr"))
+    }
+    fn to_bytes(&self) -> Vec<u8> {
+        let n = self.0.into_u256();
+
+        (0..32)
+            .map(|j| {
+                // Byte j holds bits 8 * (31 - j) up to 8 * (32 - j)
+                (0..8)
+                    .filter(|&k| n.get_bit(8 * (31 - j) + k) == Some(true))
+                    .fold(0, |acc, k| acc | 1 << k)
+            }).collect()
+    }
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        // Big-endian byte strings of the same length compare as their
+        // integers
+        if bytes.len() != 32 || bytes >= &FR_MODULUS_BYTES[..] {
+            return None;
+        }
+
+        let base = FrLocal::from(256);
+        Some(
+            bytes
+                .iter()
+                .fold(FrLocal::zero(), |acc, &b| acc * base + FrLocal::from(b as usize)),
+        )
+    }
+}
+
+impl TwoAdicField for FrLocal {
+    // r - 1 = 2^28 * 3^2 * 13 * 29 * ...
+    const TWO_ADICITY: u32 = 28;
+
+    /// 5^((r - 1) / 2^28) where 5 is a generator of the multiplicative group.
+    fn two_adic_root() -> Self {
+        FrLocal::from_str(
+            "19103219067921713944291392827692070036145651957329286315305642004821462161904",
+        ).expect("Could not convert string to Fr")
+    }
 }
 
 impl From<usize> for FrLocal {
@@ -87,6 +140,38 @@ impl FromStr for FrLocal {
     }
 }
 
+/// The characteristic of the field of `FrLocal`, in decimal.
+pub(crate) const FR_MODULUS: &str =
+    "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+
+/// `FR_MODULUS` as 32 big-endian bytes.
+const FR_MODULUS_BYTES: [u8; 32] = [
+    0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29, 0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
+    0x28, 0x33, 0xe8, 0x48, 0x79, 0xb9, 0x70, 0x91, 0x43, 0xe1, 0xf5, 0x93, 0xf0, 0x00, 0x00, 0x01,
+];
+
+impl<'a> TryFrom<&'a str> for FrLocal {
+    type Error = FieldParseErr;
+
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(FieldParseErr::Invalid(s.to_string()));
+        }
+
+        // Decimal strings without leading zeros compare as their integers
+        // once they have the same length
+        let digits = s.trim_start_matches('0');
+        if (digits.len(), digits) >= (FR_MODULUS.len(), FR_MODULUS) {
+            return Err(FieldParseErr::OutOfRange {
+                value: s.to_string(),
+                field: "FrLocal".to_string(),
+            });
+        }
+
+        FrLocal::from_str(s).map_err(|_| FieldParseErr::Invalid(s.to_string()))
+    }
+}
+
 impl Random for FrLocal {
     fn random_elem() -> Self {
         let rng = &mut rand::StdRng::from_entropy();
@@ -96,6 +181,13 @@ impl Random for FrLocal {
         }
         FrLocal(r)
     }
+    fn random_elem_from<R: Rng>(rng: &mut R) -> Self {
+        let mut r = Fr::random(rng);
+        while r == Fr::zero() {
+            r = Fr::random(rng);
+        }
+        FrLocal(r)
+    }
 }
 
 impl EllipticEncryptable for FrLocal {
@@ -112,12 +204,16 @@ impl EllipticEncryptable for FrLocal {
         G2Local(g * self.0)
     }
     fn exp_encrypted_g1(self, g1: Self::G1) -> Self::G1 {
+        #[cfg(feature = "metrics")]
+        super::metrics::count_exponentiations(1);
         G1Local(g1.0 * self.0)
     }
     fn exp_encrypted_g2(self, g2: Self::G2) -> Self::G2 {
         G2Local(g2.0 * self.0)
     }
     fn pairing(g1: Self::G1, g2: Self::G2) -> Self::GT {
+        #[cfg(feature = "metrics")]
+        super::metrics::count_pairings(1);
         GtLocal(bn::pairing(g1.0, g2.0))
     }
 }
@@ -142,33 +238,7 @@ where
     T: RootRepresentation<FrLocal>,
 {
     fn from(root_rep: T) -> Self {
-        let (mut u, mut v, mut w) = (Vec::new(), Vec::new(), Vec::new());
-
-        for points in root_rep.u() {
-            u.push(CoefficientPoly::from((root_rep.roots(), points)));
-        }
-        for points in root_rep.v() {
-            v.push(CoefficientPoly::from((root_rep.roots(), points)));
-        }
-        for points in root_rep.w() {
-            w.push(CoefficientPoly::from((root_rep.roots(), points)));
-        }
-
-        assert_eq!(u.len(), v.len());
-        assert_eq!(u.len(), w.len());
-
-        let t = root_poly(root_rep.roots());
-        let input = root_rep.input();
-        let degree = t.degree();
-
-        QAP {
-            u,
-            v,
-            w,
-            t,
-            input,
-            degree,
-        }
+        super::interpolate_root_rep(root_rep)
     }
 }
 
@@ -233,7 +303,8 @@ impl Add for GtLocal {
 #[cfg(test)]
 mod tests {
     use super::super::circuit::{ASTParser, TryParse};
-    use super::super::tests::constant;
+    use super::super::tests::single_mult_qap;
+    use field::{dft, fft, fft_bluestein, ifft};
     use super::*;
     use std::time::Instant;
 
@@ -245,16 +316,71 @@ mod tests {
         }
     }
 
+    #[test]
+    fn fr_bytes_round_trip() {
+        for &x in [FrLocal::zero(), FrLocal::from(258), -FrLocal::one()].iter() {
+            let bytes = x.to_bytes();
+            assert_eq!(bytes.len(), 32);
+            assert!(FrLocal::from_bytes(&bytes) == Some(x));
+        }
+        assert_eq!(FrLocal::from(258).to_bytes()[30..], [1, 2]);
+
+        // The modulus itself is not canonical
+        assert!(FrLocal::from_bytes(&FR_MODULUS_BYTES) == None);
+        assert!(FrLocal::from_bytes(&[0; 31]) == None);
+    }
+
+    #[test]
+    fn fr_try_from_str() {
+        let modulus = FR_MODULUS;
+        let max = "21888242871839275222246405745257275088548364400416034343698204186575808495616";
+
+        assert!(FrLocal::try_from("42") == Ok(FrLocal::from(42)));
+        assert!(FrLocal::try_from("0042") == Ok(FrLocal::from(42)));
+        assert!(FrLocal::try_from(max) == Ok(-FrLocal::one()));
+        assert_eq!(
+            FrLocal::try_from(modulus).err(),
+            Some(FieldParseErr::OutOfRange {
+                value: modulus.to_string(),
+                field: "FrLocal".to_string()
+            })
+        );
+        assert_eq!(
+            FrLocal::try_from("4 2").err(),
+            Some(FieldParseErr::Invalid("4 2".to_string()))
+        );
+    }
+
+    #[test]
+    fn two_adic_root_order() {
+        // Squaring 27 times gives -1, so the order is exactly 2^28
+        let root = (0..27).fold(FrLocal::two_adic_root(), |r, _| r * r);
+        assert!(root == -FrLocal::one());
+        assert!(root * root == FrLocal::one());
+    }
+
+    #[test]
+    fn fft_matches_dft() {
+        let seq = (1..17).map(FrLocal::from).collect::<Vec<_>>();
+        let root = FrLocal::root_of_unity(4);
+
+        assert!(fft(&seq, root) == dft(&seq, root));
+        assert!(ifft(&fft(&seq, root), root).unwrap() == seq);
+    }
+
+    #[test]
+    fn fft_bluestein_matches_dft() {
+        let seq = (0..15).map(|i| FrLocal::from(i * i + 3)).collect::<Vec<_>>();
+
+        // Any nonzero root works, it does not have to have order 15
+        for &root in [FrLocal::from(7), FrLocal::root_of_unity(4)].iter() {
+            assert!(fft_bluestein(&seq, root) == dft(&seq, root));
+        }
+    }
+
     #[test]
     fn single_mult_honest_bn() {
-        let qap: QAP<CoefficientPoly<FrLocal>> = QAP {
-            u: vec![constant(0), constant(0), constant(1), constant(0)],
-            v: vec![constant(0), constant(0), constant(0), constant(1)],
-            w: vec![constant(0), constant(1), constant(0), constant(0)],
-            t: vec![FrLocal::from(250), FrLocal::from(1)].into(),
-            input: 2,
-            degree: 1,
-        };
+        let qap: QAP<CoefficientPoly<FrLocal>> = single_mult_qap();
         let weights: Vec<FrLocal> = vec![1.into(), 51.into(), 3.into(), 17.into()];
 
         for _ in 0..10 {
@@ -263,13 +389,38 @@ mod tests {
             let proof = prove(&qap, (&sigmag1, &sigmag2), &weights);
 
             assert!(verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
-                (sigmag1, sigmag2),
+                (&sigmag1, &sigmag2),
                 &vec![FrLocal::from(51), FrLocal::from(3)],
-                proof
+                &proof
             ));
         }
     }
 
+    #[test]
+    fn rerandomized_proof_verifies() {
+        let qap: QAP<CoefficientPoly<FrLocal>> = single_mult_qap();
+        let weights: Vec<FrLocal> = vec![1.into(), 51.into(), 3.into(), 17.into()];
+        let inputs = vec![FrLocal::from(51), FrLocal::from(3)];
+
+        let (sigmag1, sigmag2) = setup(&qap);
+        let proof = prove(&qap, (&sigmag1, &sigmag2), &weights);
+        let vk = VerifyingKey::from((&sigmag1, &sigmag2));
+
+        let rerandomized = proof.rerandomize::<FrLocal>(&vk);
+        assert!(rerandomized != proof);
+
+        assert!(verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
+            &inputs,
+            &proof
+        ));
+        assert!(verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
+            &inputs,
+            &rerandomized
+        ));
+    }
+
     #[test]
     fn bn_encrypt_quad_test() {
         let root_rep = ASTParser::try_parse(
@@ -294,9 +445,9 @@ mod tests {
             let proof = prove(&qap, (&sigmag1, &sigmag2), &weights);
 
             assert!(verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
-                (sigmag1, sigmag2),
+                (&sigmag1, &sigmag2),
                 &vec![x, share],
-                proof
+                &proof
             ));
         }
     }
@@ -346,9 +497,9 @@ mod tests {
 
             let now = Instant::now();
             assert!(verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
-                (sigmag1, sigmag2),
+                (&sigmag1, &sigmag2),
                 &vec![x, share],
-                proof
+                &proof
             ));
             verify_time += now.elapsed().subsec_millis();
         }
@@ -403,9 +554,9 @@ mod tests {
 
             let now = Instant::now();
             assert!(verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
-                (sigmag1, sigmag2),
+                (&sigmag1, &sigmag2),
                 &weights[1..3],
-                proof
+                &proof
             ));
             verify_time += now.elapsed().subsec_millis();
         }
@@ -0,0 +1,389 @@
+//! A QAP kept as the values of its polynomials at the roots of unity of a
+//! power of two order, rather than as their coefficients, along with a
+//! `setup` and `prove` that work on it directly.
+//!
+//! `setup_lagrange` evaluates the Lagrange basis at the secret point once,
+//! after which the value of any polynomial of the QAP there is a weighted sum,
+//! so no polynomial is ever evaluated. `prove_lagrange` writes `A` and `B` in
+//! that basis, and finds `h` with `fft` and `ifft` over the roots of unity.
+//! The proofs are ordinary groth16 proofs that `verify` accepts.
+
+use super::coefficient_poly::CoefficientPoly;
+use super::{
+    blind_proof, check_setup, encrypt_crs, map_all, EllipticEncryptable, MaybeSync, Proof,
+    Random, SetupError, SigmaG1, SigmaG2, ToxicWaste, QAP,
+};
+use encryption::rand::{FromEntropy, StdRng};
+use field::{fft, ifft, powers, Field, Polynomial, TwoAdicField};
+use serde_derive::{Deserialize, Serialize};
+use std::iter::Sum;
+use std::ops::{Add, Sub};
+
+/// A QAP in the Lagrange basis over the roots of unity of order `degree`,
+/// which is a power of two, given by `QAP::to_lagrange_basis`. `u[i][j]` is
+/// the value of `u_i` at `w^j` for the root of unity `w` of that order, and
+/// likewise for `v` and `w`. The target polynomial is `x^degree - 1`.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub struct LagrangeQAP<T> {
+    pub u: Vec<Vec<T>>,
+    pub v: Vec<Vec<T>>,
+    pub w: Vec<Vec<T>>,
+    pub t: CoefficientPoly<T>,
+    pub input: usize,
+    pub degree: usize,
+}
+
+impl<T> QAP<CoefficientPoly<T>>
+where
+    T: TwoAdicField + From<usize>,
+{
+    /// The same circuit as a QAP in the Lagrange basis over the roots of
+    /// unity. The gate at the root `j + 1` of this QAP is moved to `w^j`, and
+    /// the number of gates is padded up to a power of two with gates that
+    /// are zero in `u`, `v` and `w`, which every witness satisfies.
+    ///
+    /// Panics unless the roots are `1` up to its degree, as they are for one
+    /// from `ASTParser`, or if the field has no roots of unity of twice the
+    /// padded degree, which `prove_lagrange` needs.
+    pub fn to_lagrange_basis(&self) -> LagrangeQAP<T> {
+        assert!(
+            self.has_counting_roots(),
+            "to_lagrange_basis: the roots of the QAP must be 1 up to its degree"
+        );
+
+        let size = self.degree.next_power_of_two();
+        assert!(
+            (2 * size).trailing_zeros() <= T::TWO_ADICITY,
+            "to_lagrange_basis: the field has no roots of unity of order {}",
+            2 * size
+        );
+
+        let degree = self.degree;
+        let at_roots = |polys: &[CoefficientPoly<T>]| {
+            polys
+                .iter()
+                .map(|p| {
+                    (1..degree + 1)
+                        .map(|j| p.evaluate(T::from(j)))
+                        .chain((degree..size).map(|_| T::zero()))
+                        .collect()
+                }).collect::<Vec<_>>()
+        };
+
+        // x^size - 1
+        let mut t = vec![T::zero(); size + 1];
+        t[0] = -T::one();
+        t[size] = T::one();
+
+        LagrangeQAP {
+            u: at_roots(&self.u),
+            v: at_roots(&self.v),
+            w: at_roots(&self.w),
+            t: CoefficientPoly::from(t),
+            input: self.input,
+            degree: size,
+        }
+    }
+}
+
+impl<T> LagrangeQAP<T>
+where
+    T: TwoAdicField + From<usize>,
+{
+    /// The root of unity of order `degree`.
+    fn root(&self) -> T {
+        T::root_of_unity(self.degree.trailing_zeros())
+    }
+
+    /// The values at the roots of the weighted sum of `polys`.
+    fn weighted_sum(&self, polys: &[Vec<T>], weights: &[T]) -> Vec<T> {
+        polys
+            .iter()
+            .zip(weights.iter())
+            .fold(vec![T::zero(); self.degree], |acc, (p, &a)| {
+                acc.into_iter()
+                    .zip(p.iter())
+                    .map(|(acc, &y)| acc + a * y)
+                    .collect()
+            })
+    }
+
+    /// The coefficients of the polynomial with the values `ys` at the roots.
+    fn interpolate(&self, ys: &[T]) -> Vec<T> {
+        // The degree divides the order of the multiplicative group, so it is
+        // never a multiple of the characteristic
+        ifft(ys, self.root()).expect("LagrangeQAP: the degree is invertible")
+    }
+
+    /// The coefficients of `h = (A·B - C) / t` for `A`, `B` and `C` given by
+    /// their values at the roots. `A·B` has degree below `2 * degree`, so it
+    /// is found from its values at the roots of unity of twice the order.
+    /// Dividing by `t = x^degree - 1` then leaves the upper half of the
+    /// coefficients of `A·B - C`, since the lower half is the remainder
+    /// plus the quotient, and the remainder is zero when the witness is
+    /// valid.
+    fn quotient(&self, a: &[T], b: &[T], c: &[T]) -> Vec<T> {
+        let n = self.degree;
+        let root = T::root_of_unity((2 * n).trailing_zeros());
+        let extend = |ys: &[T]| {
+            let mut coeffs = self.interpolate(ys);
+            coeffs.resize(2 * n, T::zero());
+            fft(&coeffs, root)
+        };
+
+        let (a, b, c) = (extend(a), extend(b), extend(c));
+        let product = a
+            .iter()
+            .zip(b.iter())
+            .zip(c.iter())
+            .map(|((&a, &b), &c)| a * b - c)
+            .collect::<Vec<_>>();
+
+        ifft(&product, root)
+            .expect("LagrangeQAP: twice the degree is invertible")
+            .split_off(n)
+    }
+}
+
+/// The Lagrange basis polynomials over the roots of unity of order `size`,
+/// generated by `root`, evaluated at `x`. With the barycentric form
+///
+/// `L_j(x) = (x^size - 1) / size * w^j / (x - w^j)`
+///
+/// all of the `x - w^j` are inverted together with a single inversion. If `x`
+/// is one of the roots the basis is one there and zero elsewhere.
+fn lagrange_at<T>(x: T, root: T, size: usize) -> Vec<T>
+where
+    T: Field + From<usize>,
+{
+    let roots = powers(root).take(size).collect::<Vec<_>>();
+    if let Some(j) = roots.iter().position(|&w| w == x) {
+        let mut basis = vec![T::zero(); size];
+        basis[j] = T::one();
+        return basis;
+    }
+
+    // Batch inversion: the inverse of the product of all of the differences,
+    // unwound with the running products
+    let diffs = roots.iter().map(|&w| x - w).collect::<Vec<_>>();
+    let prefix = diffs
+        .iter()
+        .scan(T::one(), |acc, &d| {
+            let before = *acc;
+            *acc = *acc * d;
+            Some(before)
+        }).collect::<Vec<_>>();
+    let mut inv = (prefix[size - 1] * diffs[size - 1]).mul_inv();
+    let mut inverses = vec![T::zero(); size];
+    for j in (0..size).rev() {
+        inverses[j] = inv * prefix[j];
+        inv = inv * diffs[j];
+    }
+
+    let scale = (powers(x).nth(size).unwrap() - T::one()) / T::from(size);
+    roots
+        .iter()
+        .zip(inverses.iter())
+        .map(|(&w, &d)| scale * w * d)
+        .collect()
+}
+
+/// The same as `setup` but for a `LagrangeQAP`. The CRS must only be used
+/// with `prove_lagrange`, since the elements that `setup` makes from the
+/// powers of the secret point are made from the Lagrange basis instead. The
+/// verifying key is made the same way, so `verify` is unchanged.
+///
+/// Panics if the QAP is degenerate, see `try_setup`.
+pub fn setup_lagrange<T, U, V>(qap: &LagrangeQAP<T>) -> (SigmaG1<U>, SigmaG2<V>)
+where
+    T: EllipticEncryptable<G1 = U, G2 = V>
+        + Random
+        + TwoAdicField
+        + From<usize>
+        + Copy
+        + MaybeSync,
+    U: MaybeSync,
+    V: MaybeSync,
+{
+    let rng = &mut StdRng::from_entropy();
+    match setup_lagrange_from_rng(qap, rng, cfg!(feature = "parallel")) {
+        Ok(sigma) => sigma,
+        Err(e) => panic!("setup_lagrange: degenerate QAP: {:?}", e),
+    }
+}
+
+fn setup_lagrange_from_rng<T, U, V>(
+    qap: &LagrangeQAP<T>,
+    rng: &mut StdRng,
+    parallel: bool,
+) -> Result<(SigmaG1<U>, SigmaG2<V>), SetupError>
+where
+    T: EllipticEncryptable<G1 = U, G2 = V>
+        + Random
+        + TwoAdicField
+        + From<usize>
+        + Copy
+        + MaybeSync,
+    U: MaybeSync,
+    V: MaybeSync,
+{
+    check_setup::<T>(qap.degree, qap.u.len())?;
+
+    let waste = ToxicWaste::from_rng(rng);
+    let x = waste.x;
+    let xi = powers(x).take(qap.degree).collect::<Vec<_>>();
+    let basis = lagrange_at(x, qap.root(), qap.degree);
+
+    // Each polynomial at x is its values at the roots weighted by the basis
+    let at = |ys: &Vec<T>| {
+        ys.iter()
+            .zip(basis.iter())
+            .fold(T::zero(), |acc, (&y, &l)| acc + y * l)
+    };
+    let uvw = qap
+        .u
+        .iter()
+        .zip(qap.v.iter().zip(qap.w.iter()))
+        .collect::<Vec<_>>();
+    let at_x = map_all(&uvw, parallel, |&(ui, (vi, wi))| (at(ui), at(vi), at(wi)));
+
+    Ok(encrypt_crs(
+        &waste,
+        &basis,
+        &xi,
+        &at_x,
+        qap.t.evaluate(x),
+        qap.input,
+        parallel,
+    ))
+}
+
+/// The same as `prove` but for a `LagrangeQAP` and a CRS from
+/// `setup_lagrange`, with the weights in the same order.
+pub fn prove_lagrange<T, U, V>(
+    qap: &LagrangeQAP<T>,
+    (sigmag1, sigmag2): (&SigmaG1<U>, &SigmaG2<V>),
+    weights: &[T],
+) -> Proof<U, V>
+where
+    T: EllipticEncryptable<G1 = U, G2 = V> + Random + TwoAdicField + From<usize> + Copy,
+    U: Add<Output = U> + Sub<Output = U> + Sum + Copy,
+    V: Add<Output = V> + Sum + Copy,
+{
+    let (r, s) = (T::random_elem(), T::random_elem());
+
+    let u_sum = qap.weighted_sum(&qap.u, weights);
+    let v_sum = qap.weighted_sum(&qap.v, weights);
+    let w_sum = qap.weighted_sum(&qap.w, weights);
+
+    let a_g1 = u_sum
+        .iter()
+        .zip(sigmag1.xi.iter())
+        .map(|(&a, &l)| a.exp_encrypted_g1(l))
+        .sum::<U>();
+    let b_g1 = v_sum
+        .iter()
+        .zip(sigmag1.xi.iter())
+        .map(|(&b, &l)| b.exp_encrypted_g1(l))
+        .sum::<U>();
+    let b_g2 = v_sum
+        .iter()
+        .zip(sigmag2.xi.iter())
+        .map(|(&b, &l)| b.exp_encrypted_g2(l))
+        .sum::<V>();
+
+    let h = qap.quotient(&u_sum, &v_sum, &w_sum);
+
+    blind_proof(
+        qap.input,
+        (sigmag1, sigmag2),
+        weights,
+        (a_g1, b_g1, b_g2),
+        &h,
+        (r, s),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::circuit::{ASTParser, TryParse};
+    use super::super::fr::FrLocal;
+    use super::super::{verify, weights};
+    use super::*;
+    use field::FieldIdentity;
+
+    #[test]
+    fn to_lagrange_basis_test() {
+        let code = &*::std::fs::read_to_string("test_programs/simple.zk").unwrap();
+        let qap: QAP<CoefficientPoly<FrLocal>> = ASTParser::try_parse(code).unwrap().into();
+        let lagrange = qap.to_lagrange_basis();
+
+        assert_eq!(lagrange.u.len(), qap.u.len());
+        assert!(lagrange.degree.is_power_of_two() && lagrange.degree >= qap.degree);
+
+        // Gate j + 1 of the QAP is at w^j, and the padding gates are zero
+        let roots = powers(lagrange.root()).take(lagrange.degree).collect::<Vec<_>>();
+        for (p, ys) in qap.w.iter().zip(lagrange.w.iter()) {
+            let q = CoefficientPoly::from(lagrange.interpolate(ys));
+            for (j, &root) in roots.iter().enumerate() {
+                let expected = if j < qap.degree {
+                    p.evaluate(FrLocal::from(j + 1))
+                } else {
+                    FrLocal::zero()
+                };
+                assert!(q.evaluate(root) == expected);
+            }
+        }
+        assert!(roots.iter().all(|&root| lagrange.t.evaluate(root) == FrLocal::zero()));
+    }
+
+    #[test]
+    fn lagrange_at_test() {
+        let size = 4;
+        let root = FrLocal::root_of_unity(2);
+        let roots = powers(root).take(size).collect::<Vec<_>>();
+
+        // Each basis polynomial is one at its own root and zero at the others
+        for (j, &x) in roots.iter().enumerate() {
+            let basis = lagrange_at(x, root, size);
+            for (k, &l) in basis.iter().enumerate() {
+                let expected = if k == j { FrLocal::one() } else { FrLocal::zero() };
+                assert!(l == expected);
+            }
+        }
+
+        // Away from the roots the barycentric form agrees with interpolating
+        // and evaluating the polynomial
+        let ys = [3, 1, 4, 1].iter().map(|&y| FrLocal::from(y)).collect::<Vec<_>>();
+        let p = CoefficientPoly::from(ifft(&ys, root).unwrap());
+        let x = FrLocal::from(10);
+        let at_x = lagrange_at(x, root, size)
+            .into_iter()
+            .zip(ys.iter())
+            .fold(FrLocal::zero(), |acc, (l, &y)| acc + l * y);
+        assert!(at_x == p.evaluate(x));
+    }
+
+    #[test]
+    fn simple_circuit_lagrange() {
+        // x = 4ab + c + 6
+        let code = &*::std::fs::read_to_string("test_programs/simple.zk").unwrap();
+        let qap: QAP<CoefficientPoly<FrLocal>> = ASTParser::try_parse(code).unwrap().into();
+        let lagrange = qap.to_lagrange_basis();
+        let weights = weights(code, &[3.into(), 2.into(), 4.into()]).unwrap();
+
+        let (sigmag1, sigmag2) = setup_lagrange(&lagrange);
+        let proof = prove_lagrange(&lagrange, (&sigmag1, &sigmag2), &weights);
+
+        assert!(verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
+            &[FrLocal::from(2), FrLocal::from(34)],
+            &proof
+        ));
+        assert!(!verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
+            &[FrLocal::from(2), FrLocal::from(25)],
+            &proof
+        ));
+    }
+}
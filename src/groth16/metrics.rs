@@ -0,0 +1,48 @@
+//! Counters for the expensive group operations, useful for comparing the
+//! cost of different backends or of different ways to verify.
+//!
+//! Every `EllipticEncryptable` backend counts its own calls to `pairing` and
+//! `exp_encrypted_g1`, so setup and proving are counted as well: `reset`
+//! before the part to be measured. The counters are kept per thread and are
+//! only compiled in with the `metrics` feature.
+
+use std::cell::Cell;
+
+/// The number of pairings and exponentiations (in G1) performed since the
+/// counter was last reset.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OpCounter {
+    pub pairings: usize,
+    pub exponentiations: usize,
+}
+
+thread_local! {
+    static COUNTER: Cell<OpCounter> = Cell::new(OpCounter::default());
+}
+
+/// Sets both counters for the current thread back to zero.
+pub fn reset() {
+    COUNTER.with(|c| c.set(OpCounter::default()));
+}
+
+/// Returns the operations counted on the current thread since the last
+/// `reset`.
+pub fn snapshot() -> OpCounter {
+    COUNTER.with(|c| c.get())
+}
+
+pub(crate) fn count_pairings(n: usize) {
+    COUNTER.with(|c| {
+        let mut ops = c.get();
+        ops.pairings += n;
+        c.set(ops);
+    });
+}
+
+pub(crate) fn count_exponentiations(n: usize) {
+    COUNTER.with(|c| {
+        let mut ops = c.get();
+        ops.exponentiations += n;
+        c.set(ops);
+    });
+}
@@ -5,27 +5,97 @@
 //! the [paper](https://eprint.iacr.org/2016/260.pdf), which can be consulted to
 //! help with any confusion.
 
-use self::circuit::RootRepresentation;
+use self::circuit::dummy_rep::DummyRep;
+use self::circuit::{verified_roles, InputRole, ParseErr, RootRepresentation};
 use self::coefficient_poly::{root_poly, CoefficientPoly};
 use super::field::z251::Z251;
+use super::field::zp::{Z4294967291, Z65521};
 use super::field::{polynomial_division, powers, Field, FieldIdentity, Polynomial};
+use encryption::rand::{FromEntropy, Rng, SeedableRng, StdRng};
+use std::collections::HashSet;
+use std::fmt;
+use std::io::{BufReader, Read};
 use std::iter::{once, repeat, Sum};
 use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::str::FromStr;
+use serde::de::DeserializeOwned;
 use serde_derive::{Serialize, Deserialize};
 
 pub mod circuit;
 pub mod coefficient_poly;
 pub mod fr;
 pub mod fft;
+pub mod lagrange;
+pub mod toy_curve;
+#[cfg(feature = "metrics")]
+pub mod metrics;
 
-pub use self::circuit::weights;
+pub use self::circuit::{weights, weights_batch};
+pub use self::circuit::{import_circom_r1cs, CircomImportErr};
 pub use self::fr::FrLocal;
+use self::fr::{G1Local, G2Local};
 
 /// Represents that a type can produce a random element of itself.
 pub trait Random {
     fn random_elem() -> Self;
+    /// The same as `random_elem` but drawing from `rng`, so that a seeded
+    /// `rng` always produces the same elements.
+    ///
+    /// By default this draws as many random bytes as an element takes,
+    /// clears the bits above the top bit of the largest element, and tries
+    /// again until they are the `Field::to_bytes` of a nonzero element.
+    fn random_elem_from<R: Rng>(rng: &mut R) -> Self
+    where
+        Self: Field,
+    {
+        let max = (-Self::one()).to_bytes();
+        let mask = 0xff >> max[0].leading_zeros();
+
+        let mut bytes = vec![0; max.len()];
+        loop {
+            rng.fill(&mut bytes[..]);
+            bytes[0] &= mask;
+            match Self::from_bytes(&bytes) {
+                Some(r) if r != Self::zero() => return r,
+                _ => continue,
+            }
+        }
+    }
+    /// A uniformly random element whose canonical value, as given by
+    /// `Field::to_canonical_u64`, is in `lo..hi`. Unlike `random_elem` this
+    /// may be zero if `lo` is. Panics if the range is empty or does not fit
+    /// below the characteristic of the field.
+    fn random_in_range(lo: usize, hi: usize) -> Self
+    where
+        Self: Field,
+    {
+        assert!(lo < hi, "random_in_range: the range {}..{} is empty", lo, hi);
+        let last = (hi - 1) as u64;
+        assert!(
+            Self::from_canonical_u64(last).to_canonical_u64() == last,
+            "random_in_range: {} is not below the characteristic of the field",
+            last
+        );
+
+        let n = StdRng::from_entropy().gen_range(lo, hi);
+        Self::from_canonical_u64(n as u64)
+    }
 }
 
+/// `Send + Sync` when the `parallel` feature is enabled and nothing otherwise,
+/// so that `setup` has the same bounds with or without rayon.
+#[cfg(feature = "parallel")]
+pub trait MaybeSync: Send + Sync {}
+#[cfg(feature = "parallel")]
+impl<T: Send + Sync> MaybeSync for T {}
+
+/// `Send + Sync` when the `parallel` feature is enabled and nothing otherwise,
+/// so that `setup` has the same bounds with or without rayon.
+#[cfg(not(feature = "parallel"))]
+pub trait MaybeSync {}
+#[cfg(not(feature = "parallel"))]
+impl<T> MaybeSync for T {}
+
 /// Defines a relationship between the three groups in the pairing based
 /// cryptography system, in relation to the `Self` type which is to be
 /// encrypted.
@@ -57,9 +127,21 @@ pub trait Identity {
     fn is_identity(&self) -> bool;
 }
 
+/// Represents a group element that can be written as a fixed number of field
+/// elements, such as when a `Proof` is the input to another circuit.
+pub trait ToFieldElements<T>: Sized {
+    /// How many field elements a single group element is written as.
+    const LEN: usize;
+
+    fn to_field_elements(&self) -> Vec<T>;
+    /// Gives `None` if `elems` does not have exactly `LEN` elements or they
+    /// are not a valid group element.
+    fn from_field_elements(elems: &[T]) -> Option<Self>;
+}
+
 /// The Quadratic Arithmetic Program (QAP) that represents an arithmetic
 /// circuit.
-#[derive(Serialize, Deserialize, PartialEq, Clone)]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
 pub struct QAP<P> {
     pub u: Vec<P>,
     pub v: Vec<P>,
@@ -69,41 +151,379 @@ pub struct QAP<P> {
     pub degree: usize,
 }
 
-impl<R> From<R> for QAP<CoefficientPoly<Z251>>
+impl<P> QAP<P> {
+    /// The indices into the weights of the wires that are public inputs, in
+    /// the order that `verify` expects their values. Index 0 is the unity
+    /// wire which is neither public nor private, since its weight is always
+    /// one.
+    pub fn public_input_indices(&self) -> Vec<usize> {
+        (1..self.input + 1).collect()
+    }
+
+    /// The indices into the weights of every other wire, which only the
+    /// prover knows the values of.
+    pub fn private_input_indices(&self) -> Vec<usize> {
+        (self.input + 1..self.u.len()).collect()
+    }
+
+    /// The weights of `self.disjoint_union(other)` from the weights `a` of
+    /// `self` and `b` of `other`: the unity wire, the public inputs of
+    /// `self` then of `other`, and the private wires of `self` then of
+    /// `other`.
+    pub fn disjoint_union_weights<T>(&self, other: &QAP<P>, a: &[T], b: &[T]) -> Vec<T>
+    where
+        T: Copy,
+    {
+        let (a_public, a_private) = a[1..].split_at(self.input);
+        let (b_public, b_private) = b[1..].split_at(other.input);
+
+        once(a[0])
+            .chain(a_public.iter().cloned())
+            .chain(b_public.iter().cloned())
+            .chain(a_private.iter().cloned())
+            .chain(b_private.iter().cloned())
+            .collect()
+    }
+
+    /// A readable listing of the QAP with one line per constraint. Constraint
+    /// `j` is read off at the root `j` of the target polynomial, giving the
+    /// terms `A * B = C` where each side is a sum of `weight * w_i` over the
+    /// wires `w_i` that take part in it. `w_0` is the unity wire.
+    pub fn pretty<T>(&self) -> String
+    where
+        P: Polynomial<T>,
+        T: Field + fmt::Debug,
+    {
+        let mut out = format!(
+            "QAP with {} wires, {} public inputs and {} constraints\n",
+            self.u.len(),
+            self.input,
+            self.degree
+        );
+
+        let terms = |polys: &[P], root: T| {
+            let terms = polys
+                .iter()
+                .enumerate()
+                .map(|(i, p)| (i, p.evaluate(root)))
+                .filter(|&(_, weight)| weight != T::zero())
+                .map(|(i, weight)| format!("{:?} * w_{}", weight, i))
+                .collect::<Vec<_>>();
+
+            if terms.is_empty() {
+                "0".to_string()
+            } else {
+                terms.join(" + ")
+            }
+        };
+
+        let mut root = T::zero();
+        for j in 1..self.degree + 1 {
+            root = root + T::one();
+            out.push_str(&format!(
+                "constraint {}: ({}) * ({}) = ({})\n",
+                j,
+                terms(&self.u, root),
+                terms(&self.v, root),
+                terms(&self.w, root)
+            ));
+        }
+
+        out
+    }
+
+    /// The QAP as the three matrices `A`, `B` and `C` of its constraints, in
+    /// CSV. Row `j` is read off at the root `j` of the target polynomial, so
+    /// there is one row per constraint, and column `i` is the wire `w_i`,
+    /// with the unity wire first. Each entry is written in decimal from
+    /// `Field::to_bytes`, so entries of any size are written in full, such
+    /// as those of `FrLocal`.
+    pub fn to_csv<T>(&self) -> (String, String, String)
+    where
+        P: Polynomial<T>,
+        T: Field,
+    {
+        let roots = repeat(T::one())
+            .scan(T::zero(), |root, one| {
+                *root = *root + one;
+                Some(*root)
+            })
+            .take(self.degree)
+            .collect::<Vec<_>>();
+
+        let matrix = |polys: &[P]| {
+            roots
+                .iter()
+                .map(|&root| {
+                    let row = polys
+                        .iter()
+                        .map(|p| to_decimal(&p.evaluate(root).to_bytes()))
+                        .collect::<Vec<_>>();
+                    row.join(",") + "\n"
+                })
+                .collect::<String>()
+        };
+
+        (matrix(&self.u), matrix(&self.v), matrix(&self.w))
+    }
+}
+
+/// The decimal digits of the big-endian integer `bytes`.
+fn to_decimal(bytes: &[u8]) -> String {
+    let mut n = bytes.to_vec();
+    let mut digits = Vec::new();
+
+    // Long division by ten until nothing is left
+    while n.iter().any(|&b| b != 0) {
+        let mut rem = 0;
+        for b in n.iter_mut() {
+            let cur = rem * 256 + *b as u32;
+            *b = (cur / 10) as u8;
+            rem = cur % 10;
+        }
+        digits.push(b'0' + rem as u8);
+    }
+    if digits.is_empty() {
+        digits.push(b'0');
+    }
+
+    digits.iter().rev().map(|&d| d as char).collect()
+}
+
+impl<T> QAP<CoefficientPoly<T>>
 where
-    R: RootRepresentation<Z251>,
+    T: Field + From<usize>,
 {
-    fn from(root_rep: R) -> Self {
-        let (mut u, mut v, mut w) = (Vec::new(), Vec::new(), Vec::new());
+    /// A QAP for the two independent statements of `self` and `other`, so
+    /// that a single proof attests to both. No wires are shared except for
+    /// the unity wire; the weights are laid out by `disjoint_union_weights`,
+    /// and so the public inputs are those of `self` followed by those of
+    /// `other`.
+    ///
+    /// The constraints of `other` are moved to the roots after those of
+    /// `self`. Panics unless the roots of each QAP are `1` up to its degree,
+    /// as they are for one from `ASTParser`.
+    pub fn disjoint_union(self, other: Self) -> Self {
+        assert!(
+            self.has_counting_roots() && other.has_counting_roots(),
+            "disjoint_union: the roots of each QAP must be 1 up to its degree"
+        );
+
+        let (a_degree, b_degree) = (self.degree, other.degree);
+        let (a_input, b_input) = (self.input, other.input);
+        let degree = a_degree + b_degree;
+        let roots = (1..degree + 1).map(T::from).collect::<Vec<_>>();
+
+        // The polynomial that agrees with `a` on the roots of `self`, with
+        // `b` on the roots of `other` after they have been moved, and is
+        // zero on the rest
+        let relocate = |a: Option<&CoefficientPoly<T>>, b: Option<&CoefficientPoly<T>>| {
+            let a_points = a.into_iter().flat_map(|p| {
+                (1..a_degree + 1).map(move |k| (T::from(k), p.evaluate(T::from(k))))
+            });
+            let b_points = b.into_iter().flat_map(|p| {
+                (1..b_degree + 1)
+                    .map(move |k| (T::from(a_degree + k), p.evaluate(T::from(k))))
+            });
+            let points = a_points
+                .chain(b_points)
+                .filter(|&(_, y)| y != T::zero())
+                .collect::<Vec<_>>();
+
+            CoefficientPoly::from((roots.iter().cloned(), points.into_iter()))
+        };
+        let union = |a: &[CoefficientPoly<T>], b: &[CoefficientPoly<T>]| {
+            once(relocate(Some(&a[0]), Some(&b[0])))
+                .chain(a[1..a_input + 1].iter().map(|p| relocate(Some(p), None)))
+                .chain(b[1..b_input + 1].iter().map(|p| relocate(None, Some(p))))
+                .chain(a[a_input + 1..].iter().map(|p| relocate(Some(p), None)))
+                .chain(b[b_input + 1..].iter().map(|p| relocate(None, Some(p))))
+                .collect::<Vec<_>>()
+        };
 
-        for points in root_rep.u() {
-            u.push(CoefficientPoly::from((root_rep.roots(), points)));
+        QAP {
+            u: union(&self.u, &other.u),
+            v: union(&self.v, &other.v),
+            w: union(&self.w, &other.w),
+            t: root_poly(roots.iter().cloned()),
+            input: a_input + b_input,
+            degree,
         }
-        for points in root_rep.v() {
-            v.push(CoefficientPoly::from((root_rep.roots(), points)));
+    }
+
+    /// Whether the roots of the target polynomial are `1, 2, ..., degree`.
+    fn has_counting_roots(&self) -> bool {
+        self.t.degree() == self.degree
+            && (1..self.degree + 1).all(|k| self.t.evaluate(T::from(k)) == T::zero())
+    }
+
+    /// The same as `QAP::from` on a `DummyRep`, but first checks that `rep`
+    /// is consistent, since `from` gives a QAP whose polynomials do not match
+    /// the constraints for one that is not. This works for any field, not
+    /// only those that `From` is implemented for.
+    pub fn try_from_dummy(rep: DummyRep<T>) -> Result<Self, QapError> {
+        let wires = rep.u.len();
+        if rep.v.len() != wires || rep.w.len() != wires {
+            return Err(QapError::WireCountMismatch {
+                u: wires,
+                v: rep.v.len(),
+                w: rep.w.len(),
+            });
+        }
+        if rep.input >= wires {
+            return Err(QapError::TooManyInputs {
+                input: rep.input,
+                wires,
+            });
         }
-        for points in root_rep.w() {
-            w.push(CoefficientPoly::from((root_rep.roots(), points)));
+        if rep.roots.is_empty() {
+            return Err(QapError::NoRoots);
         }
 
-        assert_eq!(u.len(), v.len());
-        assert_eq!(u.len(), w.len());
+        // Field elements need not be `Hash`, so they are looked up by their
+        // bytes, which are unique to each element
+        let mut roots = HashSet::with_capacity(rep.roots.len());
+        if let Some(index) = rep.roots.iter().position(|x| !roots.insert(x.to_bytes())) {
+            return Err(QapError::RepeatedRoot { index });
+        }
 
-        let t = root_poly(root_rep.roots());
-        let input = root_rep.input();
-        let degree = t.degree();
+        for column in [&rep.u, &rep.v, &rep.w].iter() {
+            for (wire, points) in column.iter().enumerate() {
+                let mut seen = HashSet::with_capacity(points.len());
+                let bad_point = points.iter().any(|&(x, _)| {
+                    let x = x.to_bytes();
+                    !roots.contains(&x) || !seen.insert(x)
+                });
+                if bad_point {
+                    return Err(QapError::BadPoint { wire });
+                }
+            }
+        }
 
-        QAP {
-            u,
-            v,
-            w,
-            t,
-            input,
-            degree,
+        Ok(interpolate_root_rep(rep))
+    }
+
+    /// Checks that the target polynomial is `(x - 1)(x - 2)...(x - n)` for
+    /// the `n` gates of the QAP, which is the domain that `ASTParser` and
+    /// `RootRepresentation` use. It does not check for `x^n - 1`: a QAP on
+    /// the roots of unity fails this check, as does any other domain. A QAP
+    /// from another tool has to pass it before it can be combined with one
+    /// from this crate or used with `to_lagrange_basis`.
+    pub fn validate_domain(&self) -> Result<(), TargetDomainError> {
+        let target = self.t.degree();
+        if target != self.degree {
+            return Err(TargetDomainError::DegreeMismatch {
+                degree: self.degree,
+                target,
+            });
+        }
+
+        let roots = (1..self.degree + 1).map(T::from).collect::<Vec<_>>();
+        let expected = CoefficientPoly::from_roots(&roots).coefficients();
+        if self.t.coefficients().iter().zip(expected.iter()).all(|(a, b)| a == b) {
+            Ok(())
+        } else {
+            Err(TargetDomainError::WrongTarget)
         }
     }
 }
 
+/// The ways that the target polynomial of a QAP can differ from
+/// `(x - 1)(x - 2)...(x - n)`, as found by `QAP::validate_domain`. This is
+/// about the roots of the target polynomial, not about the evaluation
+/// domains of `field::DomainError`.
+#[derive(Debug, PartialEq)]
+pub enum TargetDomainError {
+    /// The target polynomial has degree `target` rather than the number of
+    /// gates, `degree`.
+    DegreeMismatch { degree: usize, target: usize },
+    /// The target polynomial has the right degree but not the roots `1` up
+    /// to the number of gates, or is not monic.
+    WrongTarget,
+}
+
+/// The ways that a `DummyRep` can fail to describe a QAP, as found by
+/// `QAP::try_from_dummy`.
+#[derive(Debug, PartialEq)]
+pub enum QapError {
+    /// `u`, `v` and `w` do not have the same number of wires.
+    WireCountMismatch { u: usize, v: usize, w: usize },
+    /// There are not enough wires for the unity wire and `input` public
+    /// inputs.
+    TooManyInputs { input: usize, wires: usize },
+    /// There are no roots, so the target polynomial would be constant and
+    /// there would be no constraints.
+    NoRoots,
+    /// The root at `index` is the same as an earlier one, so the target
+    /// polynomial would have a repeated root.
+    RepeatedRoot { index: usize },
+    /// A wire has a point that is not at one of the roots, or more than one
+    /// point at the same root.
+    BadPoint { wire: usize },
+}
+
+impl<R> From<R> for QAP<CoefficientPoly<Z251>>
+where
+    R: RootRepresentation<Z251>,
+{
+    fn from(root_rep: R) -> Self {
+        interpolate_root_rep(root_rep)
+    }
+}
+
+impl<R> From<R> for QAP<CoefficientPoly<Z65521>>
+where
+    R: RootRepresentation<Z65521>,
+{
+    fn from(root_rep: R) -> Self {
+        interpolate_root_rep(root_rep)
+    }
+}
+
+impl<R> From<R> for QAP<CoefficientPoly<Z4294967291>>
+where
+    R: RootRepresentation<Z4294967291>,
+{
+    fn from(root_rep: R) -> Self {
+        interpolate_root_rep(root_rep)
+    }
+}
+
+/// The QAP whose polynomials take the values of `root_rep` at its roots.
+fn interpolate_root_rep<T, R>(root_rep: R) -> QAP<CoefficientPoly<T>>
+where
+    T: Field + From<usize>,
+    R: RootRepresentation<T>,
+{
+    let (mut u, mut v, mut w) = (Vec::new(), Vec::new(), Vec::new());
+
+    for points in root_rep.u() {
+        u.push(CoefficientPoly::from((root_rep.roots(), points)));
+    }
+    for points in root_rep.v() {
+        v.push(CoefficientPoly::from((root_rep.roots(), points)));
+    }
+    for points in root_rep.w() {
+        w.push(CoefficientPoly::from((root_rep.roots(), points)));
+    }
+
+    assert_eq!(u.len(), v.len());
+    assert_eq!(u.len(), w.len());
+
+    let t = root_poly(root_rep.roots());
+    let input = root_rep.input();
+    let degree = t.degree();
+
+    QAP {
+        u,
+        v,
+        w,
+        t,
+        input,
+        degree,
+    }
+}
+
 /// The G1 part of the common reference string (CRS)
 #[derive(Serialize, Deserialize, PartialEq, Clone)]
 pub struct SigmaG1<T> {
@@ -126,203 +546,1175 @@ pub struct SigmaG2<T> {
 }
 
 /// The proof produced by the ZKSNARK algorithm.
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
 pub struct Proof<U, V> {
     a: U,
     b: V,
     c: U,
 }
 
+impl<U, V> Proof<U, V>
+where
+    U: Add<Output = U> + Clone,
+    V: Add<Output = V> + Clone,
+{
+    /// Produces a new proof of the same statement that cannot be linked to
+    /// this one. With fresh random `r1` and `r2` (writing the group operations
+    /// multiplicatively as in groth16) the new proof is
+    ///
+    /// `A' = A^(1/r1)`, `B' = B^r1 * delta^(r1 r2)` and `C' = C * A^r2`
+    ///
+    /// The extra `e(A, delta)^r2` that this adds to `e(A', B')` is matched by
+    /// the extra `A^r2` in `e(C', delta)`, so the new proof still verifies.
+    /// Both are drawn until they are nonzero, since `r1` is inverted.
+    pub fn rerandomize<T>(&self, vk: &VerifyingKey<U, V>) -> Proof<U, V>
+    where
+        T: Field + Random + EllipticEncryptable<G1 = U, G2 = V>,
+    {
+        let nonzero = || loop {
+            let r = T::random_elem();
+            if r != T::zero() {
+                break r;
+            }
+        };
+        let (r1, r2) = (nonzero(), nonzero());
+
+        Proof {
+            a: r1.mul_inv().exp_encrypted_g1(self.a.clone()),
+            b: r1.exp_encrypted_g2(self.b.clone())
+                + (r1 * r2).exp_encrypted_g2(vk.delta.clone()),
+            c: self.c.clone() + r2.exp_encrypted_g1(self.a.clone()),
+        }
+    }
+}
+
+impl<U, V> Proof<U, V> {
+    /// Writes the proof as field elements, in the order `A`, `B`, `C`.
+    pub fn to_field_elements<T>(&self) -> Vec<T>
+    where
+        U: ToFieldElements<T>,
+        V: ToFieldElements<T>,
+    {
+        let mut elems = self.a.to_field_elements();
+        elems.extend(self.b.to_field_elements());
+        elems.extend(self.c.to_field_elements());
+        elems
+    }
+
+    /// The inverse of `to_field_elements`. Gives `None` if `elems` is not the
+    /// right length for a proof or does not hold valid group elements.
+    pub fn from_field_elements<T>(elems: &[T]) -> Option<Proof<U, V>>
+    where
+        U: ToFieldElements<T>,
+        V: ToFieldElements<T>,
+    {
+        if elems.len() != 2 * U::LEN + V::LEN {
+            return None;
+        }
+
+        let (a, rest) = elems.split_at(U::LEN);
+        let (b, c) = rest.split_at(V::LEN);
+
+        Some(Proof {
+            a: U::from_field_elements(a)?,
+            b: V::from_field_elements(b)?,
+            c: U::from_field_elements(c)?,
+        })
+    }
+}
+
+/// The parts of the CRS that are needed to verify a `Proof`.
+#[derive(Serialize, Deserialize, PartialEq, Clone)]
+pub struct VerifyingKey<U, V> {
+    alpha: U,
+    beta: V,
+    gamma: V,
+    delta: V,
+    sum_gamma: Vec<U>,
+}
+
+impl<'a, U, V> From<(&'a SigmaG1<U>, &'a SigmaG2<V>)> for VerifyingKey<U, V>
+where
+    U: Clone,
+    V: Clone,
+{
+    fn from((sigmag1, sigmag2): (&'a SigmaG1<U>, &'a SigmaG2<V>)) -> Self {
+        VerifyingKey {
+            alpha: sigmag1.alpha.clone(),
+            beta: sigmag2.beta.clone(),
+            gamma: sigmag2.gamma.clone(),
+            delta: sigmag2.delta.clone(),
+            sum_gamma: sigmag1.sum_gamma.clone(),
+        }
+    }
+}
+
+impl<U, V> VerifyingKey<U, V>
+where
+    U: Clone,
+    V: Clone,
+{
+    /// Does the work that is shared between every verification with this
+    /// key, which for now is the pairing of alpha and beta. Backends with
+    /// more to precompute (such as Miller loop lines) can extend this.
+    pub fn prepare<T, W>(&self) -> PreparedVerifyingKey<U, V, W>
+    where
+        T: EllipticEncryptable<G1 = U, G2 = V, GT = W>,
+    {
+        PreparedVerifyingKey {
+            alpha_beta: T::pairing(self.alpha.clone(), self.beta.clone()),
+            gamma: self.gamma.clone(),
+            delta: self.delta.clone(),
+            sum_gamma: self.sum_gamma.clone(),
+        }
+    }
+
+    /// The term that the public inputs contribute to verification, the sum
+    /// of the elements of `sum_gamma` weighted by one for the unity wire and
+    /// then by the inputs, for use with `verify_with_combined_input`. Inputs
+    /// past the number the key has are ignored.
+    pub fn combine_inputs<T>(&self, inputs: &[T]) -> U
+    where
+        T: Field + EllipticEncryptable<G1 = U>,
+        U: Sum,
+    {
+        input_term(&self.sum_gamma, numbered_inputs(inputs))
+    }
+}
+
+impl<U, V> VerifyingKey<U, V> {
+    /// The role of each public input of the key, for a key made from the
+    /// .zk program `code`. The first is always `InputRole::ConstantOne`,
+    /// which `verify` supplies itself, and the rest are the `verify`
+    /// variables of the program, so position `k` describes the input at
+    /// index `k - 1` of those passed to `verify`. Gives an error if the
+    /// program does not parse or does not verify as many variables as the key
+    /// has inputs.
+    ///
+    /// ```
+    /// use zksnark::field::z251::Z251;
+    /// use zksnark::groth16::circuit::{ASTParser, InputRole, TryParse};
+    /// use zksnark::groth16::{self, VerifyingKey, QAP};
+    /// use zksnark::groth16::coefficient_poly::CoefficientPoly;
+    ///
+    /// let code = "(in a b) (out x) (verify x) (program (= x (* a b)))";
+    /// let qap: QAP<CoefficientPoly<Z251>> = ASTParser::try_parse(code).unwrap().into();
+    /// let (sigmag1, sigmag2) = groth16::setup(&qap);
+    /// let vk = VerifyingKey::from((&sigmag1, &sigmag2));
+    ///
+    /// assert_eq!(
+    ///     vk.describe_inputs::<Z251>(code).unwrap(),
+    ///     vec![InputRole::ConstantOne, InputRole::DeclaredOutput("x".to_string())]
+    /// );
+    /// ```
+    pub fn describe_inputs<T>(&self, code: &str) -> Result<Vec<InputRole>, ParseErr>
+    where
+        T: FromStr,
+    {
+        let roles = verified_roles::<T>(code)?;
+
+        if roles.len() + 1 != self.sum_gamma.len() {
+            return Err(ParseErr::StructureErr(
+                None,
+                format!(
+                    "The program verifies {} variables but the key has {} public inputs",
+                    roles.len(),
+                    self.sum_gamma.len() - 1
+                ),
+            ));
+        }
+
+        Ok(once(InputRole::ConstantOne).chain(roles).collect())
+    }
+}
+
+/// A `VerifyingKey` that has been prepared so that it can be reused across
+/// many calls to `verify_prepared`.
+#[derive(Clone)]
+pub struct PreparedVerifyingKey<U, V, W> {
+    alpha_beta: W,
+    gamma: V,
+    delta: V,
+    sum_gamma: Vec<U>,
+}
+
+/// A `PreparedVerifyingKey` along with the input term of some public inputs
+/// that are the same for many proofs, such as a fixed root. Only the inputs
+/// that vary are combined by `VerifyContext::verify`, which otherwise checks
+/// a proof in the same way as `verify_prepared`.
+#[derive(Clone)]
+pub struct VerifyContext<U, V, W> {
+    pvk: PreparedVerifyingKey<U, V, W>,
+    // The positions of the pinned inputs in the QAP, which start from 1
+    pinned: Vec<usize>,
+    pinned_term: U,
+}
+
+impl<U, V, W> VerifyContext<U, V, W>
+where
+    U: Sum + Clone,
+    V: Clone,
+    W: Add<Output = W> + PartialEq + Clone,
+{
+    /// Pins the public inputs in `pinned`, given as their index into the
+    /// inputs that `verify` would take and their value. Gives `None` if an
+    /// index is pinned twice, is not an input of the key, or its value is not
+    /// canonical, since no proof could then be verified.
+    pub fn new<T>(pvk: PreparedVerifyingKey<U, V, W>, pinned: &[(usize, T)]) -> Option<Self>
+    where
+        T: Field + Copy + EllipticEncryptable<G1 = U, G2 = V, GT = W>,
+    {
+        let mut positions = pinned.iter().map(|&(i, _)| i + 1).collect::<Vec<_>>();
+        positions.sort();
+        positions.dedup();
+        if positions.len() != pinned.len()
+            || positions.iter().any(|&i| i >= pvk.sum_gamma.len())
+            || !pinned.iter().all(|(_, x)| x.is_canonical())
+        {
+            return None;
+        }
+
+        let pinned_term = input_term(&pvk.sum_gamma, pinned.iter().map(|&(i, x)| (i + 1, x)));
+
+        Some(VerifyContext {
+            pvk,
+            pinned: positions,
+            pinned_term,
+        })
+    }
+
+    /// Verify a proof against the pinned inputs together with `inputs`, which
+    /// are the rest of the public inputs in order with the pinned ones left
+    /// out. This gives the same answer as `verify_prepared` with the pinned
+    /// inputs put back in their places.
+    pub fn verify<T>(&self, inputs: &[T], proof: &Proof<U, V>) -> bool
+    where
+        T: Field + Copy + EllipticEncryptable<G1 = U, G2 = V, GT = W>,
+    {
+        if !inputs.iter().all(|x| x.is_canonical()) {
+            return false;
+        }
+        let pvk = &self.pvk;
+        let varying = (1..)
+            .filter(|i| !self.pinned.contains(i))
+            .zip(inputs.iter())
+            .filter_map(|(i, a)| pvk.sum_gamma.get(i).map(|x| a.exp_encrypted_g1(x.clone())));
+        let sum_term = once(self.pinned_term.clone()).chain(varying).sum::<U>();
+
+        pvk.alpha_beta.clone()
+            + T::pairing(sum_term, pvk.gamma.clone())
+            + T::pairing(proof.c.clone(), pvk.delta.clone())
+            == T::pairing(proof.a.clone(), proof.b.clone())
+    }
+}
+
+/// The ways that a QAP can be too degenerate for `try_setup` to use.
+#[derive(Debug, PartialEq)]
+pub enum SetupError {
+    /// The QAP has no wires or no gates.
+    EmptyQap,
+    /// The field does not have enough nonzero elements to give every gate
+    /// its own root, so the roots of the target polynomial cannot be
+    /// distinct.
+    DegreeTooLarge { degree: usize },
+}
+
 /// Performs the setup for the ZKSNARK given a QAP.
 ///
 /// The tuple returned is split into the G1 and G2 parts, and together these
-/// constitute the CRS.
+/// constitute the CRS. With the `parallel` feature the group elements of the
+/// CRS are computed on the rayon thread pool.
+///
+/// The QAP is not checked, so a degenerate one gives a CRS that no proof
+/// can be made with. Use `try_setup` to have it rejected with a `SetupError`
+/// instead.
 pub fn setup<P, T, U, V>(qap: &QAP<P>) -> (SigmaG1<U>, SigmaG2<V>)
 where
-    P: Add + Polynomial<T>,
-    T: EllipticEncryptable<G1 = U, G2 = V> + Random + Field + Copy + PartialEq,
+    P: Add + Polynomial<T> + MaybeSync,
+    T: EllipticEncryptable<G1 = U, G2 = V> + Random + Field + Copy + PartialEq + MaybeSync,
+    U: MaybeSync,
+    V: MaybeSync,
 {
-    let (alpha, beta, gamma, delta, x) = (
-        T::random_elem(),
-        T::random_elem(),
-        T::random_elem(),
-        T::random_elem(),
-        T::random_elem(),
-    );
+    let rng = &mut StdRng::from_entropy();
+    setup_from_waste(qap, &ToxicWaste::from_rng(rng), cfg!(feature = "parallel"))
+}
+
+/// Performs the setup for the ZKSNARK given a QAP, first checking that the
+/// QAP is one that a CRS can be made for.
+pub fn try_setup<P, T, U, V>(qap: &QAP<P>) -> Result<(SigmaG1<U>, SigmaG2<V>), SetupError>
+where
+    P: Add + Polynomial<T> + MaybeSync,
+    T: EllipticEncryptable<G1 = U, G2 = V> + Random + Field + Copy + PartialEq + MaybeSync,
+    U: MaybeSync,
+    V: MaybeSync,
+{
+    let rng = &mut StdRng::from_entropy();
+    setup_from_rng(qap, rng, cfg!(feature = "parallel"))
+}
+
+/// The same as `setup` but the toxic waste is drawn from an rng seeded with
+/// `seed`, so the same seed always gives the same CRS. This is only useful
+/// for testing, since anyone who knows the seed can forge proofs. As with
+/// `setup` the QAP is not checked.
+pub fn setup_seeded<P, T, U, V>(qap: &QAP<P>, seed: u64) -> (SigmaG1<U>, SigmaG2<V>)
+where
+    P: Add + Polynomial<T> + MaybeSync,
+    T: EllipticEncryptable<G1 = U, G2 = V> + Random + Field + Copy + PartialEq + MaybeSync,
+    U: MaybeSync,
+    V: MaybeSync,
+{
+    let rng = &mut StdRng::seed_from_u64(seed);
+    setup_from_waste(qap, &ToxicWaste::from_rng(rng), cfg!(feature = "parallel"))
+}
+
+/// The CRS for `qap_new`, given the CRS `prev` made for `qap_old`. Every
+/// element of a CRS depends on the target polynomial and so on every
+/// constraint, even when constraints are only appended, and the toxic waste
+/// that would be needed to extend `prev` is gone. So `prev` is only kept when
+/// the QAP is unchanged, and otherwise this falls back to a full `setup`.
+pub fn update_setup<P, T, U, V>(
+    prev: (SigmaG1<U>, SigmaG2<V>),
+    qap_old: &QAP<P>,
+    qap_new: &QAP<P>,
+) -> (SigmaG1<U>, SigmaG2<V>)
+where
+    P: Add + Polynomial<T> + PartialEq + MaybeSync,
+    T: EllipticEncryptable<G1 = U, G2 = V> + Random + Field + Copy + PartialEq + MaybeSync,
+    U: MaybeSync,
+    V: MaybeSync,
+{
+    if qap_old == qap_new {
+        prev
+    } else {
+        setup(qap_new)
+    }
+}
+
+fn setup_from_rng<P, T, U, V>(
+    qap: &QAP<P>,
+    rng: &mut StdRng,
+    parallel: bool,
+) -> Result<(SigmaG1<U>, SigmaG2<V>), SetupError>
+where
+    P: Add + Polynomial<T> + MaybeSync,
+    T: EllipticEncryptable<G1 = U, G2 = V> + Random + Field + Copy + PartialEq + MaybeSync,
+    U: MaybeSync,
+    V: MaybeSync,
+{
+    check_setup::<T>(qap.degree, qap.u.len())?;
+
+    Ok(setup_from_waste(qap, &ToxicWaste::from_rng(rng), parallel))
+}
+
+fn setup_from_waste<P, T, U, V>(
+    qap: &QAP<P>,
+    waste: &ToxicWaste<T>,
+    parallel: bool,
+) -> (SigmaG1<U>, SigmaG2<V>)
+where
+    P: Add + Polynomial<T> + MaybeSync,
+    T: EllipticEncryptable<G1 = U, G2 = V> + Field + Copy + PartialEq + MaybeSync,
+    U: MaybeSync,
+    V: MaybeSync,
+{
+    let x = waste.x;
     let xi = powers(x).take(qap.degree).collect::<Vec<_>>();
-    let sum_gamma = qap
+
+    let uvw = qap
         .u
         .as_slice()
         .iter()
         .zip(qap.v.as_slice().iter().zip(qap.w.as_slice().iter()))
-        .map(|(ui, (vi, wi))| {
-            ((beta * ui.evaluate(x) + alpha * vi.evaluate(x) + wi.evaluate(x)) / gamma).encrypt_g1()
-        }).take(qap.input + 1)
         .collect::<Vec<_>>();
-    let sum_delta = qap
-        .u
-        .as_slice()
+    let at_x = map_all(&uvw, parallel, |&(ui, (vi, wi))| {
+        (ui.evaluate(x), vi.evaluate(x), wi.evaluate(x))
+    });
+
+    encrypt_crs(
+        waste,
+        &xi,
+        &xi,
+        &at_x,
+        qap.t.evaluate(x),
+        qap.input,
+        parallel,
+    )
+}
+
+/// The random scalars that `setup_with_transcript` made a CRS from, so that
+/// an auditor can check the CRS with `setup_from_transcript`. Anyone who
+/// knows these can forge proofs, so this only exists with the
+/// `unsafe-audit` feature and must never be kept for a real CRS.
+#[cfg(feature = "unsafe-audit")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SetupTranscript<T> {
+    pub alpha: T,
+    pub beta: T,
+    pub gamma: T,
+    pub delta: T,
+    pub x: T,
+}
+
+/// The same as `setup` but also gives the random scalars the CRS was made
+/// from.
+#[cfg(feature = "unsafe-audit")]
+pub fn setup_with_transcript<P, T, U, V>(
+    qap: &QAP<P>,
+) -> ((SigmaG1<U>, SigmaG2<V>), SetupTranscript<T>)
+where
+    P: Add + Polynomial<T> + MaybeSync,
+    T: EllipticEncryptable<G1 = U, G2 = V> + Random + Field + Copy + PartialEq + MaybeSync,
+    U: MaybeSync,
+    V: MaybeSync,
+{
+    let waste = ToxicWaste::from_rng(&mut StdRng::from_entropy());
+    let sigma = setup_from_waste(qap, &waste, cfg!(feature = "parallel"));
+    let ToxicWaste {
+        alpha,
+        beta,
+        gamma,
+        delta,
+        x,
+    } = waste;
+
+    (
+        sigma,
+        SetupTranscript {
+            alpha,
+            beta,
+            gamma,
+            delta,
+            x,
+        },
+    )
+}
+
+/// Makes the CRS that `setup` would have made from the random scalars in
+/// `transcript`, for an auditor to compare with the one that was published.
+#[cfg(feature = "unsafe-audit")]
+pub fn setup_from_transcript<P, T, U, V>(
+    qap: &QAP<P>,
+    transcript: &SetupTranscript<T>,
+) -> (SigmaG1<U>, SigmaG2<V>)
+where
+    P: Add + Polynomial<T> + MaybeSync,
+    T: EllipticEncryptable<G1 = U, G2 = V> + Field + Copy + PartialEq + MaybeSync,
+    U: MaybeSync,
+    V: MaybeSync,
+{
+    let waste = ToxicWaste {
+        alpha: transcript.alpha,
+        beta: transcript.beta,
+        gamma: transcript.gamma,
+        delta: transcript.delta,
+        x: transcript.x,
+    };
+
+    setup_from_waste(qap, &waste, cfg!(feature = "parallel"))
+}
+
+/// Checks that a QAP with `degree` gates and `wires` wires is one that a CRS
+/// can be made for.
+fn check_setup<T>(degree: usize, wires: usize) -> Result<(), SetupError>
+where
+    T: Field,
+{
+    if degree == 0 || wires == 0 {
+        return Err(SetupError::EmptyQap);
+    }
+
+    // The gates are given the roots 1, 2, ..., degree which wrap around to
+    // zero once degree reaches the characteristic of the field
+    let mut root = T::zero();
+    for _ in 0..degree {
+        root = root + T::one();
+        if root == T::zero() {
+            return Err(SetupError::DegreeTooLarge { degree });
+        }
+    }
+
+    Ok(())
+}
+
+/// The secrets drawn by `setup`, which must be forgotten once the CRS has
+/// been made.
+struct ToxicWaste<T> {
+    alpha: T,
+    beta: T,
+    gamma: T,
+    delta: T,
+    x: T,
+}
+
+impl<T> ToxicWaste<T>
+where
+    T: Random + Field,
+{
+    fn from_rng<R: Rng>(rng: &mut R) -> Self {
+        ToxicWaste {
+            alpha: T::random_elem_from(rng),
+            beta: T::random_elem_from(rng),
+            gamma: T::random_elem_from(rng),
+            delta: T::random_elem_from(rng),
+            x: T::random_elem_from(rng),
+        }
+    }
+}
+
+/// Encrypts the CRS, where `basis` holds the values at `x` of the basis that
+/// the prover writes `A` and `B` in, `xi` holds the powers of `x`, `at_x`
+/// holds `(u_i(x), v_i(x), w_i(x))` for every wire and `t` is `t(x)`.
+fn encrypt_crs<T, U, V>(
+    waste: &ToxicWaste<T>,
+    basis: &[T],
+    xi: &[T],
+    at_x: &[(T, T, T)],
+    t: T,
+    input: usize,
+    parallel: bool,
+) -> (SigmaG1<U>, SigmaG2<V>)
+where
+    T: EllipticEncryptable<G1 = U, G2 = V> + Field + Copy + MaybeSync,
+    U: MaybeSync,
+    V: MaybeSync,
+{
+    let ToxicWaste {
+        alpha,
+        beta,
+        gamma,
+        delta,
+        ..
+    } = *waste;
+    let (public, private) = at_x.split_at(::std::cmp::min(input + 1, at_x.len()));
+
+    let sum_gamma = map_all(public, parallel, |&(ui, vi, wi)| {
+        ((beta * ui + alpha * vi + wi) / gamma).encrypt_g1()
+    });
+    let sum_delta = map_all(private, parallel, |&(ui, vi, wi)| {
+        ((beta * ui + alpha * vi + wi) / delta).encrypt_g1()
+    });
+    let xi_t = map_all(&xi[..xi.len() - 1], parallel, |&i| {
+        ((i * t) / delta).encrypt_g1()
+    });
+
+    let sigmag1 = SigmaG1 {
+        alpha: alpha.encrypt_g1(),
+        beta: beta.encrypt_g1(),
+        delta: delta.encrypt_g1(),
+        xi: map_all(basis, parallel, |&i| i.encrypt_g1()),
+        sum_delta,
+        sum_gamma,
+        xi_t,
+    };
+    let sigmag2 = SigmaG2 {
+        beta: beta.encrypt_g2(),
+        gamma: gamma.encrypt_g2(),
+        delta: delta.encrypt_g2(),
+        xi: map_all(basis, parallel, |&i| i.encrypt_g2()),
+    };
+
+    (sigmag1, sigmag2)
+}
+
+/// Maps `f` over `items`, on the rayon thread pool if `parallel` is set. The
+/// results are in the same order either way.
+#[cfg(feature = "parallel")]
+fn map_all<A, B, F>(items: &[A], parallel: bool, f: F) -> Vec<B>
+where
+    A: Sync,
+    B: Send,
+    F: Fn(&A) -> B + Sync + Send,
+{
+    use rayon::prelude::*;
+
+    if parallel {
+        items.par_iter().map(f).collect()
+    } else {
+        items.iter().map(f).collect()
+    }
+}
+
+/// Maps `f` over `items`. Without the `parallel` feature this is always
+/// sequential.
+#[cfg(not(feature = "parallel"))]
+fn map_all<A, B, F>(items: &[A], _parallel: bool, f: F) -> Vec<B>
+where
+    F: Fn(&A) -> B,
+{
+    items.iter().map(f).collect()
+}
+
+/// Create a `Proof` from a QAP, CRS and the circuit weights.
+///
+/// For a given proof, the weights are the corresponding wire assignments for
+/// the entire circuit. The order is determined by the .zk program as follows:
+/// * The first element corresponds the the weight for the constant unity input
+///   to the circuit (if present). As such, the weight should always be the
+///   multiplicative element of the field (the `1` element).
+/// * The next elements correspond to the circuit wires that are to be verified,
+///   and are defined by the `verify` keyword in the .zk file. The order that
+///   they appear in the .zk file determines the order in the vector.
+/// * Finally, the remaining weights are the other input/output wires to the
+///   circuit, along with all of the internal wires that are represented by
+///   intermediate variables in the .zk file. The order of these is again the
+///   same as the order in which they first appear in the .zk file.
+pub fn prove<P, T, U, V>(
+    qap: &QAP<P>,
+    (sigmag1, sigmag2): (&SigmaG1<U>, &SigmaG2<V>),
+    weights: &[T],
+) -> Proof<U, V>
+where
+    P: Add
+        + Sub<Output = P>
+        + Mul<T, Output = P>
+        + Mul<Output = P>
+        + Div<Output = P>
+        + Polynomial<T>
+        + Sum
+        + Clone,
+    T: EllipticEncryptable<G1 = U, G2 = V> + Random + Field + Copy + PartialEq,
+    U: Add<Output = U> + Sub<Output = U> + Sum + Copy,
+    V: Add<Output = V> + Sum + Copy,
+{
+    prove_with_stats(qap, (sigmag1, sigmag2), weights).0
+}
+
+/// The size of what `prove_with_stats` worked on, for logging.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProveStats {
+    /// The number of constraints, which is the degree of the QAP.
+    pub constraints: usize,
+    /// The number of weights, including the unity wire.
+    pub witness_len: usize,
+    /// The degree of the quotient `h = (A·B - C) / t`.
+    pub quotient_degree: usize,
+}
+
+/// The same as `prove` but also gives the `ProveStats` of the proof.
+pub fn prove_with_stats<P, T, U, V>(
+    qap: &QAP<P>,
+    (sigmag1, sigmag2): (&SigmaG1<U>, &SigmaG2<V>),
+    weights: &[T],
+) -> (Proof<U, V>, ProveStats)
+where
+    P: Add
+        + Sub<Output = P>
+        + Mul<T, Output = P>
+        + Mul<Output = P>
+        + Div<Output = P>
+        + Polynomial<T>
+        + Sum
+        + Clone,
+    T: EllipticEncryptable<G1 = U, G2 = V> + Random + Field + Copy + PartialEq,
+    U: Add<Output = U> + Sub<Output = U> + Sum + Copy,
+    V: Add<Output = V> + Sum + Copy,
+{
+    let (r, s) = (T::random_elem(), T::random_elem());
+    prove_from_randomness(qap, (sigmag1, sigmag2), weights, (r, s))
+}
+
+/// The same as `prove` but with the blinding factors `r` and `s` given
+/// rather than sampled, so that they can be derived deterministically, say
+/// from a transcript. The same `r` and `s` always give the same proof, which
+/// reveals the witness to anyone who knows them, so they must be kept secret
+/// and never used for more than one proof.
+pub fn prove_with_randomness<P, T, U, V>(
+    qap: &QAP<P>,
+    (sigmag1, sigmag2): (&SigmaG1<U>, &SigmaG2<V>),
+    weights: &[T],
+    r: T,
+    s: T,
+) -> Proof<U, V>
+where
+    P: Add
+        + Sub<Output = P>
+        + Mul<T, Output = P>
+        + Mul<Output = P>
+        + Div<Output = P>
+        + Polynomial<T>
+        + Sum
+        + Clone,
+    T: EllipticEncryptable<G1 = U, G2 = V> + Field + Copy + PartialEq,
+    U: Add<Output = U> + Sub<Output = U> + Sum + Copy,
+    V: Add<Output = V> + Sum + Copy,
+{
+    prove_from_randomness(qap, (sigmag1, sigmag2), weights, (r, s)).0
+}
+
+fn prove_from_randomness<P, T, U, V>(
+    qap: &QAP<P>,
+    (sigmag1, sigmag2): (&SigmaG1<U>, &SigmaG2<V>),
+    weights: &[T],
+    (r, s): (T, T),
+) -> (Proof<U, V>, ProveStats)
+where
+    P: Add
+        + Sub<Output = P>
+        + Mul<T, Output = P>
+        + Mul<Output = P>
+        + Div<Output = P>
+        + Polynomial<T>
+        + Sum
+        + Clone,
+    T: EllipticEncryptable<G1 = U, G2 = V> + Field + Copy + PartialEq,
+    U: Add<Output = U> + Sub<Output = U> + Sum + Copy,
+    V: Add<Output = V> + Sum + Copy,
+{
+    let u_sum = weighted_poly_sum(&qap.u, weights);
+    let v_sum = weighted_poly_sum(&qap.v, weights);
+    let w_sum = weighted_poly_sum(&qap.w, weights);
+
+    let a_g1 = u_sum
+        .coefficients_ref()
         .iter()
-        .zip(qap.v.as_slice().iter().zip(qap.w.as_slice().iter()))
-        .map(|(ui, (vi, wi))| {
-            ((beta * ui.evaluate(x) + alpha * vi.evaluate(x) + wi.evaluate(x)) / delta).encrypt_g1()
-        }).skip(qap.input + 1)
-        .collect::<Vec<_>>();
-    let xi_t = xi
-        .as_slice()
+        .zip(sigmag1.xi.as_slice().iter())
+        .map(|(&a, &x)| a.exp_encrypted_g1(x))
+        .sum::<U>();
+    let b_g1 = v_sum
+        .coefficients_ref()
         .iter()
-        .take(xi.len() - 1)
-        .map(|&i| ((i * qap.t.evaluate(x)) / delta).encrypt_g1())
-        .collect::<Vec<_>>();
+        .zip(sigmag1.xi.as_slice().iter())
+        .map(|(&a, &x)| a.exp_encrypted_g1(x))
+        .sum::<U>();
+    let b_g2 = v_sum
+        .coefficients_ref()
+        .iter()
+        .zip(sigmag2.xi.as_slice().iter())
+        .map(|(&a, &x)| a.exp_encrypted_g2(x))
+        .sum::<V>();
+
+    let h = (u_sum * v_sum - w_sum) / qap.t.clone();
+    let stats = ProveStats {
+        constraints: qap.degree,
+        witness_len: weights.len(),
+        quotient_degree: h.degree(),
+    };
+
+    let proof = blind_proof(
+        qap.input,
+        (sigmag1, sigmag2),
+        weights,
+        (a_g1, b_g1, b_g2),
+        h.coefficients_ref(),
+        (r, s),
+    );
+
+    (proof, stats)
+}
+
+/// The sum of `polys` weighted by `weights`. The terms are added into one
+/// vector of coefficients as they go, rather than each being cloned and
+/// scaled into a polynomial of its own first.
+fn weighted_poly_sum<P, T>(polys: &[P], weights: &[T]) -> P
+where
+    P: Polynomial<T>,
+    T: Field,
+{
+    let len = polys.iter().map(|p| p.len()).max().unwrap_or(0);
+    let mut sum = vec![T::zero(); len];
+    for (p, &a) in polys.iter().zip(weights.iter()) {
+        for (acc, &x) in sum.iter_mut().zip(p.iter()) {
+            *acc = *acc + a * x;
+        }
+    }
+    P::from(sum)
+}
+
+/// The rest of `prove` once `A`, `B` (in both groups) and the coefficients
+/// of `h` are known, which adds the blinding factors `r` and `s`.
+fn blind_proof<T, U, V>(
+    input: usize,
+    (sigmag1, sigmag2): (&SigmaG1<U>, &SigmaG2<V>),
+    weights: &[T],
+    (a_g1, b_g1, b_g2): (U, U, V),
+    h: &[T],
+    (r, s): (T, T),
+) -> Proof<U, V>
+where
+    T: EllipticEncryptable<G1 = U, G2 = V> + Field + Copy,
+    U: Add<Output = U> + Sub<Output = U> + Sum + Copy,
+    V: Add<Output = V> + Copy,
+{
+    let a = a_g1 + sigmag1.alpha + r.exp_encrypted_g1(sigmag1.delta);
+    let b = b_g2 + sigmag2.beta + s.exp_encrypted_g2(sigmag2.delta);
+
+    let c = h
+        .iter()
+        .zip(sigmag1.xi_t.iter())
+        .map(|(&c, &x)| c.exp_encrypted_g1(x))
+        .sum::<U>()
+        + weights
+            .iter()
+            .skip(input + 1)
+            .zip(sigmag1.sum_delta.iter())
+            .map(|(c, &x)| c.exp_encrypted_g1(x))
+            .sum::<U>()
+        + s.exp_encrypted_g1(a)
+        + r.exp_encrypted_g1(sigmag1.beta + b_g1 + s.exp_encrypted_g1(sigmag1.delta))
+        - (r * s).exp_encrypted_g1(sigmag1.delta);
+
+    Proof { a, b, c }
+}
+
+/// Checks that `weights` satisfy the QAP, which is that `A·B - C` is
+/// divisible by the target polynomial `t` where `A`, `B` and `C` are the
+/// weighted sums of `u`, `v` and `w`. This is what `prove` relies on but
+/// needs no CRS, so a witness can be checked without running `setup`.
+///
+/// A witness that passes gives a proof that verifies against its public
+/// inputs.
+pub fn check_witness<P, T>(qap: &QAP<P>, weights: &[T]) -> bool
+where
+    P: Sub<Output = P> + Mul<T, Output = P> + Mul<Output = P> + Polynomial<T> + Sum + Clone,
+    T: Field,
+{
+    // The unity wire always has a weight of one for the verifier
+    if weights.len() != qap.u.len() || weights.first() != Some(&T::one()) {
+        return false;
+    }
+
+    let weighted_sum = |polys: &[P]| {
+        polys
+            .iter()
+            .cloned()
+            .zip(weights.iter())
+            .map(|(p, &a)| p * a)
+            .sum::<P>()
+    };
+    let mut p = weighted_sum(&qap.u) * weighted_sum(&qap.v) - weighted_sum(&qap.w);
+    p.remove_leading_zeros();
+
+    let is_zero = |p: &P| p.coefficients_ref().iter().all(|&c| c == T::zero());
+    if is_zero(&p) {
+        return true;
+    }
+    // `polynomial_division` gives a zero remainder when the dividend has the
+    // larger degree, so that case is ruled out first
+    if p.degree() < qap.t.degree() {
+        return false;
+    }
+
+    is_zero(&polynomial_division(p, qap.t.clone()).1)
+}
+
+/// Verify a given proof against the CRS and verifier inputs.
+///
+/// Everything is taken by reference so that the same CRS and proof can be
+/// checked against several sets of inputs.
+///
+/// Every public input has to be given. A proof cannot be checked against
+/// only some of them with the rest left to the prover: the verifier would
+/// have to accept the prover's value for their part of the input term, and
+/// with that free the proof `(alpha, beta, 0)` passes for any statement.
+/// Inputs that should stay hidden have to be made private wires before
+/// setup, for example with `CircuitInstance::commit_output`.
+///
+/// The proof is rejected if any input is not canonical, see
+/// `Field::is_canonical`, since it would not stand for the element it
+/// appears to be.
+pub fn verify<P, T, U, V, W>(
+    (sigmag1, sigmag2): (&SigmaG1<U>, &SigmaG2<V>),
+    inputs: &[T],
+    proof: &Proof<U, V>,
+) -> bool
+where
+    T: Field + Copy + EllipticEncryptable<G1 = U, G2 = V, GT = W>,
+    U: Sum + Clone,
+    V: Clone,
+    W: Add<Output = W> + PartialEq,
+{
+    if !inputs.iter().all(|x| x.is_canonical()) {
+        return false;
+    }
+    let sum_term = input_term(&sigmag1.sum_gamma, numbered_inputs(inputs));
+
+    T::pairing(sigmag1.alpha.clone(), sigmag2.beta.clone())
+        + T::pairing(sum_term, sigmag2.gamma.clone())
+        + T::pairing(proof.c.clone(), sigmag2.delta.clone())
+        == T::pairing(proof.a.clone(), proof.b.clone())
+}
+
+/// Verify a proof for a circuit whose public inputs were aggregated with
+/// `CircuitInstance::aggregate_inputs`. `inputs` are the values of the
+/// original public inputs, which are hashed with `aggregate_public_inputs`
+/// to give the single input that the proof is checked against. The hash is
+/// the MiMC with exponent 5 used by `CircuitInstance::commit_output`, which
+/// is only a permutation of the field of `FrLocal`.
+pub fn verify_aggregated<P>(
+    sigma: (&SigmaG1<G1Local>, &SigmaG2<G2Local>),
+    inputs: &[FrLocal],
+    proof: &Proof<G1Local, G2Local>,
+) -> bool {
+    let aggregate = circuit::aggregate_public_inputs(inputs);
+    verify::<P, _, _, _, _>(sigma, &[aggregate], proof)
+}
+
+/// Verify a given proof against a prepared verifying key and verifier inputs.
+///
+/// This is equivalent to `verify`, but the pairing of alpha and beta has
+/// already been done by `VerifyingKey::prepare`.
+pub fn verify_prepared<T, U, V, W>(
+    pvk: &PreparedVerifyingKey<U, V, W>,
+    inputs: &[T],
+    proof: &Proof<U, V>,
+) -> bool
+where
+    T: Field + Copy + EllipticEncryptable<G1 = U, G2 = V, GT = W>,
+    U: Sum + Clone,
+    V: Clone,
+    W: Add<Output = W> + PartialEq + Clone,
+{
+    if !inputs.iter().all(|x| x.is_canonical()) {
+        return false;
+    }
+    let sum_term = input_term(&pvk.sum_gamma, numbered_inputs(inputs));
+
+    pvk.alpha_beta.clone()
+        + T::pairing(sum_term, pvk.gamma.clone())
+        + T::pairing(proof.c.clone(), pvk.delta.clone())
+        == T::pairing(proof.a.clone(), proof.b.clone())
+}
+
+/// Verify a proof given the term for the public inputs, as from
+/// `VerifyingKey::combine_inputs`, instead of the inputs themselves, such as
+/// when the inputs are committed to elsewhere and only their combination is
+/// passed on. This skips combining the inputs, but nothing checks that
+/// `combined` comes from inputs of the right length.
+pub fn verify_with_combined_input<T, U, V, W>(
+    vk: &VerifyingKey<U, V>,
+    combined: U,
+    proof: &Proof<U, V>,
+) -> bool
+where
+    T: EllipticEncryptable<G1 = U, G2 = V, GT = W>,
+    U: Clone,
+    V: Clone,
+    W: Add<Output = W> + PartialEq,
+{
+    T::pairing(vk.alpha.clone(), vk.beta.clone())
+        + T::pairing(combined, vk.gamma.clone())
+        + T::pairing(proof.c.clone(), vk.delta.clone())
+        == T::pairing(proof.a.clone(), proof.b.clone())
+}
+
+/// The public inputs paired with their positions in the QAP, which start
+/// from 1 since position 0 is the unity wire.
+fn numbered_inputs<'a, T>(inputs: &'a [T]) -> impl Iterator<Item = (usize, T)> + 'a
+where
+    T: Copy,
+{
+    inputs.iter().enumerate().map(|(i, &x)| (i + 1, x))
+}
+
+/// The linear combination of `sum_gamma` with the unity wire and the given
+/// public inputs. Positions past the end of `sum_gamma` are ignored.
+fn input_term<T, U>(sum_gamma: &[U], inputs: impl Iterator<Item = (usize, T)>) -> U
+where
+    T: Field + EllipticEncryptable<G1 = U>,
+    U: Sum + Clone,
+{
+    once((0, T::one()))
+        .chain(inputs)
+        .filter_map(|(i, a)| sum_gamma.get(i).map(|x| a.exp_encrypted_g1(x.clone())))
+        .sum::<U>()
+}
+
+/// The ways that `merge_public_inputs` can fail.
+#[derive(Debug, PartialEq)]
+pub enum MergeInputsErr {
+    /// A shared pair refers past the end of one of the input vectors.
+    OutOfRange { a: usize, b: usize },
+    /// A shared pair holds different values in the two input vectors.
+    Conflict { a: usize, b: usize },
+}
+
+/// Merges the public inputs of two statements into the inputs of the
+/// statement that composes them. Each pair `(i, j)` in `shared` says that
+/// `a[i]` and `b[j]` are the same public wire, so the merged inputs are all
+/// of `a` followed by the entries of `b` that are not shared, in order.
+pub fn merge_public_inputs<T>(
+    a: &[T],
+    b: &[T],
+    shared: &[(usize, usize)],
+) -> Result<Vec<T>, MergeInputsErr>
+where
+    T: Clone + PartialEq,
+{
+    for &(i, j) in shared {
+        match (a.get(i), b.get(j)) {
+            (Some(x), Some(y)) if x == y => (),
+            (Some(_), Some(_)) => return Err(MergeInputsErr::Conflict { a: i, b: j }),
+            _ => return Err(MergeInputsErr::OutOfRange { a: i, b: j }),
+        }
+    }
+
+    let mut merged = a.to_vec();
+    merged.extend(
+        b.iter()
+            .enumerate()
+            .filter(|&(j, _)| shared.iter().all(|&(_, s)| s != j))
+            .map(|(_, x)| x.clone()),
+    );
+
+    Ok(merged)
+}
+
+/// The version of the format written by `to_bytes`. It is written as the
+/// first byte so that `from_bytes` can tell formats apart.
+pub const SERIALIZATION_VERSION: u8 = 0;
+
+/// The ways that `from_bytes` can fail.
+#[derive(Debug, PartialEq)]
+pub enum FromBytesErr {
+    /// There was not even a version byte.
+    Empty,
+    /// The bytes were written in a version of the format that cannot be read.
+    Unsupported(u8),
+    /// The bytes after the version byte could not be deserialized.
+    Malformed(String),
+}
 
-    let sigmag1 = SigmaG1 {
-        alpha: alpha.encrypt_g1(),
-        beta: beta.encrypt_g1(),
-        delta: delta.encrypt_g1(),
-        xi: xi
-            .as_slice()
-            .iter()
-            .map(|&i| i.encrypt_g1())
-            .collect::<Vec<_>>(),
-        sum_delta,
-        sum_gamma,
-        xi_t,
-    };
-    let sigmag2 = SigmaG2 {
-        beta: beta.encrypt_g2(),
-        gamma: gamma.encrypt_g2(),
-        delta: delta.encrypt_g2(),
-        xi: xi
-            .as_slice()
-            .iter()
-            .map(|&i| i.encrypt_g2())
-            .collect::<Vec<_>>(),
-    };
+impl<U, V> Proof<U, V>
+where
+    U: ::serde::Serialize + DeserializeOwned,
+    V: ::serde::Serialize + DeserializeOwned,
+{
+    /// Serializes the proof, prefixed with `SERIALIZATION_VERSION`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_versioned_bytes(self)
+    }
 
-    (sigmag1, sigmag2)
+    /// Deserializes a proof written by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FromBytesErr> {
+        from_versioned_bytes(bytes)
+    }
 }
 
-/// Create a `Proof` from a QAP, CRS and the circuit weights.
-///
-/// For a given proof, the weights are the corresponding wire assignments for
-/// the entire circuit. The order is determined by the .zk program as follows:
-/// * The first element corresponds the the weight for the constant unity input
-///   to the circuit (if present). As such, the weight should always be the
-///   multiplicative element of the field (the `1` element).
-/// * The next elements correspond to the circuit wires that are to be verified,
-///   and are defined by the `verify` keyword in the .zk file. The order that
-///   they appear in the .zk file determines the order in the vector.
-/// * Finally, the remaining weights are the other input/output wires to the
-///   circuit, along with all of the internal wires that are represented by
-///   intermediate variables in the .zk file. The order of these is again the
-///   same as the order in which they first appear in the .zk file.
-pub fn prove<P, T, U, V>(
-    qap: &QAP<P>,
-    (sigmag1, sigmag2): (&SigmaG1<U>, &SigmaG2<V>),
-    weights: &[T],
-) -> Proof<U, V>
+impl<U, V> VerifyingKey<U, V>
 where
-    P: Add
-        + Sub<Output = P>
-        + Mul<T, Output = P>
-        + Mul<Output = P>
-        + Div<Output = P>
-        + Polynomial<T>
-        + Sum
-        + Clone,
-    T: EllipticEncryptable<G1 = U, G2 = V> + Random + Field + Copy + PartialEq,
-    U: Add<Output = U> + Sub<Output = U> + Sum + Copy,
-    V: Add<Output = V> + Sum + Copy,
+    U: ::serde::Serialize + DeserializeOwned,
+    V: ::serde::Serialize + DeserializeOwned,
 {
-    let (r, s) = (T::random_elem(), T::random_elem());
+    /// Serializes the key, prefixed with `SERIALIZATION_VERSION`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_versioned_bytes(self)
+    }
 
-    let u_sum = qap
-        .u
-        .clone()
-        .into_iter()
-        .zip(weights.iter())
-        .map(|(p, &a)| p * a)
-        .sum::<P>();
-    let v_sum = qap
-        .v
-        .clone()
-        .into_iter()
-        .zip(weights.iter())
-        .map(|(p, &a)| p * a)
-        .sum::<P>();
-    let w_sum = qap
-        .w
-        .clone()
-        .into_iter()
-        .zip(weights.iter())
-        .map(|(p, &a)| p * a)
-        .sum::<P>();
+    /// Deserializes a key written by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FromBytesErr> {
+        from_versioned_bytes(bytes)
+    }
+}
 
-    let a_g1 = u_sum
-        .coefficients()
-        .into_iter()
-        .zip(sigmag1.xi.as_slice().iter())
-        .map(|(a, &x)| a.exp_encrypted_g1(x))
-        .sum::<U>();
-    let b_g1 = v_sum
-        .coefficients()
-        .into_iter()
-        .zip(sigmag1.xi.as_slice().iter())
-        .map(|(a, &x)| a.exp_encrypted_g1(x))
-        .sum::<U>();
-    let b_g2 = v_sum
-        .coefficients()
-        .into_iter()
-        .zip(sigmag2.xi.as_slice().iter())
-        .map(|(a, &x)| a.exp_encrypted_g2(x))
-        .sum::<V>();
+fn to_versioned_bytes<S: ::serde::Serialize>(value: &S) -> Vec<u8> {
+    let mut bytes = vec![SERIALIZATION_VERSION];
+    bytes.extend(::serde_json::to_vec(value).expect("serializing to JSON cannot fail"));
+    bytes
+}
 
-    let a = a_g1 + sigmag1.alpha + r.exp_encrypted_g1(sigmag1.delta);
-    let b = b_g2 + sigmag2.beta + s.exp_encrypted_g2(sigmag2.delta);
+fn from_versioned_bytes<D: DeserializeOwned>(bytes: &[u8]) -> Result<D, FromBytesErr> {
+    match bytes.split_first() {
+        None => Err(FromBytesErr::Empty),
+        Some((&version, rest)) if version == SERIALIZATION_VERSION => {
+            ::serde_json::from_slice(rest).map_err(|e| FromBytesErr::Malformed(e.to_string()))
+        }
+        Some((&version, _)) => Err(FromBytesErr::Unsupported(version)),
+    }
+}
 
-    let h = (u_sum * v_sum - w_sum) / qap.t.clone();
+/// The ways that `verify_bytes` can fail before a proof is checked.
+#[derive(Debug, PartialEq)]
+pub enum VerifyBytesErr {
+    /// The public inputs could not be deserialized.
+    Inputs(String),
+    /// The proof could not be deserialized.
+    Proof(String),
+    /// The number of public inputs does not match the verifying key.
+    InputCount { expected: usize, found: usize },
+    /// The public input at `index` is not a canonical field element, see
+    /// `Field::is_canonical`.
+    NonCanonicalInput { index: usize },
+}
 
-    let c = h
-        .coefficients()
-        .into_iter()
-        .zip(sigmag1.xi_t.clone().into_iter())
-        .map(|(c, x)| c.exp_encrypted_g1(x))
-        .sum::<U>()
-        + weights
-            .iter()
-            .skip(qap.input + 1)
-            .zip(sigmag1.sum_delta.clone().into_iter())
-            .map(|(c, x)| c.exp_encrypted_g1(x))
-            .sum::<U>()
-        + s.exp_encrypted_g1(a)
-        + r.exp_encrypted_g1(sigmag1.beta + b_g1 + s.exp_encrypted_g1(sigmag1.delta))
-        - (r * s).exp_encrypted_g1(sigmag1.delta);
+/// Verify a proof given as bytes against public inputs also given as bytes.
+/// Both are expected to be serialized as JSON, the inputs as a list of field
+/// elements. This is intended as the entry point for callers on the other
+/// side of an FFI or network boundary.
+///
+/// `Ok` means the bytes were well formed and carries whether the proof was
+/// accepted. Inputs that deserialize to a non-canonical field element are
+/// reported with `VerifyBytesErr::NonCanonicalInput`.
+pub fn verify_bytes<T, U, V, W>(
+    vk: &VerifyingKey<U, V>,
+    input_bytes: &[u8],
+    proof_bytes: &[u8],
+) -> Result<bool, VerifyBytesErr>
+where
+    T: Field + Copy + EllipticEncryptable<G1 = U, G2 = V, GT = W> + DeserializeOwned,
+    U: Sum + Clone + DeserializeOwned,
+    V: Clone + DeserializeOwned,
+    W: Add<Output = W> + PartialEq + Clone,
+{
+    let inputs: Vec<T> = ::serde_json::from_slice(input_bytes)
+        .map_err(|e| VerifyBytesErr::Inputs(e.to_string()))?;
+    let proof: Proof<U, V> = ::serde_json::from_slice(proof_bytes)
+        .map_err(|e| VerifyBytesErr::Proof(e.to_string()))?;
 
-    Proof { a, b, c }
+    check_inputs(vk, &inputs)?;
+
+    Ok(verify_prepared(&vk.prepare::<T, W>(), &inputs, &proof))
 }
 
-/// Verify a given proof against the CRS and verifier inputs.
-pub fn verify<P, T, U, V, W>(
-    (sigmag1, sigmag2): (SigmaG1<U>, SigmaG2<V>),
+/// The same as `verify_bytes` but the proof is deserialized as it is read
+/// from `reader`, such as a socket, instead of from a buffer holding all of
+/// it. The inputs are already deserialized. Failing to read from `reader`
+/// is reported the same way as a malformed proof.
+///
+/// Reading stops at the end of the proof, so `reader` does not have to
+/// reach its end, as a socket that is left open does not.
+pub fn verify_reader<T, U, V, W, R>(
+    vk: &VerifyingKey<U, V>,
     inputs: &[T],
-    proof: Proof<U, V>,
-) -> bool
+    reader: R,
+) -> Result<bool, VerifyBytesErr>
 where
     T: Field + Copy + EllipticEncryptable<G1 = U, G2 = V, GT = W>,
-    U: Sum,
-    W: Add<Output = W> + PartialEq,
+    U: Sum + Clone + DeserializeOwned,
+    V: Clone + DeserializeOwned,
+    W: Add<Output = W> + PartialEq + Clone,
+    R: Read,
 {
-    let sum_term = sigmag1
-        .sum_gamma
-        .into_iter()
-        .zip(once(T::one()).chain(inputs.iter().map(|&x| x)))
-        .map(|(x, a)| a.exp_encrypted_g1(x))
-        .sum::<U>();
+    let proof: Proof<U, V> = ::serde_json::Deserializer::from_reader(BufReader::new(reader))
+        .into_iter::<Proof<U, V>>()
+        .next()
+        .ok_or_else(|| VerifyBytesErr::Proof("no proof before the end of the input".to_string()))?
+        .map_err(|e| VerifyBytesErr::Proof(e.to_string()))?;
+
+    check_inputs(vk, inputs)?;
+
+    Ok(verify_prepared(&vk.prepare::<T, W>(), inputs, &proof))
+}
+
+fn check_inputs<T, U, V>(
+    vk: &VerifyingKey<U, V>,
+    inputs: &[T],
+) -> Result<(), VerifyBytesErr>
+where
+    T: Field,
+{
+    // The first element of sum_gamma belongs to the unity wire
+    let expected = vk.sum_gamma.len().saturating_sub(1);
+    if inputs.len() != expected {
+        return Err(VerifyBytesErr::InputCount {
+            expected,
+            found: inputs.len(),
+        });
+    }
 
-    T::pairing(sigmag1.alpha, sigmag2.beta)
-        + T::pairing(sum_term, sigmag2.gamma)
-        + T::pairing(proof.c, sigmag2.delta)
-        == T::pairing(proof.a, proof.b)
+    match inputs.iter().position(|x| !x.is_canonical()) {
+        Some(index) => Err(VerifyBytesErr::NonCanonicalInput { index }),
+        None => Ok(()),
+    }
 }
 
 #[cfg(test)]
@@ -331,6 +1723,33 @@ mod tests {
     use super::super::encryption::Encryptable;
     use super::circuit::{ASTParser, TryParse};
     use super::*;
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    /// Counts the allocations made on each thread, so that a test can check
+    /// how many some code makes while other tests run alongside it.
+    struct CountingAlloc;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAlloc {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+            System.alloc(layout)
+        }
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAlloc = CountingAlloc;
+
+    fn allocations() -> usize {
+        ALLOCATIONS.with(|n| n.get())
+    }
 
     impl Random for Z251 {
         fn random_elem() -> Self {
@@ -340,6 +1759,13 @@ mod tests {
             }
             r
         }
+        fn random_elem_from<R: Rng>(rng: &mut R) -> Self {
+            let mut r = Z251::zero();
+            while r == Z251::zero() {
+                r = Z251::from(rng.gen::<u8>() as usize % 251);
+            }
+            r
+        }
     }
 
     impl EllipticEncryptable for Z251 {
@@ -354,12 +1780,16 @@ mod tests {
             self * 69.into()
         }
         fn exp_encrypted_g1(self, g1: Self::G1) -> Self::G1 {
+            #[cfg(feature = "metrics")]
+            metrics::count_exponentiations(1);
             self * g1
         }
         fn exp_encrypted_g2(self, g2: Self::G2) -> Self::G2 {
             self * g2
         }
         fn pairing(g1: Self::G1, g2: Self::G2) -> Self::GT {
+            #[cfg(feature = "metrics")]
+            metrics::count_pairings(1);
             g1 * g2
         }
     }
@@ -386,16 +1816,25 @@ mod tests {
         vec![c.into()].into()
     }
 
-    #[test]
-    fn single_mult_honest() {
-        let qap: QAP<CoefficientPoly<Z251>> = QAP {
+    /// The QAP of a single multiplication gate `a * b = c`, with the wires
+    /// ordered unity, `c`, `a`, `b` and the first two of `c`, `a` verified.
+    pub fn single_mult_qap<T>() -> QAP<CoefficientPoly<T>>
+    where
+        T: From<usize>,
+    {
+        QAP {
             u: vec![constant(0), constant(0), constant(1), constant(0)],
             v: vec![constant(0), constant(0), constant(0), constant(1)],
             w: vec![constant(0), constant(1), constant(0), constant(0)],
-            t: vec![Z251::from(250), Z251::from(1)].into(),
+            t: vec![T::from(250), T::from(1)].into(),
             input: 2,
             degree: 1,
-        };
+        }
+    }
+
+    #[test]
+    fn single_mult_honest() {
+        let qap: QAP<CoefficientPoly<Z251>> = single_mult_qap();
         let weights: Vec<Z251> = vec![1.into(), 17.into(), 100.into(), 83.into()];
 
         for _ in 0..1000 {
@@ -424,26 +1863,390 @@ mod tests {
             let proof = prove(&qap, (&sigmag1, &sigmag2), &weights);
 
             assert!(verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
-                (sigmag1, sigmag2),
+                (&sigmag1, &sigmag2),
                 &vec![Z251::from(17), Z251::from(100)],
-                proof
+                &proof
             ));
         }
     }
 
+    #[test]
+    fn single_mult_prepared() {
+        let qap: QAP<CoefficientPoly<Z251>> = single_mult_qap();
+        let weights: Vec<Z251> = vec![1.into(), 17.into(), 100.into(), 83.into()];
+
+        let (sigmag1, sigmag2) = setup(&qap);
+        let proof = prove(&qap, (&sigmag1, &sigmag2), &weights);
+        let pvk = VerifyingKey::from((&sigmag1, &sigmag2)).prepare::<Z251, _>();
+
+        for inputs in [[17, 100], [17, 99], [3, 100]].iter() {
+            let inputs = inputs.iter().map(|&x| Z251::from(x)).collect::<Vec<_>>();
+
+            assert_eq!(
+                verify_prepared(&pvk, &inputs, &proof),
+                verify::<CoefficientPoly<Z251>, _, _, _, _>(
+                    (&sigmag1, &sigmag2),
+                    &inputs,
+                    &proof
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn single_mult_verify_context() {
+        let qap: QAP<CoefficientPoly<Z251>> = single_mult_qap();
+
+        // The second input is fixed at 100 for every proof
+        let (sigmag1, sigmag2) = setup(&qap);
+        let pvk = VerifyingKey::from((&sigmag1, &sigmag2)).prepare::<Z251, _>();
+        let context = VerifyContext::new(pvk, &[(1, Z251::from(100))]).unwrap();
+
+        for &(product, x) in [(17, 83), (100, 1), (200, 2)].iter() {
+            let weights: Vec<Z251> = vec![1.into(), product.into(), 100.into(), x.into()];
+            let proof = prove(&qap, (&sigmag1, &sigmag2), &weights);
+
+            for &claimed in [product, product + 1].iter() {
+                assert_eq!(
+                    context.verify(&[Z251::from(claimed)], &proof),
+                    verify::<CoefficientPoly<Z251>, _, _, _, _>(
+                        (&sigmag1, &sigmag2),
+                        &[Z251::from(claimed), Z251::from(100)],
+                        &proof
+                    )
+                );
+            }
+            assert!(context.verify(&[Z251::from(product)], &proof));
+        }
+
+        let pvk = VerifyingKey::from((&sigmag1, &sigmag2)).prepare::<Z251, _>();
+        assert!(VerifyContext::new(pvk, &[(1, Z251::from(1)), (1, Z251::from(2))]).is_none());
+
+        // There are only two inputs
+        let pvk = VerifyingKey::from((&sigmag1, &sigmag2)).prepare::<Z251, _>();
+        assert!(VerifyContext::new(pvk, &[(2, Z251::from(1))]).is_none());
+    }
+
+    #[test]
+    fn single_mult_fixed_randomness() {
+        let qap: QAP<CoefficientPoly<Z251>> = single_mult_qap();
+        let weights: Vec<Z251> = vec![1.into(), 17.into(), 100.into(), 83.into()];
+        let (r, s) = (Z251::from(5), Z251::from(7));
+
+        let (sigmag1, sigmag2) = setup(&qap);
+        let proof = prove_with_randomness(&qap, (&sigmag1, &sigmag2), &weights, r, s);
+
+        assert_eq!(
+            proof,
+            prove_with_randomness(&qap, (&sigmag1, &sigmag2), &weights, r, s)
+        );
+        assert_ne!(
+            proof,
+            prove_with_randomness(&qap, (&sigmag1, &sigmag2), &weights, r + 1.into(), s)
+        );
+        assert!(verify::<CoefficientPoly<Z251>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
+            &vec![Z251::from(17), Z251::from(100)],
+            &proof
+        ));
+    }
+
+    #[test]
+    fn setup_degenerate_qap() {
+        let empty: QAP<CoefficientPoly<Z251>> = QAP {
+            u: vec![],
+            v: vec![],
+            w: vec![],
+            t: vec![Z251::one()].into(),
+            input: 0,
+            degree: 0,
+        };
+        assert!(try_setup(&empty) == Err(SetupError::EmptyQap));
+        // setup does not check the QAP, as before try_setup was added
+        let _: (SigmaG1<Z251>, SigmaG2<Z251>) = setup(&empty);
+
+        // There are only 250 nonzero elements to use as roots in Z251
+        let too_large: QAP<CoefficientPoly<Z251>> = QAP {
+            u: vec![constant(0), constant(1)],
+            v: vec![constant(0), constant(1)],
+            w: vec![constant(0), constant(1)],
+            t: vec![Z251::one(); 252].into(),
+            input: 0,
+            degree: 251,
+        };
+        assert!(try_setup(&too_large) == Err(SetupError::DegreeTooLarge { degree: 251 }));
+    }
+
+    #[test]
+    fn random_elem_from_default() {
+        // Zp uses the default random_elem_from
+        let draw = |seed| {
+            let rng = &mut StdRng::seed_from_u64(seed);
+            (0..100)
+                .map(|_| Z65521::random_elem_from(rng))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(draw(7), draw(7));
+        assert_ne!(draw(7), draw(8));
+        assert!(draw(7).iter().all(|&r| r != Z65521::zero() && r.is_canonical()));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn setup_seeded_parallel_matches_sequential() {
+        let qap: QAP<CoefficientPoly<Z251>> = single_mult_qap();
+
+        for &seed in [0, 1, 42].iter() {
+            let parallel = setup_seeded(&qap, seed);
+            let sequential =
+                setup_from_rng(&qap, &mut StdRng::seed_from_u64(seed), false).unwrap();
+            assert!(parallel == sequential);
+        }
+    }
+
+    #[cfg(feature = "unsafe-audit")]
+    #[test]
+    fn setup_transcript_reproduces_sigmas() {
+        let qap: QAP<CoefficientPoly<Z251>> = single_mult_qap();
+
+        let (sigma, transcript) = setup_with_transcript(&qap);
+        assert!(setup_from_transcript(&qap, &transcript) == sigma);
+
+        // A transcript with any other scalar does not give the same CRS
+        let other = SetupTranscript {
+            alpha: transcript.alpha + Z251::one(),
+            ..transcript
+        };
+        assert!(setup_from_transcript(&qap, &other) != sigma);
+    }
+
+    #[test]
+    fn update_setup_appended_constraint() {
+        let code = &*::std::fs::read_to_string("test_programs/simple.zk").unwrap();
+        let qap_old: QAP<CoefficientPoly<Z251>> = ASTParser::try_parse(code).unwrap().into();
+        let prev = setup(&qap_old);
+
+        // An unchanged QAP keeps its CRS
+        let (sigmag1, sigmag2) = update_setup(prev.clone(), &qap_old, &qap_old);
+        assert!(sigmag1 == prev.0 && sigmag2 == prev.1);
+
+        // simple.zk with y = x * x appended and verified
+        let appended = "(in a b c)
+                        (out x y)
+                        (verify b x y)
+                        (program
+                            (= temp
+                                (* a b))
+                            (= x
+                                (* 1 (+ (* 4 temp) c 6)))
+                            (= y
+                                (* x x)))";
+        let qap_new: QAP<CoefficientPoly<Z251>> =
+            ASTParser::try_parse(appended).unwrap().into();
+        let (sigmag1, sigmag2) = update_setup(prev, &qap_old, &qap_new);
+
+        let weights = weights(appended, &[3.into(), 2.into(), 4.into()]).unwrap();
+        let proof = prove(&qap_new, (&sigmag1, &sigmag2), &weights);
+
+        // x = 34 and y = 34^2 = 152 mod 251
+        assert!(verify::<CoefficientPoly<Z251>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
+            &[Z251::from(2), Z251::from(34), Z251::from(152)],
+            &proof
+        ));
+    }
+
+    #[test]
+    fn single_mult_combined_input() {
+        let qap: QAP<CoefficientPoly<Z251>> = single_mult_qap();
+        let weights: Vec<Z251> = vec![1.into(), 17.into(), 100.into(), 83.into()];
+
+        let (sigmag1, sigmag2) = setup(&qap);
+        let proof = prove(&qap, (&sigmag1, &sigmag2), &weights);
+        let vk = VerifyingKey::from((&sigmag1, &sigmag2));
+
+        for inputs in [[17, 100], [17, 99], [3, 100]].iter() {
+            let inputs = inputs.iter().map(|&x| Z251::from(x)).collect::<Vec<_>>();
+
+            // The combination made by hand from the key
+            let combined = once(Z251::one())
+                .chain(inputs.iter().cloned())
+                .zip(sigmag1.sum_gamma.iter())
+                .map(|(a, &x)| a.exp_encrypted_g1(x))
+                .sum::<Z251>();
+            assert_eq!(combined, vk.combine_inputs(&inputs));
+
+            assert_eq!(
+                verify_with_combined_input::<Z251, _, _, _>(&vk, combined, &proof),
+                verify::<CoefficientPoly<Z251>, _, _, _, _>(
+                    (&sigmag1, &sigmag2),
+                    &inputs,
+                    &proof
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn single_mult_verify_bytes() {
+        let qap: QAP<CoefficientPoly<Z251>> = single_mult_qap();
+        let weights: Vec<Z251> = vec![1.into(), 17.into(), 100.into(), 83.into()];
+
+        let (sigmag1, sigmag2) = setup(&qap);
+        let proof = prove(&qap, (&sigmag1, &sigmag2), &weights);
+        let vk = VerifyingKey::from((&sigmag1, &sigmag2));
+
+        let proof_bytes = ::serde_json::to_vec(&proof).unwrap();
+        let input_bytes = ::serde_json::to_vec(&vec![Z251::from(17), Z251::from(100)]).unwrap();
+
+        assert_eq!(
+            verify_bytes::<Z251, _, _, _>(&vk, &input_bytes, &proof_bytes),
+            Ok(true)
+        );
+
+        let short_inputs = ::serde_json::to_vec(&vec![Z251::from(17)]).unwrap();
+        assert_eq!(
+            verify_bytes::<Z251, _, _, _>(&vk, &short_inputs, &proof_bytes),
+            Err(VerifyBytesErr::InputCount {
+                expected: 2,
+                found: 1
+            })
+        );
+
+        match verify_bytes::<Z251, _, _, _>(&vk, b"[17, ", &proof_bytes) {
+            Err(VerifyBytesErr::Inputs(_)) => (),
+            _ => panic!("Malformed inputs were not rejected"),
+        }
+        match verify_bytes::<Z251, _, _, _>(&vk, &input_bytes, b"{\"a\": 1}") {
+            Err(VerifyBytesErr::Proof(_)) => (),
+            _ => panic!("Malformed proof was not rejected"),
+        }
+    }
+
+    #[test]
+    fn single_mult_non_canonical_input() {
+        let qap: QAP<CoefficientPoly<Z251>> = single_mult_qap();
+        let weights: Vec<Z251> = vec![1.into(), 17.into(), 100.into(), 83.into()];
+
+        let (sigmag1, sigmag2) = setup(&qap);
+        let proof = prove(&qap, (&sigmag1, &sigmag2), &weights);
+        let vk = VerifyingKey::from((&sigmag1, &sigmag2));
+        let proof_bytes = ::serde_json::to_vec(&proof).unwrap();
+
+        // 251 is not below the characteristic, it is a second encoding of 0
+        let input_bytes = br#"[{"inner": 17}, {"inner": 251}]"#;
+        assert_eq!(
+            verify_bytes::<Z251, _, _, _>(&vk, input_bytes, &proof_bytes),
+            Err(VerifyBytesErr::NonCanonicalInput { index: 1 })
+        );
+
+        let inputs: Vec<Z251> = ::serde_json::from_slice(input_bytes).unwrap();
+        assert!(!inputs[1].is_canonical());
+        assert!(!verify::<CoefficientPoly<Z251>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
+            &inputs,
+            &proof
+        ));
+    }
+
+    #[test]
+    fn single_mult_verify_reader() {
+        use std::io::Cursor;
+
+        let qap: QAP<CoefficientPoly<Z251>> = single_mult_qap();
+        let weights: Vec<Z251> = vec![1.into(), 17.into(), 100.into(), 83.into()];
+
+        let (sigmag1, sigmag2) = setup(&qap);
+        let proof = prove(&qap, (&sigmag1, &sigmag2), &weights);
+        let vk = VerifyingKey::from((&sigmag1, &sigmag2));
+
+        let proof_bytes = ::serde_json::to_vec(&proof).unwrap();
+        let inputs = vec![Z251::from(17), Z251::from(100)];
+        let input_bytes = ::serde_json::to_vec(&inputs).unwrap();
+
+        for (inputs, input_bytes) in vec![
+            (inputs.clone(), input_bytes),
+            (vec![Z251::from(17), Z251::from(101)], b"[17, 101]".to_vec()),
+            (vec![Z251::from(17)], b"[17]".to_vec()),
+        ] {
+            assert_eq!(
+                verify_reader::<Z251, _, _, _, _>(&vk, &inputs, Cursor::new(&proof_bytes)),
+                verify_bytes::<Z251, _, _, _>(&vk, &input_bytes, &proof_bytes)
+            );
+        }
+        assert_eq!(
+            verify_reader::<Z251, _, _, _, _>(&vk, &inputs, Cursor::new(&proof_bytes)),
+            Ok(true)
+        );
+
+        match verify_reader::<Z251, _, _, _, _>(&vk, &inputs, Cursor::new(b"{\"a\": 1}")) {
+            Err(VerifyBytesErr::Proof(_)) => (),
+            _ => panic!("Malformed proof was not rejected"),
+        }
+
+        // A reader that is still open after the proof, such as a socket, has
+        // no end to read up to
+        struct Open<'a>(Cursor<&'a [u8]>);
+        impl<'a> Read for Open<'a> {
+            fn read(&mut self, buf: &mut [u8]) -> ::std::io::Result<usize> {
+                match self.0.read(buf)? {
+                    0 => panic!("read past the end of the proof"),
+                    n => Ok(n),
+                }
+            }
+        }
+        assert_eq!(
+            verify_reader::<Z251, _, _, _, _>(&vk, &inputs, Open(Cursor::new(&proof_bytes[..]))),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    fn single_mult_versioned_bytes() {
+        let qap: QAP<CoefficientPoly<Z251>> = single_mult_qap();
+        let weights: Vec<Z251> = vec![1.into(), 17.into(), 100.into(), 83.into()];
+
+        let (sigmag1, sigmag2) = setup(&qap);
+        let proof = prove(&qap, (&sigmag1, &sigmag2), &weights);
+        let vk = VerifyingKey::from((&sigmag1, &sigmag2));
+
+        let proof_bytes = proof.to_bytes();
+        let vk_bytes = vk.to_bytes();
+        assert_eq!(proof_bytes[0], SERIALIZATION_VERSION);
+        assert!(Proof::from_bytes(&proof_bytes) == Ok(proof));
+        assert!(VerifyingKey::from_bytes(&vk_bytes) == Ok(vk));
+
+        // A proof from a newer format is not read as if it were version 0
+        let mut newer = proof_bytes.clone();
+        newer[0] = 1;
+        assert!(Proof::<Z251, Z251>::from_bytes(&newer) == Err(FromBytesErr::Unsupported(1)));
+
+        assert!(Proof::<Z251, Z251>::from_bytes(&[]) == Err(FromBytesErr::Empty));
+    }
+
+    #[test]
+    fn single_mult_proof_field_elements() {
+        let qap: QAP<CoefficientPoly<Z251>> = single_mult_qap();
+        let weights: Vec<Z251> = vec![1.into(), 17.into(), 100.into(), 83.into()];
+
+        let (sigmag1, sigmag2) = setup(&qap);
+        let proof = prove(&qap, (&sigmag1, &sigmag2), &weights);
+
+        // One field element for each of A, B and C
+        let elems: Vec<Z251> = proof.to_field_elements();
+        assert_eq!(elems.len(), 3);
+        assert!(Proof::from_field_elements(&elems) == Some(proof));
+
+        assert!(Proof::<Z251, Z251>::from_field_elements(&elems[..2]).is_none());
+    }
+
     #[test]
     fn single_mult_random_proof() {
         let mut count = 0;
         let total = 10000;
 
-        let qap: QAP<CoefficientPoly<Z251>> = QAP {
-            u: vec![constant(0), constant(0), constant(1), constant(0)],
-            v: vec![constant(0), constant(0), constant(0), constant(1)],
-            w: vec![constant(0), constant(1), constant(0), constant(0)],
-            t: vec![Z251::from(250), Z251::from(1)].into(),
-            input: 2,
-            degree: 1,
-        };
+        let qap: QAP<CoefficientPoly<Z251>> = single_mult_qap();
 
         for _ in 0..total {
             let (sigmag1, sigmag2) = setup(&qap);
@@ -455,9 +2258,9 @@ mod tests {
             };
 
             if verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
-                (sigmag1, sigmag2),
+                (&sigmag1, &sigmag2),
                 &vec![Z251::from(17), Z251::from(100)],
-                proof,
+                &proof,
             ) {
                 count += 1;
             }
@@ -540,9 +2343,9 @@ mod tests {
             let proof = prove(&qap, (&sigmag1, &sigmag2), &weights);
 
             assert!(verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
-                (sigmag1, sigmag2),
+                (&sigmag1, &sigmag2),
                 &vec![x, share],
-                proof
+                &proof
             ));
         }
     }
@@ -618,9 +2421,9 @@ mod tests {
             };
 
             if verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
-                (sigmag1, sigmag2),
+                (&sigmag1, &sigmag2),
                 &vec![x, share],
-                proof,
+                &proof,
             ) {
                 count += 1;
             }
@@ -691,9 +2494,9 @@ mod tests {
             let proof = prove(&qap, (&sigmag1, &sigmag2), &weights);
 
             assert!(verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
-                (sigmag1, sigmag2),
+                (&sigmag1, &sigmag2),
                 &vec![x, share],
-                proof
+                &proof
             ));
         }
     }
@@ -718,9 +2521,9 @@ mod tests {
             let proof = prove(&qap, (&sigmag1, &sigmag2), &weights);
 
             assert!(verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
-                (sigmag1, sigmag2),
+                (&sigmag1, &sigmag2),
                 &vec![x, share],
-                proof
+                &proof
             ));
         }
 
@@ -754,13 +2557,125 @@ mod tests {
             let proof: Proof<Z251, Z251> = prove(&qap, (&sigmag1, &sigmag2), &weights);
 
             assert!(verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
-                (sigmag1, sigmag2),
+                (&sigmag1, &sigmag2),
                 &vec![x, share],
-                proof
+                &proof
             ));
         }
     }
 
+    #[test]
+    fn qap_to_csv() {
+        let code = &*::std::fs::read_to_string("test_programs/simple.zk").unwrap();
+        let qap: QAP<CoefficientPoly<Z251>> = ASTParser::try_parse(code).unwrap().into();
+        let (a, b, c) = qap.to_csv();
+
+        for matrix in &[&a, &b, &c] {
+            assert_eq!(matrix.lines().count(), qap.degree);
+            assert!(matrix
+                .lines()
+                .all(|row| row.split(',').count() == qap.u.len()));
+        }
+
+        // The entries are the polynomials at the roots
+        for (j, row) in c.lines().enumerate() {
+            for (i, entry) in row.split(',').enumerate() {
+                let expected = qap.w[i].evaluate(Z251::from(j + 1));
+                assert_eq!(Z251::from(entry.parse::<usize>().unwrap()), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn qap_to_csv_frlocal() {
+        let code = &*::std::fs::read_to_string("test_programs/simple.zk").unwrap();
+        let qap: QAP<CoefficientPoly<FrLocal>> = ASTParser::try_parse(code).unwrap().into();
+        let (a, b, c) = qap.to_csv();
+
+        for (matrix, polys) in [(&a, &qap.u), (&b, &qap.v), (&c, &qap.w)].iter() {
+            for (j, row) in matrix.lines().enumerate() {
+                for (i, entry) in row.split(',').enumerate() {
+                    let expected = polys[i].evaluate(FrLocal::from(j + 1));
+                    assert!(entry.parse::<FrLocal>().ok() == Some(expected));
+                }
+            }
+        }
+
+        // -1 is r - 1, which does not fit in a u64
+        assert_eq!(
+            to_decimal(&(-FrLocal::one()).to_bytes()),
+            "21888242871839275222246405745257275088548364400416034343698204186575808495616"
+        );
+        assert_eq!(to_decimal(&FrLocal::zero().to_bytes()), "0");
+    }
+
+    #[test]
+    fn qap_validate_domain() {
+        let code = &*::std::fs::read_to_string("test_programs/simple.zk").unwrap();
+        let qap: QAP<CoefficientPoly<Z251>> = ASTParser::try_parse(code).unwrap().into();
+        assert_eq!(qap.validate_domain(), Ok(()));
+
+        // x^2 - 1 is the target over the square roots of unity instead
+        let mut unity = qap.clone();
+        unity.t = vec![Z251::from(250), Z251::from(0), Z251::from(1)].into();
+        assert_eq!(unity.validate_domain(), Err(TargetDomainError::WrongTarget));
+
+        let mut short = qap.clone();
+        short.t = vec![Z251::from(250), Z251::from(1)].into();
+        assert_eq!(
+            short.validate_domain(),
+            Err(TargetDomainError::DegreeMismatch {
+                degree: 2,
+                target: 1
+            })
+        );
+    }
+
+    #[test]
+    fn qap_try_from_dummy() {
+        let parse = || -> DummyRep<Z251> {
+            ASTParser::try_parse(
+                &*::std::fs::read_to_string("test_programs/lispesque_quad.zk").unwrap(),
+            ).unwrap()
+        };
+
+        let qap: QAP<CoefficientPoly<Z251>> = parse().into();
+        assert_eq!(QAP::try_from_dummy(parse()), Ok(qap));
+
+        let mut rep = parse();
+        rep.w.pop();
+        let wires = rep.u.len();
+        assert_eq!(
+            QAP::try_from_dummy(rep),
+            Err(QapError::WireCountMismatch {
+                u: wires,
+                v: wires,
+                w: wires - 1
+            })
+        );
+
+        let mut rep = parse();
+        rep.input = rep.u.len();
+        assert!(match QAP::try_from_dummy(rep) {
+            Err(QapError::TooManyInputs { .. }) => true,
+            _ => false,
+        });
+
+        let mut rep = parse();
+        rep.roots.clear();
+        assert_eq!(QAP::try_from_dummy(rep), Err(QapError::NoRoots));
+
+        let mut rep = parse();
+        let first = rep.roots[0];
+        rep.roots.push(first);
+        let index = rep.roots.len() - 1;
+        assert_eq!(QAP::try_from_dummy(rep), Err(QapError::RepeatedRoot { index }));
+
+        let mut rep = parse();
+        rep.u[1].push((Z251::from(200), Z251::one()));
+        assert_eq!(QAP::try_from_dummy(rep), Err(QapError::BadPoint { wire: 1 }));
+    }
+
     #[test]
     fn qap_from_ast() {
         // Quadratic polynomial share
@@ -786,9 +2701,9 @@ mod tests {
             let proof = prove(&qap, (&sigmag1, &sigmag2), &weights);
 
             assert!(verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
-                (sigmag1, sigmag2),
+                (&sigmag1, &sigmag2),
                 &vec![x, share],
-                proof
+                &proof
             ));
         }
 
@@ -827,10 +2742,33 @@ mod tests {
             let proof = prove(&qap, (&sigmag1, &sigmag2), &weights);
 
             assert!(verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
-                (sigmag1, sigmag2),
+                (&sigmag1, &sigmag2),
                 &vec![x, share],
-                proof
+                &proof
             ));
         }
     }
+
+    #[test]
+    fn weighted_poly_sum_allocations() {
+        let polys = (0..100usize)
+            .map(|i| CoefficientPoly::from(vec![Z251::from(i); 10]))
+            .collect::<Vec<_>>();
+        let weights = (0..100usize).map(|i| Z251::from(i + 3)).collect::<Vec<_>>();
+
+        let before = allocations();
+        let sum = weighted_poly_sum(&polys, &weights);
+        let after = allocations();
+
+        // One vector for the sum however many polynomials there are, where
+        // cloning each of them took at least one per polynomial
+        assert_eq!(after - before, 1);
+
+        let expected = polys
+            .iter()
+            .zip(weights.iter())
+            .map(|(p, &a)| p.clone() * a)
+            .sum::<CoefficientPoly<Z251>>();
+        assert!((0..10).all(|k| sum.coefficient(k) == expected.coefficient(k)));
+    }
 }
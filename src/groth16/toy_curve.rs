@@ -0,0 +1,457 @@
+//! A toy twisted Edwards curve `-x^2 + y^2 = 1 + 241 x^2 y^2` over `Zp<997>`,
+//! whose points form a group of order `4 * 251`. The subgroup of order 251
+//! makes `Zp<251>` (as `ToyScalar`) an `EllipticEncryptable` with real point
+//! addition and with `G1`, `G2` and `GT` all different types, unlike `Z251`
+//! where all three are the field itself.
+//!
+//! A pairing on a curve this small is not worth computing, so `pairing` takes
+//! the discrete logarithm of both points by brute force, which is only
+//! possible because the group is tiny. That is bilinear, which is all groth16
+//! needs, but there is no security here at all: this is for testing that
+//! `setup`, `prove` and `verify` only use the group operations.
+//!
+//! Since the coordinates are in `ToyBase`, a `Circuit<ToyBase>` can also do
+//! arithmetic on the curve, which `Circuit::new_pedersen_hash` uses.
+
+use super::circuit::{Circuit, RootRepresentation, WireId};
+use super::coefficient_poly::CoefficientPoly;
+use super::{EllipticEncryptable, QAP};
+use field::zp::Zp;
+use field::{Field, FieldIdentity};
+use std::iter::Sum;
+use std::ops::{Add, Neg, Sub};
+
+/// The scalars of the toy curve, which is the order of its prime subgroup.
+pub type ToyScalar = Zp<251>;
+
+/// The field that the coordinates of the toy curve are in.
+pub type ToyBase = Zp<997>;
+
+/// `a` and `d` of the curve `a x^2 + y^2 = 1 + d x^2 y^2`. Since `a` is a
+/// square and `d` is not, the addition law is complete.
+const A: ToyBase = Zp { inner: 996 };
+const D: ToyBase = Zp { inner: 241 };
+
+/// Generators of the subgroup of order 251, used for `G1` and `G2`.
+const G1_GENERATOR: EdwardsPoint = EdwardsPoint {
+    x: Zp { inner: 775 },
+    y: Zp { inner: 642 },
+};
+const G2_GENERATOR: EdwardsPoint = EdwardsPoint {
+    x: Zp { inner: 270 },
+    y: Zp { inner: 308 },
+};
+
+/// A point on the toy curve in affine coordinates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EdwardsPoint {
+    pub x: ToyBase,
+    pub y: ToyBase,
+}
+
+impl EdwardsPoint {
+    /// The point `(0, 1)`, which is the identity of the group.
+    pub fn identity() -> Self {
+        EdwardsPoint {
+            x: ToyBase::zero(),
+            y: ToyBase::one(),
+        }
+    }
+
+    pub fn is_on_curve(&self) -> bool {
+        let (xx, yy) = (self.x * self.x, self.y * self.y);
+        A * xx + yy == ToyBase::one() + D * xx * yy
+    }
+
+    /// `n` copies of `self` added together, by double and add.
+    pub fn mul_scalar(self, mut n: u64) -> Self {
+        let (mut base, mut acc) = (self, EdwardsPoint::identity());
+        while n != 0 {
+            if n & 1 == 1 {
+                acc = acc + base;
+            }
+            base = base + base;
+            n >>= 1;
+        }
+        acc
+    }
+
+    /// The `k` such that `k * generator == self`, found by trying every
+    /// multiple of `generator`. Panics if there is none.
+    fn discrete_log(self, generator: EdwardsPoint) -> ToyScalar {
+        let mut multiple = EdwardsPoint::identity();
+        for k in 0..251 {
+            if multiple == self {
+                return ToyScalar::from(k);
+            }
+            multiple = multiple + generator;
+        }
+        panic!("discrete_log: the point is not in the subgroup of the generator")
+    }
+}
+
+impl Add for EdwardsPoint {
+    type Output = EdwardsPoint;
+
+    fn add(self, rhs: EdwardsPoint) -> Self::Output {
+        let t = D * self.x * rhs.x * self.y * rhs.y;
+
+        EdwardsPoint {
+            x: (self.x * rhs.y + self.y * rhs.x) / (ToyBase::one() + t),
+            y: (self.y * rhs.y - A * self.x * rhs.x) / (ToyBase::one() - t),
+        }
+    }
+}
+
+impl Neg for EdwardsPoint {
+    type Output = EdwardsPoint;
+
+    fn neg(self) -> Self::Output {
+        EdwardsPoint {
+            x: -self.x,
+            y: self.y,
+        }
+    }
+}
+
+impl Sub for EdwardsPoint {
+    type Output = EdwardsPoint;
+
+    fn sub(self, rhs: EdwardsPoint) -> Self::Output {
+        self + -rhs
+    }
+}
+
+/// An element of `G1` for `ToyScalar`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ToyG1(pub EdwardsPoint);
+
+/// An element of `G2` for `ToyScalar`. This is the same curve as `ToyG1`
+/// with a different generator, but is kept as its own type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ToyG2(pub EdwardsPoint);
+
+/// An element of `GT` for `ToyScalar`, which is kept as its discrete
+/// logarithm so the group operation is addition of the exponents.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ToyGt(pub ToyScalar);
+
+impl Add for ToyG1 {
+    type Output = ToyG1;
+
+    fn add(self, rhs: ToyG1) -> Self::Output {
+        ToyG1(self.0 + rhs.0)
+    }
+}
+
+impl Sub for ToyG1 {
+    type Output = ToyG1;
+
+    fn sub(self, rhs: ToyG1) -> Self::Output {
+        ToyG1(self.0 - rhs.0)
+    }
+}
+
+impl Sum for ToyG1 {
+    fn sum<I>(iter: I) -> Self
+    where
+        I: Iterator<Item = Self>,
+    {
+        iter.fold(ToyG1(EdwardsPoint::identity()), |acc, x| acc + x)
+    }
+}
+
+impl Add for ToyG2 {
+    type Output = ToyG2;
+
+    fn add(self, rhs: ToyG2) -> Self::Output {
+        ToyG2(self.0 + rhs.0)
+    }
+}
+
+impl Sub for ToyG2 {
+    type Output = ToyG2;
+
+    fn sub(self, rhs: ToyG2) -> Self::Output {
+        ToyG2(self.0 - rhs.0)
+    }
+}
+
+impl Sum for ToyG2 {
+    fn sum<I>(iter: I) -> Self
+    where
+        I: Iterator<Item = Self>,
+    {
+        iter.fold(ToyG2(EdwardsPoint::identity()), |acc, x| acc + x)
+    }
+}
+
+impl Add for ToyGt {
+    type Output = ToyGt;
+
+    fn add(self, rhs: ToyGt) -> Self::Output {
+        ToyGt(self.0 + rhs.0)
+    }
+}
+
+impl EllipticEncryptable for ToyScalar {
+    type G1 = ToyG1;
+    type G2 = ToyG2;
+    type GT = ToyGt;
+
+    fn encrypt_g1(self) -> Self::G1 {
+        ToyG1(G1_GENERATOR.mul_scalar(self.to_canonical_u64()))
+    }
+    fn encrypt_g2(self) -> Self::G2 {
+        ToyG2(G2_GENERATOR.mul_scalar(self.to_canonical_u64()))
+    }
+    fn exp_encrypted_g1(self, g1: Self::G1) -> Self::G1 {
+        #[cfg(feature = "metrics")]
+        super::metrics::count_exponentiations(1);
+        ToyG1(g1.0.mul_scalar(self.to_canonical_u64()))
+    }
+    fn exp_encrypted_g2(self, g2: Self::G2) -> Self::G2 {
+        ToyG2(g2.0.mul_scalar(self.to_canonical_u64()))
+    }
+    fn pairing(g1: Self::G1, g2: Self::G2) -> Self::GT {
+        #[cfg(feature = "metrics")]
+        super::metrics::count_pairings(1);
+        ToyGt(g1.0.discrete_log(G1_GENERATOR) * g2.0.discrete_log(G2_GENERATOR))
+    }
+}
+
+/// The number of bits of each input to `pedersen_hash`.
+const PEDERSEN_BITS: usize = 8;
+
+/// The Pedersen hash `a * G1 + b * G2` of two values below `2^8`, given by
+/// the `x` of the point. The generators are not known to be independent, so
+/// this is for comparing against `Circuit::new_pedersen_hash` and not for
+/// commitments that need to be binding.
+pub fn pedersen_hash(a: ToyBase, b: ToyBase) -> ToyBase {
+    let (a, b) = (a.to_canonical_u64(), b.to_canonical_u64());
+    assert!(
+        a >> PEDERSEN_BITS == 0 && b >> PEDERSEN_BITS == 0,
+        "pedersen_hash: the inputs must be below 2^8"
+    );
+
+    (G1_GENERATOR.mul_scalar(a) + G2_GENERATOR.mul_scalar(b)).x
+}
+
+impl Circuit<ToyBase> {
+    /// The same as `pedersen_hash` but in the circuit. `a` and `b` are split
+    /// into bits with `new_range_check`, and each bit that is set adds its
+    /// multiple of the generator with `new_edwards_add`.
+    ///
+    /// ```
+    /// use zksnark::groth16::circuit::Circuit;
+    /// use zksnark::groth16::toy_curve::{pedersen_hash, ToyBase};
+    ///
+    /// let mut circuit = Circuit::<ToyBase>::new();
+    /// let (a, b) = (circuit.new_wire(), circuit.new_wire());
+    /// let hash = circuit.new_pedersen_hash(a, b);
+    ///
+    /// circuit.set_value(a, ToyBase::from(12));
+    /// circuit.set_value(b, ToyBase::from(200));
+    /// assert_eq!(
+    ///     circuit.evaluate(hash),
+    ///     pedersen_hash(ToyBase::from(12), ToyBase::from(200))
+    /// );
+    /// ```
+    pub fn new_pedersen_hash(&mut self, a: WireId, b: WireId) -> WireId {
+        let one = ToyBase::one();
+        let unity = self.unity_wire();
+
+        let mut acc = (vec![], vec![(one, unity)]);
+        for &(input, generator) in [(a, G1_GENERATOR), (b, G2_GENERATOR)].iter() {
+            let mut multiple = generator;
+
+            for bit in self.new_range_check(input, PEDERSEN_BITS) {
+                // (0, 1) when the bit is zero and the multiple when it is one
+                let chosen = (
+                    vec![(multiple.x, bit)],
+                    vec![(one, unity), (multiple.y - one, bit)],
+                );
+                let (x, y) = self.new_edwards_add(acc, chosen, A, D);
+
+                acc = (vec![(one, x)], vec![(one, y)]);
+                multiple = multiple + multiple;
+            }
+        }
+
+        match acc.0.as_slice() {
+            [(_, x)] => *x,
+            _ => unreachable!("new_pedersen_hash: there is always at least one addition"),
+        }
+    }
+}
+
+impl<R> From<R> for QAP<CoefficientPoly<ToyScalar>>
+where
+    R: RootRepresentation<ToyScalar>,
+{
+    fn from(root_rep: R) -> Self {
+        super::interpolate_root_rep(root_rep)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::circuit::dummy_rep::DummyRep;
+    use super::super::circuit::{ASTParser, CircuitInstance, TryParse};
+    use super::*;
+
+    /// As for `Z65521`, encrypting is multiplying by a generator, so that a
+    /// `Circuit<ToyBase>` can be proved.
+    impl EllipticEncryptable for ToyBase {
+        type G1 = Self;
+        type G2 = Self;
+        type GT = Self;
+
+        fn encrypt_g1(self) -> Self::G1 {
+            self * ToyBase::from(7)
+        }
+        fn encrypt_g2(self) -> Self::G2 {
+            self * ToyBase::from(7)
+        }
+        fn exp_encrypted_g1(self, g1: Self::G1) -> Self::G1 {
+            #[cfg(feature = "metrics")]
+            super::super::metrics::count_exponentiations(1);
+            self * g1
+        }
+        fn exp_encrypted_g2(self, g2: Self::G2) -> Self::G2 {
+            self * g2
+        }
+        fn pairing(g1: Self::G1, g2: Self::G2) -> Self::GT {
+            #[cfg(feature = "metrics")]
+            super::super::metrics::count_pairings(1);
+            g1 * g2
+        }
+    }
+
+    #[test]
+    fn toy_curve_generators() {
+        for &g in [G1_GENERATOR, G2_GENERATOR].iter() {
+            assert!(g.is_on_curve());
+            assert!(g != EdwardsPoint::identity());
+            assert_eq!(g.mul_scalar(251), EdwardsPoint::identity());
+        }
+    }
+
+    #[test]
+    fn toy_curve_group_law() {
+        let (p, q) = (G1_GENERATOR.mul_scalar(17), G1_GENERATOR.mul_scalar(200));
+
+        assert!((p + q).is_on_curve());
+        assert_eq!(p + q, q + p);
+        assert_eq!(p + q, G1_GENERATOR.mul_scalar(217));
+        assert_eq!(p - p, EdwardsPoint::identity());
+    }
+
+    #[test]
+    fn toy_curve_pairing_bilinear() {
+        let (a, b) = (ToyScalar::from(12), ToyScalar::from(99));
+        let (g1, g2) = (ToyScalar::one().encrypt_g1(), ToyScalar::one().encrypt_g2());
+
+        assert_eq!(
+            ToyScalar::pairing(a.encrypt_g1(), b.encrypt_g2()),
+            ToyScalar::pairing(a.exp_encrypted_g1(b.encrypt_g1()), g2)
+        );
+        assert_eq!(
+            ToyScalar::pairing(g1, g2) + ToyScalar::pairing(g1, g2),
+            ToyScalar::pairing(ToyScalar::from(2).encrypt_g1(), g2)
+        );
+    }
+
+    #[test]
+    fn pedersen_hash_circuit() {
+        let mut circuit = Circuit::<ToyBase>::new();
+        let (a, b) = (circuit.new_wire(), circuit.new_wire());
+        let hash = circuit.new_pedersen_hash(a, b);
+
+        for &(x, y) in [(0, 0), (1, 0), (0, 1), (12, 200), (255, 255)].iter() {
+            let (x, y) = (ToyBase::from(x), ToyBase::from(y));
+
+            circuit.reset();
+            circuit.set_value(a, x);
+            circuit.set_value(b, y);
+            assert_eq!(circuit.evaluate(hash), pedersen_hash(x, y));
+            assert!(circuit.check_constraints().is_ok());
+        }
+
+        // The native hash is of a point on the curve
+        let point = G1_GENERATOR.mul_scalar(12) + G2_GENERATOR.mul_scalar(200);
+        assert!(point.is_on_curve());
+        assert_eq!(pedersen_hash(ToyBase::from(12), ToyBase::from(200)), point.x);
+
+        // An input too large for 8 bits fails its range check
+        circuit.reset();
+        circuit.set_value(a, ToyBase::from(256));
+        circuit.set_value(b, ToyBase::from(0));
+        assert_eq!(
+            circuit.check_constraints().unwrap_err().label,
+            Some("range check".to_string())
+        );
+    }
+
+    #[test]
+    fn pedersen_hash_prove_verify() {
+        let mut circuit = Circuit::<ToyBase>::new();
+        let (a, b) = (circuit.new_wire(), circuit.new_wire());
+        let hash = circuit.new_pedersen_hash(a, b);
+
+        // The range checks, and the checks of the inverses that the additions
+        // divide by, are only enforced if they are verified to be zero
+        let zero_checks = circuit.zero_checks().len();
+        let mut verify_wires = vec![hash];
+        verify_wires.extend(circuit.zero_checks().iter());
+
+        let mut instance = CircuitInstance::new(circuit, verify_wires, vec![a, b], |w| {
+            ToyBase::from(w.inner_id() + 1)
+        });
+        let qap: QAP<CoefficientPoly<ToyBase>> =
+            super::super::interpolate_root_rep(DummyRep::from(&instance));
+        let weights = instance.weights(vec![ToyBase::from(12), ToyBase::from(200)]);
+
+        let (sigmag1, sigmag2) = super::super::setup(&qap);
+        let proof = super::super::prove(&qap, (&sigmag1, &sigmag2), &weights);
+
+        let mut expected = vec![pedersen_hash(ToyBase::from(12), ToyBase::from(200))];
+        expected.extend(vec![ToyBase::zero(); zero_checks]);
+        assert!(super::super::verify::<CoefficientPoly<ToyBase>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
+            &expected,
+            &proof
+        ));
+
+        expected[0] = expected[0] + ToyBase::one();
+        assert!(!super::super::verify::<CoefficientPoly<ToyBase>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
+            &expected,
+            &proof
+        ));
+    }
+
+    #[test]
+    fn simple_circuit_toy_curve() {
+        // x = 4ab + c + 6
+        let code = &*::std::fs::read_to_string("test_programs/simple.zk").unwrap();
+        let qap: QAP<CoefficientPoly<ToyScalar>> = ASTParser::try_parse(code).unwrap().into();
+
+        let assignments = &[3.into(), 2.into(), 4.into()];
+        let weights = super::super::weights(code, assignments).unwrap();
+
+        let (sigmag1, sigmag2) = super::super::setup(&qap);
+        let proof = super::super::prove(&qap, (&sigmag1, &sigmag2), &weights);
+
+        assert!(super::super::verify::<CoefficientPoly<ToyScalar>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
+            &[ToyScalar::from(2), ToyScalar::from(34)],
+            &proof
+        ));
+        assert!(!super::super::verify::<CoefficientPoly<ToyScalar>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
+            &[ToyScalar::from(2), ToyScalar::from(25)],
+            &proof
+        ));
+    }
+}
@@ -11,17 +11,19 @@ extern crate serde;
 extern crate serde_derive;
 extern crate serde_json;
 extern crate bn;
+#[cfg(feature = "parallel")] extern crate rayon;
 
 pub mod encryption;
 pub mod field;
 pub mod groth16;
 
 #[doc(hidden)] pub use groth16::circuit::dummy_rep::DummyRep;
-#[doc(hidden)] pub use groth16::circuit::{ASTParser, TryParse};
+#[doc(hidden)] pub use groth16::circuit::{fuzz_parse, ASTParser, TryParse};
 #[doc(hidden)] pub use groth16::circuit::{Circuit, CircuitInstance, WireId};
 #[doc(hidden)] pub use groth16::coefficient_poly::CoefficientPoly;
 #[doc(hidden)] pub use groth16::fr::FrLocal;
-#[doc(hidden)] pub use groth16::{Proof, SigmaG1, SigmaG2, QAP};
+#[doc(hidden)] pub use groth16::{PreparedVerifyingKey, Proof, SigmaG1, SigmaG2, VerifyingKey, QAP};
+#[doc(hidden)] pub use groth16::VerifyContext;
 
 #[cfg(test)]
 mod tests {
@@ -36,6 +38,9 @@ mod tests {
     extern crate tiny_keccak;
     use self::tiny_keccak::keccak256;
 
+    extern crate blake2;
+    use self::blake2::{Blake2s, Digest};
+
     #[test]
     fn simple_circuit_test() {
         // x = 4ab + c + 6
@@ -59,20 +64,70 @@ mod tests {
         let proof = groth16::prove(&qap, (&sigmag1, &sigmag2), &weights);
 
         assert!(groth16::verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
-            (sigmag1, sigmag2),
+            (&sigmag1, &sigmag2),
             &vec![FrLocal::from(2), FrLocal::from(34)],
-            proof
+            &proof
         ));
 
-        let (sigmag1, sigmag2) = groth16::setup(&qap);
+        // The same CRS and proof can be checked again against other inputs
+        assert!(!groth16::verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
+            &vec![FrLocal::from(2), FrLocal::from(25)],
+            &proof
+        ));
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn simple_circuit_verify_metrics() {
+        use groth16::metrics;
+
+        let code = &*::std::fs::read_to_string("test_programs/simple.zk").unwrap();
+        let qap: QAP<CoefficientPoly<FrLocal>> = ASTParser::try_parse(code).unwrap().into();
+        let weights = groth16::weights(code, &[3.into(), 2.into(), 4.into()]).unwrap();
 
+        let (sigmag1, sigmag2) = groth16::setup(&qap);
         let proof = groth16::prove(&qap, (&sigmag1, &sigmag2), &weights);
 
-        assert!(!groth16::verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
-            (sigmag1, sigmag2),
-            &vec![FrLocal::from(2), FrLocal::from(25)],
-            proof
+        metrics::reset();
+        assert!(groth16::verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
+            &vec![FrLocal::from(2), FrLocal::from(34)],
+            &proof
         ));
+
+        // One exponentiation for the unity wire and one for each of the two
+        // verified wires, then the four pairings of the groth16 check.
+        let ops = metrics::snapshot();
+        assert_eq!(ops.pairings, 4);
+        assert_eq!(ops.exponentiations, 3);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn simple_circuit_verify_prepared_metrics() {
+        use groth16::metrics;
+        use groth16::VerifyingKey;
+
+        let code = &*::std::fs::read_to_string("test_programs/simple.zk").unwrap();
+        let qap: QAP<CoefficientPoly<FrLocal>> = ASTParser::try_parse(code).unwrap().into();
+        let weights = groth16::weights(code, &[3.into(), 2.into(), 4.into()]).unwrap();
+
+        let (sigmag1, sigmag2) = groth16::setup(&qap);
+        let proof = groth16::prove(&qap, (&sigmag1, &sigmag2), &weights);
+        let inputs = vec![FrLocal::from(2), FrLocal::from(34)];
+
+        // The pairing of alpha and beta is done once by `prepare`, so each
+        // proof after that takes three pairings instead of four.
+        metrics::reset();
+        let pvk = VerifyingKey::from((&sigmag1, &sigmag2)).prepare::<FrLocal, _>();
+        for _ in 0..2 {
+            assert!(groth16::verify_prepared(&pvk, &inputs, &proof));
+        }
+
+        let ops = metrics::snapshot();
+        assert_eq!(ops.pairings, 1 + 2 * 3);
+        assert_eq!(ops.exponentiations, 2 * 3);
     }
 
     fn to_bits(mut num: u8) -> [u8; 8] {
@@ -86,6 +141,33 @@ mod tests {
         bits
     }
 
+    #[test]
+    fn fuzz_parse_test() {
+        use encryption::rand::random;
+
+        // Arbitrary bytes are almost never valid UTF-8, so also try random
+        // sequences of tokens which get much further into the parser
+        let tokens = [
+            "(", ")", "(in", "(out", "(verify", "(program", "(=", "(*", "(+", "(hash", "x",
+            "y)", "3", "4))", "in", "=", "x(", "*x", "))",
+        ];
+
+        for _ in 0..1000 {
+            let len = random::<u8>() as usize;
+            let bytes = (0..len).map(|_| random::<u8>()).collect::<Vec<_>>();
+            fuzz_parse(&bytes);
+
+            let code = (0..len % 32)
+                .map(|_| tokens[random::<usize>() % tokens.len()])
+                .collect::<Vec<_>>()
+                .join(" ");
+            fuzz_parse(code.as_bytes());
+        }
+
+        let code = ::std::fs::read_to_string("test_programs/simple.zk").unwrap();
+        assert!(fuzz_parse(code.as_bytes()));
+    }
+
     #[test]
     fn comparator_8bit_test() {
         // Circuit for checking if a > b
@@ -117,9 +199,9 @@ mod tests {
                 );
 
                 assert!(groth16::verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
-                    (sigmag1, sigmag2),
+                    (&sigmag1, &sigmag2),
                     &inputs,
-                    proof
+                    &proof
                 ));
             } else {
                 let mut inputs = vec![Z251::from(0)];
@@ -131,14 +213,157 @@ mod tests {
                 );
 
                 assert!(groth16::verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
-                    (sigmag1, sigmag2),
+                    (&sigmag1, &sigmag2),
                     &inputs,
-                    proof
+                    &proof
                 ));
             }
         }
     }
 
+    #[test]
+    fn build_comparator_test() {
+        let code = &*::std::fs::read_to_string("test_programs/8bit_comparator.zk").unwrap();
+        let mut instance = groth16::circuit::build_comparator::<Z251>(8);
+        let qap: QAP<CoefficientPoly<Z251>> = QAP::from(DummyRep::from(&instance));
+        let (sigmag1, sigmag2) = groth16::setup(&qap);
+
+        for _ in 0..100 {
+            let (a, b) = (Z251::random_elem(), Z251::random_elem());
+            let (abits, bbits) = (to_bits(a.inner), to_bits(b.inner));
+            let to_field = |bits: &[u8]| {
+                bits.iter()
+                    .map(|&bit| Z251::from(bit as usize))
+                    .collect::<Vec<_>>()
+            };
+            let assignments = to_field(&abits)
+                .into_iter()
+                .chain(to_field(&bbits))
+                .collect::<Vec<_>>();
+
+            // The result is the first weight after the unity wire in both
+            let zk_weights = groth16::weights(code, &assignments).unwrap();
+            let weights = instance.weights(assignments);
+            assert_eq!(weights[1], zk_weights[1]);
+            assert_eq!(weights[1], Z251::from((a.inner > b.inner) as usize));
+
+            let proof = groth16::prove(&qap, (&sigmag1, &sigmag2), &weights);
+            let mut inputs = vec![weights[1]];
+            inputs.extend(to_field(&bbits));
+            // Every input is a bit, so all 16 bit checkers are zero
+            inputs.extend(vec![Z251::from(0); 16]);
+            assert!(groth16::verify::<CoefficientPoly<Z251>, _, _, _, _>(
+                (&sigmag1, &sigmag2),
+                &inputs,
+                &proof
+            ));
+
+            // The bit checkers are part of the statement
+            *inputs.last_mut().unwrap() = Z251::from(1);
+            assert!(!groth16::verify::<CoefficientPoly<Z251>, _, _, _, _>(
+                (&sigmag1, &sigmag2),
+                &inputs,
+                &proof
+            ));
+        }
+    }
+
+    #[test]
+    fn comparator_8bit_z65521_test() {
+        use field::zp::Z65521;
+
+        // Circuit for checking if a > b, which unlike 8bit_comparator.zk
+        // writes -1 rather than 250 so that it works in any field
+        let code =
+            &*::std::fs::read_to_string("test_programs/8bit_comparator_any_field.zk").unwrap();
+        let qap: QAP<CoefficientPoly<Z65521>> = ASTParser::try_parse(code).unwrap().into();
+        let (sigmag1, sigmag2) = groth16::setup(&qap);
+
+        for &(a, b) in [(200, 100), (100, 200), (7, 7), (255, 0)].iter() {
+            let (abits, bbits) = (to_bits(a), to_bits(b));
+            let to_field = |bits: &[u8]| {
+                bits.iter()
+                    .map(|&bit| Z65521::from(bit as usize))
+                    .collect::<Vec<_>>()
+            };
+
+            let assignments = to_field(&abits)
+                .into_iter()
+                .chain(to_field(&bbits))
+                .collect::<Vec<_>>();
+            let weights = groth16::weights(code, &assignments).unwrap();
+            let proof = groth16::prove(&qap, (&sigmag1, &sigmag2), &weights);
+
+            let greater = Z65521::from((a > b) as usize);
+            let mut inputs = vec![greater];
+            inputs.extend(to_field(&bbits));
+            assert!(groth16::verify::<CoefficientPoly<Z65521>, _, _, _, _>(
+                (&sigmag1, &sigmag2),
+                &inputs,
+                &proof
+            ));
+
+            inputs[0] = Z65521::from(1) - greater;
+            assert!(!groth16::verify::<CoefficientPoly<Z65521>, _, _, _, _>(
+                (&sigmag1, &sigmag2),
+                &inputs,
+                &proof
+            ));
+        }
+    }
+
+    #[test]
+    fn simple_circuit_z4294967291_test() {
+        use field::zp::Z4294967291;
+
+        // x = 4ab + c + 6, with a value of x that would not fit in Z251
+        let code = &*::std::fs::read_to_string("test_programs/simple.zk").unwrap();
+        let qap: QAP<CoefficientPoly<Z4294967291>> = ASTParser::try_parse(code).unwrap().into();
+        let weights = groth16::weights(code, &[1000.into(), 2000.into(), 4.into()]).unwrap();
+
+        let (sigmag1, sigmag2) = groth16::setup(&qap);
+        let proof = groth16::prove(&qap, (&sigmag1, &sigmag2), &weights);
+
+        assert!(groth16::verify::<CoefficientPoly<Z4294967291>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
+            &[Z4294967291::from(2000), Z4294967291::from(8_000_010)],
+            &proof
+        ));
+    }
+
+    #[test]
+    fn comparator_8bit_array_test() {
+        // The same circuit with its inputs declared as `(in a[8] b[8])`
+        let unrolled = &*::std::fs::read_to_string("test_programs/8bit_comparator.zk").unwrap();
+        let code = &*::std::fs::read_to_string("test_programs/8bit_comparator_array.zk").unwrap();
+
+        let qap: QAP<CoefficientPoly<Z251>> = ASTParser::try_parse(code).unwrap().into();
+        let unrolled_qap: QAP<CoefficientPoly<Z251>> =
+            ASTParser::try_parse(unrolled).unwrap().into();
+        assert!(qap == unrolled_qap);
+
+        // a = 200 and b = 100
+        let (abits, bbits) = (to_bits(200), to_bits(100));
+        let assignments = abits
+            .iter()
+            .chain(bbits.iter())
+            .map(|&bit| Z251::from(bit as usize))
+            .collect::<Vec<_>>();
+        let weights = groth16::weights(code, &assignments).unwrap();
+        assert_eq!(weights, groth16::weights(unrolled, &assignments).unwrap());
+
+        let (sigmag1, sigmag2) = groth16::setup(&qap);
+        let proof = groth16::prove(&qap, (&sigmag1, &sigmag2), &weights);
+
+        let mut inputs = vec![Z251::from(1)];
+        inputs.extend(bbits.iter().map(|&bit| Z251::from(bit as usize)));
+        assert!(groth16::verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
+            &inputs,
+            &proof
+        ));
+    }
+
     #[test]
     fn circuit_builder_test() {
         // Build the circuit
@@ -161,9 +386,720 @@ mod tests {
         let proof = groth16::prove(&qap, (&sigmag1, &sigmag2), &weights);
 
         assert!(groth16::verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
-            (sigmag1, sigmag2),
+            (&sigmag1, &sigmag2),
+            &[FrLocal::from(0), FrLocal::from(0), FrLocal::from(1)],
+            &proof
+        ));
+    }
+
+    #[test]
+    fn circuit_builder_dot_test() {
+        // The circuit of circuit_builder_test
+        let mut circuit = Circuit::<FrLocal>::new();
+        let x = circuit.new_wire();
+        circuit.new_bit_checker(x);
+        let y = circuit.new_wire();
+        circuit.new_bit_checker(y);
+        circuit.new_or(x, y);
+
+        let dot = circuit.to_dot();
+        let count = |pattern: &str| dot.lines().filter(|line| line.contains(pattern)).count();
+
+        assert!(dot.starts_with("digraph circuit {"));
+        // Four gates, two bit checkers and the two of `new_or`
+        assert_eq!(count("shape=box"), 4);
+        // The inputs x, y and unity, and the outputs of the checkers and `or`
+        assert_eq!(count("shape=plaintext"), 6);
+        // Three inputs to each bit checker, two to the `and` of `new_or`,
+        // four to its `or`, and one edge to each of the three outputs
+        assert_eq!(count("->"), 15);
+    }
+
+    #[test]
+    fn duplicate_public_input_test() {
+        use groth16::circuit::DuplicatePublicInput;
+
+        // x * y, with the wires that are verified given separately
+        let build = || {
+            let mut circuit = Circuit::<FrLocal>::new();
+            let (x, y) = (circuit.new_wire(), circuit.new_wire());
+            let product = circuit.new_sub_circuit(
+                vec![(FrLocal::from(1), x)],
+                vec![(FrLocal::from(1), y)],
+            );
+            (circuit, x, y, product)
+        };
+
+        let (circuit, x, y, product) = build();
+        let duplicated = CircuitInstance::try_new(circuit, vec![product, x, product], vec![x, y], |w| {
+            FrLocal::from(w.inner_id() + 1)
+        });
+        match duplicated {
+            Err(e) => assert_eq!(e, DuplicatePublicInput { wire: product }),
+            Ok(_) => panic!("a duplicated public input was not rejected"),
+        }
+
+        let (circuit, x, y, product) = build();
+        assert!(
+            CircuitInstance::try_new(circuit, vec![product, x], vec![x, y], |w| {
+                FrLocal::from(w.inner_id() + 1)
+            }).is_ok()
+        );
+    }
+
+    #[test]
+    fn import_circom_r1cs_test() {
+        // c = a * b, with the output c public and the inputs a and b private
+        let json = r#"{
+            "n8": 32,
+            "prime": "21888242871839275222246405745257275088548364400416034343698204186575808495617",
+            "nVars": 4,
+            "nOutputs": 1,
+            "nPubInputs": 0,
+            "nPrvInputs": 2,
+            "nLabels": 4,
+            "nConstraints": 1,
+            "constraints": [
+                [{"2": "1"}, {"3": "1"}, {"1": "1"}]
+            ],
+            "map": [0, 1, 2, 3]
+        }"#;
+        let mut instance = groth16::import_circom_r1cs(json).unwrap();
+
+        let qap: QAP<CoefficientPoly<FrLocal>> = QAP::from(DummyRep::from(&instance));
+        let witness = vec![FrLocal::from(15), FrLocal::from(3), FrLocal::from(5)];
+        let weights = instance.weights(witness);
+
+        let (sigmag1, sigmag2) = groth16::setup(&qap);
+        let proof = groth16::prove(&qap, (&sigmag1, &sigmag2), &weights);
+
+        // The public output followed by a zero for the one constraint
+        assert!(groth16::verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
+            &[FrLocal::from(15), FrLocal::from(0)],
+            &proof
+        ));
+        assert!(!groth16::verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
+            &[FrLocal::from(16), FrLocal::from(0)],
+            &proof
+        ));
+    }
+
+    #[test]
+    fn common_subexpr_elim_test() {
+        // q = a * b + b * a, where the second product repeats the first
+        let mut circuit = Circuit::<FrLocal>::new();
+        let (a, b) = (circuit.new_wire(), circuit.new_wire());
+        let ab = circuit.new_sub_circuit(vec![(FrLocal::from(1), a)], vec![(FrLocal::from(1), b)]);
+        let ba = circuit.new_sub_circuit(vec![(FrLocal::from(1), b)], vec![(FrLocal::from(1), a)]);
+        let one = circuit.one_wire();
+        let q = circuit.new_sub_circuit(
+            vec![(FrLocal::from(1), ab), (FrLocal::from(1), ba)],
+            vec![(FrLocal::from(1), one)],
+        );
+
+        assert_eq!(circuit.sub_circuits().count(), 3);
+        let replaced = circuit.common_subexpr_elim();
+        assert_eq!(circuit.sub_circuits().count(), 2);
+        assert!(replaced.len() == 1 && replaced[&ba] == ab);
+
+        let mut instance =
+            CircuitInstance::new(circuit, vec![q], vec![a, b], |w| FrLocal::from(w.inner_id() + 1));
+        let qap: QAP<CoefficientPoly<FrLocal>> = QAP::from(DummyRep::from(&instance));
+        let weights = instance.weights(vec![FrLocal::from(3), FrLocal::from(5)]);
+
+        let (sigmag1, sigmag2) = groth16::setup(&qap);
+        let proof = groth16::prove(&qap, (&sigmag1, &sigmag2), &weights);
+
+        assert!(groth16::verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
+            &[FrLocal::from(30)],
+            &proof
+        ));
+        assert!(!groth16::verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
+            &[FrLocal::from(15)],
+            &proof
+        ));
+    }
+
+    #[test]
+    fn one_wire_weight_test() {
+        let mut circuit = Circuit::<FrLocal>::new();
+        let x = circuit.new_wire();
+        let one = circuit.one_wire();
+        assert!(one == circuit.unity_wire());
+
+        // 3x + 2 is weighted by the one wire, but the output is not verified
+        circuit.new_sub_circuit(
+            vec![(FrLocal::from(3), x), (FrLocal::from(2), one)],
+            vec![(FrLocal::from(1), one)],
+        );
+        let mut instance =
+            CircuitInstance::new(circuit, vec![], vec![x], |w| FrLocal::from(w.inner_id() + 1));
+        let dummy_rep = DummyRep::from(&instance);
+
+        for value in 0..5 {
+            let weights = instance.weights(vec![FrLocal::from(value)]);
+            assert!(weights[0] == FrLocal::from(1));
+            assert!(weights.contains(&FrLocal::from(3 * value + 2)));
+        }
+
+        // The constant 2 of the sub circuit is on the wire at index 0
+        assert!(dummy_rep.u[0] == vec![(FrLocal::from(1), FrLocal::from(2))]);
+    }
+
+    #[test]
+    fn simple_circuit_deterministic_test() {
+        let code = &*::std::fs::read_to_string("test_programs/simple.zk").unwrap();
+
+        let first: QAP<CoefficientPoly<Z251>> = ASTParser::try_parse(code).unwrap().into();
+        let second: QAP<CoefficientPoly<Z251>> = ASTParser::try_parse(code).unwrap().into();
+        assert_eq!(first, second);
+
+        // So too through the circuit builder
+        let instance = ASTParser::to_circuit::<Z251>(code).unwrap().into_instance();
+        let first: QAP<CoefficientPoly<Z251>> = QAP::from(DummyRep::from(&instance));
+        let instance = ASTParser::to_circuit::<Z251>(code).unwrap().into_instance();
+        let second: QAP<CoefficientPoly<Z251>> = QAP::from(DummyRep::from(&instance));
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn simple_circuit_describe_inputs_test() {
+        use groth16::circuit::InputRole;
+
+        // x = 4ab + c + 6, verifying b and x
+        let code = &*::std::fs::read_to_string("test_programs/simple.zk").unwrap();
+        let qap: QAP<CoefficientPoly<Z251>> = ASTParser::try_parse(code).unwrap().into();
+        let (sigmag1, sigmag2) = groth16::setup(&qap);
+        let vk = VerifyingKey::from((&sigmag1, &sigmag2));
+
+        let roles = vk.describe_inputs::<Z251>(code).unwrap();
+        println!("{:?}", roles);
+        assert_eq!(
+            roles,
+            vec![
+                InputRole::ConstantOne,
+                InputRole::PublicInput("b".to_string()),
+                InputRole::DeclaredOutput("x".to_string()),
+            ]
+        );
+
+        // The key of another program has a different number of inputs
+        let other = "(in a b) (out x) (verify x) (program (= x (* a b)))";
+        assert!(vk.describe_inputs::<Z251>(other).is_err());
+    }
+
+    #[test]
+    fn simple_circuit_pretty_test() {
+        // x = 4ab + c + 6
+        let code = &*::std::fs::read_to_string("test_programs/simple.zk").unwrap();
+        let qap: QAP<CoefficientPoly<Z251>> = ASTParser::try_parse(code).unwrap().into();
+
+        // One constraint for temp = ab and one for x
+        let pretty = qap.pretty();
+        assert!(pretty.contains("2 constraints"));
+        assert_eq!(pretty.lines().filter(|l| l.starts_with("constraint")).count(), 2);
+
+        let weights = groth16::weights(code, &[3.into(), 2.into(), 4.into()]).unwrap();
+        let (sigmag1, sigmag2) = groth16::setup(&qap);
+        let proof = groth16::prove(&qap, (&sigmag1, &sigmag2), &weights);
+
+        let debug = format!("{:?}", proof);
+        assert!(debug.contains("a:") && debug.contains("b:") && debug.contains("c:"));
+    }
+
+    #[test]
+    fn qap_input_indices_test() {
+        // The same circuit as circuit_builder_test
+        let mut circuit = Circuit::<FrLocal>::new();
+        let x = circuit.new_wire();
+        let x_checker = circuit.new_bit_checker(x);
+        let y = circuit.new_wire();
+        let y_checker = circuit.new_bit_checker(y);
+        let or = circuit.new_or(x, y);
+        let mut instance =
+            CircuitInstance::new(circuit, vec![x_checker, y_checker, or], vec![x, y], |w| {
+                FrLocal::from(w.inner_id() + 1)
+            });
+
+        let qap: QAP<CoefficientPoly<FrLocal>> = QAP::from(DummyRep::from(&instance));
+        let weights = instance.weights(vec![FrLocal::from(0), FrLocal::from(1)]);
+
+        assert_eq!(qap.public_input_indices(), vec![1, 2, 3]);
+        assert_eq!(
+            qap.public_input_indices().len() + qap.private_input_indices().len() + 1,
+            weights.len()
+        );
+
+        // The public weights are exactly the inputs that verify expects
+        let inputs = qap
+            .public_input_indices()
+            .into_iter()
+            .map(|i| weights[i])
+            .collect::<Vec<_>>();
+        assert!(inputs == vec![FrLocal::from(0), FrLocal::from(0), FrLocal::from(1)]);
+
+        let (sigmag1, sigmag2) = groth16::setup(&qap);
+        let proof = groth16::prove(&qap, (&sigmag1, &sigmag2), &weights);
+        assert!(groth16::verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
+            &inputs,
+            &proof
+        ));
+    }
+
+    #[test]
+    fn merged_public_inputs_test() {
+        use groth16::MergeInputsErr;
+
+        // Statement A is y = x^2 and statement B is z = y^2, which share y
+        let mut circuit = Circuit::<FrLocal>::new();
+        let x = circuit.new_wire();
+        let y = circuit.new_sub_circuit(vec![(FrLocal::from(1), x)], vec![(FrLocal::from(1), x)]);
+        let z = circuit.new_sub_circuit(vec![(FrLocal::from(1), y)], vec![(FrLocal::from(1), y)]);
+        let mut instance = CircuitInstance::new(circuit, vec![x, y, z], vec![x], |w| {
+            FrLocal::from(w.inner_id() + 1)
+        });
+
+        let qap: QAP<CoefficientPoly<FrLocal>> = QAP::from(DummyRep::from(&instance));
+        let weights = instance.weights(vec![FrLocal::from(3)]);
+
+        let a_inputs = vec![FrLocal::from(3), FrLocal::from(9)];
+        let b_inputs = vec![FrLocal::from(9), FrLocal::from(81)];
+        let inputs = groth16::merge_public_inputs(&a_inputs, &b_inputs, &[(1, 0)]).unwrap();
+        assert!(inputs == vec![FrLocal::from(3), FrLocal::from(9), FrLocal::from(81)]);
+
+        let (sigmag1, sigmag2) = groth16::setup(&qap);
+        let proof = groth16::prove(&qap, (&sigmag1, &sigmag2), &weights);
+        assert!(groth16::verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
+            &inputs,
+            &proof
+        ));
+
+        assert!(
+            groth16::merge_public_inputs(&a_inputs, &b_inputs, &[(0, 0)])
+                == Err(MergeInputsErr::Conflict { a: 0, b: 0 })
+        );
+    }
+
+    #[test]
+    fn simple_circuit_check_witness_test() {
+        // x = 4ab + c + 6, checked without a CRS
+        let code = &*::std::fs::read_to_string("test_programs/simple.zk").unwrap();
+        let qap: QAP<CoefficientPoly<Z251>> = ASTParser::try_parse(code).unwrap().into();
+
+        let mut weights = groth16::weights(code, &[3.into(), 2.into(), 4.into()]).unwrap();
+        assert!(groth16::check_witness(&qap, &weights));
+
+        for i in 1..weights.len() {
+            weights[i] = weights[i] + Z251::from(1);
+            assert!(!groth16::check_witness(&qap, &weights), "weight {}", i);
+            weights[i] = weights[i] - Z251::from(1);
+        }
+
+        // Too few weights or a unity wire that is not one
+        assert!(!groth16::check_witness(&qap, &weights[1..]));
+        weights[0] = Z251::from(2);
+        assert!(!groth16::check_witness(&qap, &weights));
+    }
+
+    #[test]
+    fn weights_with_hints_test() {
+        use field::Field;
+        use groth16::circuit::HintErr;
+        use std::collections::HashMap;
+
+        let mut circuit = Circuit::<FrLocal>::new();
+        let x = circuit.new_wire();
+        let nonzero = circuit.new_nonzero(x);
+        let inverse = circuit.hint_wires()[0];
+        let mut instance = CircuitInstance::new(circuit, vec![nonzero], vec![x], |w| {
+            FrLocal::from(w.inner_id() + 1)
+        });
+
+        let qap: QAP<CoefficientPoly<FrLocal>> = QAP::from(DummyRep::from(&instance));
+        let (sigmag1, sigmag2) = groth16::setup(&qap);
+
+        // The inverse of 4 supplied by someone else
+        let hints = vec![(inverse, FrLocal::from(4).mul_inv())].into_iter().collect();
+        let weights = instance
+            .weights_with_hints(vec![FrLocal::from(4)], hints)
+            .unwrap();
+        let proof = groth16::prove(&qap, (&sigmag1, &sigmag2), &weights);
+        assert!(groth16::verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
+            &[FrLocal::from(1)],
+            &proof
+        ));
+
+        // The inverse of 3 is not the inverse of 4
+        let mut hints = HashMap::new();
+        hints.insert(inverse, FrLocal::from(3).mul_inv());
+        match instance.weights_with_hints(vec![FrLocal::from(4)], hints) {
+            Err(HintErr::Unsatisfied(err)) => assert_eq!(err.label, Some("nonzero".to_string())),
+            _ => panic!("the wrong inverse was accepted"),
+        }
+
+        // Only hint wires can be given
+        let mut hints = HashMap::new();
+        hints.insert(x, FrLocal::from(4));
+        assert_eq!(
+            instance
+                .weights_with_hints(vec![FrLocal::from(4)], hints)
+                .err(),
+            Some(HintErr::NotAHint(x))
+        );
+
+        // A hint too large for a u64 makes the range check on it fail rather
+        // than panic when its bits are computed
+        let mut circuit = Circuit::<FrLocal>::new();
+        let x = circuit.new_wire();
+        let nonzero = circuit.new_nonzero(x);
+        let inverse = circuit.hint_wires()[0];
+        circuit.new_range_check(inverse, 8);
+        let mut instance = CircuitInstance::new(circuit, vec![nonzero], vec![x], |w| {
+            FrLocal::from(w.inner_id() + 1)
+        });
+
+        let mut hints = HashMap::new();
+        hints.insert(inverse, FrLocal::from(4).mul_inv());
+        match instance.weights_with_hints(vec![FrLocal::from(4)], hints) {
+            Err(HintErr::Unsatisfied(err)) => {
+                assert_eq!(err.label, Some("range check".to_string()))
+            }
+            _ => panic!("the range check passed"),
+        }
+    }
+
+    #[test]
+    fn disjoint_union_test() {
+        // x = 4ab + c + 6
+        let code = &*::std::fs::read_to_string("test_programs/simple.zk").unwrap();
+        let simple: QAP<CoefficientPoly<FrLocal>> = ASTParser::try_parse(code).unwrap().into();
+        let simple_weights = groth16::weights(code, &[3.into(), 2.into(), 4.into()]).unwrap();
+
+        // x or y, with both checked to be bits
+        let mut circuit = Circuit::<FrLocal>::new();
+        let x = circuit.new_wire();
+        let x_checker = circuit.new_bit_checker(x);
+        let y = circuit.new_wire();
+        let y_checker = circuit.new_bit_checker(y);
+        let or = circuit.new_or(x, y);
+        let mut instance =
+            CircuitInstance::new(circuit, vec![x_checker, y_checker, or], vec![x, y], |w| {
+                FrLocal::from(w.inner_id() + 1)
+            });
+        let builder: QAP<CoefficientPoly<FrLocal>> = QAP::from(DummyRep::from(&instance));
+        let builder_weights = instance.weights(vec![FrLocal::from(0), FrLocal::from(1)]);
+
+        let weights = simple.disjoint_union_weights(&builder, &simple_weights, &builder_weights);
+        let qap = simple.disjoint_union(builder);
+        assert!(groth16::check_witness(&qap, &weights));
+
+        let (sigmag1, sigmag2) = groth16::setup(&qap);
+        let proof = groth16::prove(&qap, (&sigmag1, &sigmag2), &weights);
+
+        // One proof for both statements
+        let inputs = [2, 34, 0, 0, 1].iter().map(|&n| FrLocal::from(n)).collect::<Vec<_>>();
+        assert!(groth16::verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
+            &inputs,
+            &proof
+        ));
+
+        let inputs = [2, 34, 0, 0, 0].iter().map(|&n| FrLocal::from(n)).collect::<Vec<_>>();
+        assert!(!groth16::verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
+            &inputs,
+            &proof
+        ));
+    }
+
+    #[test]
+    fn commit_output_test() {
+        // The output x * y is only revealed as a commitment
+        let mut circuit = Circuit::<FrLocal>::new();
+        let (x, y) = (circuit.new_wire(), circuit.new_wire());
+        let out = circuit.new_sub_circuit(vec![(FrLocal::from(1), x)], vec![(FrLocal::from(1), y)]);
+        let mut instance = CircuitInstance::new(circuit, vec![out], vec![x, y], |w| {
+            FrLocal::from(w.inner_id() + 1)
+        });
+        instance.commit_output(&[out]);
+
+        let qap: QAP<CoefficientPoly<FrLocal>> = QAP::from(DummyRep::from(&instance));
+        let weights = instance.weights(vec![FrLocal::from(6), FrLocal::from(7)]);
+
+        // The only public input is the commitment, which is not the output
+        assert_eq!(qap.public_input_indices(), vec![1]);
+        let commitment = weights[1];
+        assert!(commitment != FrLocal::from(42));
+
+        let (sigmag1, sigmag2) = groth16::setup(&qap);
+        let proof = groth16::prove(&qap, (&sigmag1, &sigmag2), &weights);
+
+        assert!(groth16::verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
+            &[commitment],
+            &proof
+        ));
+        assert!(!groth16::verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
+            &[commitment + FrLocal::from(1)],
+            &proof
+        ));
+    }
+
+    #[test]
+    fn aggregated_inputs_test() {
+        // Four public outputs of the private x and y, verified as one hash
+        let one = FrLocal::from(1);
+        let mut circuit = Circuit::<FrLocal>::new();
+        let (x, y) = (circuit.new_wire(), circuit.new_wire());
+        let outputs = vec![
+            circuit.new_sub_circuit(vec![(one, x)], vec![(one, y)]),
+            circuit.new_sub_circuit(vec![(one, x), (one, y)], vec![(one, circuit.unity_wire())]),
+            circuit.new_sub_circuit(vec![(one, x)], vec![(one, x)]),
+            circuit.new_sub_circuit(vec![(one, y)], vec![(one, y)]),
+        ];
+        let mut instance = CircuitInstance::new(circuit, outputs, vec![x, y], |w| {
+            FrLocal::from(w.inner_id() + 1)
+        });
+        instance.aggregate_inputs();
+
+        let qap: QAP<CoefficientPoly<FrLocal>> = QAP::from(DummyRep::from(&instance));
+        assert_eq!(qap.public_input_indices(), vec![1]);
+        let weights = instance.weights(vec![FrLocal::from(6), FrLocal::from(7)]);
+
+        let values = [42, 13, 36, 49]
+            .iter()
+            .map(|&v| FrLocal::from(v))
+            .collect::<Vec<_>>();
+        assert!(weights[1] == groth16::circuit::aggregate_public_inputs(&values));
+
+        let (sigmag1, sigmag2) = groth16::setup(&qap);
+        let proof = groth16::prove(&qap, (&sigmag1, &sigmag2), &weights);
+
+        assert!(groth16::verify_aggregated::<CoefficientPoly<FrLocal>>(
+            (&sigmag1, &sigmag2),
+            &values,
+            &proof
+        ));
+
+        let mut wrong = values.clone();
+        wrong[3] = FrLocal::from(50);
+        assert!(!groth16::verify_aggregated::<CoefficientPoly<FrLocal>>(
+            (&sigmag1, &sigmag2),
+            &wrong,
+            &proof
+        ));
+    }
+
+    #[test]
+    fn simple_circuit_to_circuit_test() {
+        // x = 4ab + c + 6
+        let code = &*::std::fs::read_to_string("test_programs/simple.zk").unwrap();
+        let parsed = ASTParser::to_circuit::<FrLocal>(code).unwrap();
+
+        // One gate for temp = ab and one for x
+        assert_eq!(parsed.circuit.sub_circuits().count(), 2);
+
+        let mut instance = parsed.into_instance();
+        let qap: QAP<CoefficientPoly<FrLocal>> = QAP::from(DummyRep::from(&instance));
+        let weights = instance.weights(vec![3.into(), 2.into(), 4.into()]);
+
+        let (sigmag1, sigmag2) = groth16::setup(&qap);
+        let proof = groth16::prove(&qap, (&sigmag1, &sigmag2), &weights);
+
+        assert!(groth16::verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
+            &vec![FrLocal::from(2), FrLocal::from(34)],
+            &proof
+        ));
+    }
+
+    #[test]
+    fn simple_circuit_public_inputs_from_witness_test() {
+        // x = 4ab + c + 6
+        let code = &*::std::fs::read_to_string("test_programs/simple.zk").unwrap();
+        let mut instance = ASTParser::to_circuit::<FrLocal>(code)
+            .unwrap()
+            .into_instance();
+        let qap: QAP<CoefficientPoly<FrLocal>> = QAP::from(DummyRep::from(&instance));
+        let weights = instance.weights(vec![3.into(), 2.into(), 4.into()]);
+
+        let inputs = instance.public_inputs_from_witness(&weights);
+        assert!(inputs == vec![FrLocal::from(2), FrLocal::from(34)]);
+
+        let (sigmag1, sigmag2) = groth16::setup(&qap);
+        let proof = groth16::prove(&qap, (&sigmag1, &sigmag2), &weights);
+
+        assert!(groth16::verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
+            &inputs,
+            &proof
+        ));
+    }
+
+    #[test]
+    fn weights_from_instance_test() {
+        use groth16::circuit::weights_from_instance;
+
+        // x = 4ab + c + 6
+        let code = &*::std::fs::read_to_string("test_programs/simple.zk").unwrap();
+        let mut instance = ASTParser::to_circuit::<FrLocal>(code)
+            .unwrap()
+            .into_instance();
+        let assignments = &[3.into(), 2.into(), 4.into()];
+
+        let from_instance = weights_from_instance(&mut instance, assignments).unwrap();
+        let from_code = groth16::weights(code, assignments).unwrap();
+
+        assert_eq!(from_instance.len(), from_code.len());
+        assert!(from_instance.iter().zip(from_code.iter()).all(|(a, b)| a == b));
+
+        // Both are also checked against the number of inputs
+        assert!(weights_from_instance(&mut instance, &[3.into()]).is_err());
+        assert!(groth16::weights(code, &[FrLocal::from(3)]).is_err());
+    }
+
+    #[test]
+    fn simple_circuit_prove_stats_test() {
+        // x = 4ab + c + 6
+        let code = &*::std::fs::read_to_string("test_programs/simple.zk").unwrap();
+        let qap: QAP<CoefficientPoly<FrLocal>> = ASTParser::try_parse(code).unwrap().into();
+        let weights = groth16::weights(code, &[3.into(), 2.into(), 4.into()]).unwrap();
+
+        let (sigmag1, sigmag2) = groth16::setup(&qap);
+        let (proof, stats) = groth16::prove_with_stats(&qap, (&sigmag1, &sigmag2), &weights);
+
+        // Two gates, and the weights of 1, b, x, temp, a and c. A and B have
+        // degree at most 1, so h = (A·B - C) / t is a constant
+        assert_eq!(
+            stats,
+            groth16::ProveStats {
+                constraints: 2,
+                witness_len: 6,
+                quotient_degree: 0,
+            }
+        );
+        assert!(groth16::verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
+            &[FrLocal::from(2), FrLocal::from(34)],
+            &proof
+        ));
+    }
+
+    #[test]
+    fn simple_circuit_estimate_size_test() {
+        let code = &*::std::fs::read_to_string("test_programs/simple.zk").unwrap();
+        let estimate = ASTParser::estimate_size::<FrLocal>(code).unwrap();
+        let parsed = ASTParser::to_circuit::<FrLocal>(code).unwrap();
+
+        // Without any hashes the estimate is exact
+        assert_eq!(estimate.constraints, parsed.circuit.sub_circuits().count());
+        assert_eq!(estimate.wires, parsed.circuit.num_wires());
+    }
+
+    #[test]
+    fn hash_builtin_test() {
+        use groth16::circuit::{Keccak256Hash, MiMCHash, Sha256Hash};
+
+        let code = &*::std::fs::read_to_string("test_programs/hash.zk").unwrap();
+
+        let keccak = ASTParser::to_circuit_with_hash::<FrLocal, _>(code, &Keccak256Hash).unwrap();
+        let sha256 = ASTParser::to_circuit_with_hash::<FrLocal, _>(code, &Sha256Hash).unwrap();
+        let mimc =
+            ASTParser::to_circuit_with_hash::<FrLocal, _>(code, &MiMCHash::new(10)).unwrap();
+
+        // MiMC costs two gates per round and one to feed forward for each of
+        // the 8 inputs
+        assert_eq!(mimc.circuit.sub_circuits().count(), 8 * (2 * 10 + 1));
+        assert!(keccak.circuit.sub_circuits().count() > mimc.circuit.sub_circuits().count());
+        assert!(sha256.circuit.sub_circuits().count() > mimc.circuit.sub_circuits().count());
+
+        // Every addition of SHA-256 leaves its range checks for the verifier
+        assert!(!sha256.circuit.zero_checks().is_empty());
+
+        // Without a HashFamily the builtin cannot be compiled
+        assert!(ASTParser::to_circuit::<FrLocal>(code).is_err());
+    }
+
+    #[test]
+    fn gadget_or_test() {
+        use groth16::circuit::{Gadget, OrGadget};
+
+        let mut circuit = Circuit::<FrLocal>::new();
+        let wires = OrGadget.synthesize(&mut circuit);
+        assert_eq!(wires.inputs.len(), 2);
+        assert_eq!(wires.outputs.len(), 1);
+
+        let mut verify_wires = circuit.bit_check(&wires.inputs);
+        verify_wires.extend(wires.outputs.iter());
+
+        let mut instance = CircuitInstance::new(circuit, verify_wires, wires.inputs, |w| {
+            FrLocal::from(w.inner_id() + 1)
+        });
+
+        let qap: QAP<CoefficientPoly<FrLocal>> = QAP::from(DummyRep::from(&instance));
+        let weights = instance.weights(vec![FrLocal::from(1), FrLocal::from(0)]);
+
+        let (sigmag1, sigmag2) = groth16::setup(&qap);
+        let proof = groth16::prove(&qap, (&sigmag1, &sigmag2), &weights);
+
+        assert!(groth16::verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
             &[FrLocal::from(0), FrLocal::from(0), FrLocal::from(1)],
-            proof
+            &proof
+        ));
+    }
+
+    #[test]
+    fn sort_prove_verify_test() {
+        let mut circuit = Circuit::<FrLocal>::new();
+        let inputs = (0..3).map(|_| circuit.new_wire()).collect::<Vec<_>>();
+        let sorted = circuit.new_sort(&inputs, 4);
+
+        // The range checks are only enforced if they are verified to be zero
+        let zero_checks = circuit.zero_checks().len();
+        let mut verify_wires = sorted.clone();
+        verify_wires.extend(circuit.zero_checks().iter());
+
+        let mut instance = CircuitInstance::new(circuit, verify_wires, inputs, |w| {
+            FrLocal::from(w.inner_id() + 1)
+        });
+
+        let qap: QAP<CoefficientPoly<FrLocal>> = QAP::from(DummyRep::from(&instance));
+        let weights = instance.weights(
+            [9usize, 2, 9]
+                .iter()
+                .map(|&x| FrLocal::from(x))
+                .collect(),
+        );
+
+        let (sigmag1, sigmag2) = groth16::setup(&qap);
+        let proof = groth16::prove(&qap, (&sigmag1, &sigmag2), &weights);
+
+        let mut expected = [2usize, 9, 9]
+            .iter()
+            .map(|&x| FrLocal::from(x))
+            .collect::<Vec<_>>();
+        expected.extend(vec![FrLocal::from(0); zero_checks]);
+        assert!(groth16::verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
+            &expected,
+            &proof
+        ));
+
+        // The same inputs in a different order are not sorted
+        expected.swap(0, 1);
+        assert!(!groth16::verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
+            &expected,
+            &proof
         ));
     }
 
@@ -200,9 +1136,88 @@ mod tests {
         correct_output_vals.append(&mut bit_check_vals);
 
         assert!(groth16::verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
-            (sigmag1, sigmag2),
+            (&sigmag1, &sigmag2),
             &correct_output_vals,
-            proof
+            &proof
         ));
     }
+
+    #[ignore]
+    #[test]
+    fn circuit_keccak_preimage() {
+        const LEN: usize = 20;
+        let keccak_input: [u8; LEN] = [63; LEN];
+
+        let mut circuit = Circuit::<FrLocal>::new();
+        let (circuit_input, hash) = circuit.new_keccak_preimage(LEN);
+        let mut verify_wires = flatten_word8(&hash);
+        verify_wires.extend_from_slice(circuit.zero_checks());
+
+        let mut instance =
+            CircuitInstance::new(circuit, verify_wires, flatten_word8(&circuit_input), |w| {
+                FrLocal::from(w.inner_id() + 1)
+            });
+
+        let qap: QAP<CoefficientPoly<FrLocal>> = QAP::from(DummyRep::from(&instance));
+        let weights = instance.weights(to_field_bits(&keccak_input));
+
+        let (sigmag1, sigmag2) = groth16::setup(&qap);
+        let proof = groth16::prove(&qap, (&sigmag1, &sigmag2), &weights);
+
+        let mut inputs: Vec<FrLocal> = to_field_bits(&keccak256(&keccak_input));
+        let mut bit_check_vals: Vec<FrLocal> = to_field_bits(&[0; LEN]);
+        inputs.append(&mut bit_check_vals);
+
+        assert!(groth16::verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
+            (&sigmag1, &sigmag2),
+            &inputs,
+            &proof
+        ));
+    }
+
+    #[ignore]
+    #[test]
+    fn circuit_blake2s_fixed_inputs() {
+        let inputs: [&[u8]; 3] = [&[], b"abc", &[63; 70]];
+
+        for input in inputs.iter() {
+            let mut expected = [0; 32];
+            expected.copy_from_slice(&Blake2s::digest(input));
+
+            let mut circuit = Circuit::<FrLocal>::new();
+            let circuit_input = circuit.new_word8_vec(input.len());
+            let hash = circuit.blake2s_stream(&circuit_input);
+
+            // The range checks of the additions hold the hash together, so
+            // they are verified as well as the bit checks of the input
+            let mut bit_check = circuit.bit_check(flatten_word8(&circuit_input));
+            let mut verify_wires = flatten_word8(&hash);
+            verify_wires.append(&mut bit_check);
+            let zero_checks = circuit.zero_checks().len();
+            verify_wires.extend_from_slice(circuit.zero_checks());
+
+            let mut instance =
+                CircuitInstance::new(circuit, verify_wires, flatten_word8(&circuit_input), |w| {
+                    FrLocal::from(w.inner_id() + 1)
+                });
+
+            let qap: QAP<CoefficientPoly<FrLocal>> = QAP::from(DummyRep::from(&instance));
+            let assignments = to_field_bits(*input);
+            let weights = instance.try_weights(assignments).unwrap();
+
+            let (sigmag1, sigmag2) = groth16::setup(&qap);
+            let proof = groth16::prove(&qap, (&sigmag1, &sigmag2), &weights);
+
+            let mut correct_output_vals: Vec<FrLocal> = to_field_bits(&expected);
+            let mut bit_check_vals: Vec<FrLocal> = to_field_bits(&vec![0u8; input.len()]);
+            correct_output_vals.append(&mut bit_check_vals);
+            correct_output_vals.extend(vec![FrLocal::from(0); zero_checks]);
+
+            assert!(groth16::verify::<CoefficientPoly<FrLocal>, _, _, _, _>(
+                (&sigmag1, &sigmag2),
+                &correct_output_vals,
+                &proof
+            ));
+        }
+    }
 }